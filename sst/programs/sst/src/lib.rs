@@ -1,12 +1,172 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::program::{invoke_signed, set_return_data};
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::{create_metadata_accounts_v3, CreateMetadataAccountsV3, Metadata};
+use anchor_spl::token::{self, Approve, Burn, FreezeAccount, Mint, MintTo, ThawAccount, Token, TokenAccount, Transfer};
+use switchboard_on_demand::accounts::{PullFeedAccountData, RandomnessAccountData};
+
+mod math;
+use math::{mul_div_ceil, mul_div_floor};
 
 declare_id!("FGbGLGj7h1sTpfescPQvDteMj8mQpe9HNWd7V1xvyMnM");
 
 /// Minimum duration (in seconds) a non-locked stake must remain before unstaking without penalty (7 days)
 const MIN_NON_LOCKED_STAKE_DURATION: i64 = 7 * 24 * 60 * 60;
-/// VIP threshold: 100,000 SST (assuming 6 decimals)
-const VIP_THRESHOLD: u64 = 100_000 * 1_000_000;
+/// Delay (in seconds) before a changed withdrawal address takes effect (2 days)
+const WITHDRAWAL_ADDRESS_CHANGE_DELAY: i64 = 2 * 24 * 60 * 60;
+/// Delay (in seconds) before a two-factor bypass lets the owner act without the second signer (7 days)
+const TWO_FACTOR_BYPASS_DELAY: i64 = 7 * 24 * 60 * 60;
+/// Voting period (in seconds) after a proposal is created (3 days)
+const PROPOSAL_VOTING_PERIOD: i64 = 3 * 24 * 60 * 60;
+/// Voting period (in seconds) of a proposal fast-tracked as an emergency (1 day)
+const EMERGENCY_VOTING_PERIOD: i64 = 24 * 60 * 60;
+/// Voting left open after a proposal is fast-tracked, however old it already is (6 hours)
+const EMERGENCY_MIN_VOTING_WINDOW: i64 = 6 * 60 * 60;
+/// Execution delay of a passed emergency proposal, leaving time for a veto or an exit (6 hours)
+const EMERGENCY_EXECUTION_DELAY: i64 = 6 * 60 * 60;
+/// Share of the decided votes that must be in favour for an emergency proposal to pass.
+const EMERGENCY_APPROVAL_THRESHOLD_BPS: u64 = 8_000;
+/// Number of pre-deposit voting checkpoints retained per position.
+const VOTING_CHECKPOINTS: usize = 4;
+/// Maximum delegators whose power a delegate can cast in one `vote_proposal`.
+const MAX_DELEGATORS_PER_VOTE: usize = 8;
+/// Longest vote-escrow lock; a lock this long carries one veSST per locked SST.
+const MAX_VE_LOCK_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+/// Default execution delay (in seconds) after a proposal passes, during which it may be vetoed and
+/// dissenters may exit (2 days)
+const DEFAULT_EXECUTION_DELAY: i64 = 2 * 24 * 60 * 60;
+/// Time (in seconds) a lottery winner has to claim the prize before it rolls over (7 days)
+const LOTTERY_CLAIM_WINDOW: i64 = 7 * 24 * 60 * 60;
+/// Inactivity (in seconds) after which an empty position may be flagged as dormant (180 days)
+const DORMANCY_PERIOD: i64 = 180 * 24 * 60 * 60;
+/// Notice period (in seconds) between flagging a dormant position and closing it (30 days)
+const DORMANT_CLOSURE_NOTICE: i64 = 30 * 24 * 60 * 60;
+/// Initial VIP threshold: 100,000 SST (assuming 6 decimals); governance can change it.
+const DEFAULT_VIP_THRESHOLD: u64 = 100_000 * 1_000_000;
+/// Seconds in a 365-day year, used to annualize reward rates.
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+/// Yearly LP boost, in bps of the position's weighted LP deposit (5%).
+const LP_BOOST_APR_BPS: u64 = 500;
+/// Largest debt (principal plus interest) a flexible position may take on, in bps of its stake,
+/// until governance configures collateral factors.
+const DEFAULT_COLLATERAL_FACTOR_BPS: u64 = 4_000;
+/// Loan-to-value above a position's collateral factor at which it can be liquidated.
+const LIQUIDATION_LTV_MARGIN_BPS: u64 = 1_500;
+/// Upper bound on the borrow origination fee.
+const MAX_BORROW_ORIGINATION_FEE_BPS: u64 = 500;
+/// Share of a position's debt a single liquidation may repay.
+const LIQUIDATION_CLOSE_FACTOR_BPS: u64 = 5_000;
+/// Extra stake a liquidator receives on top of the debt repaid.
+const LIQUIDATION_BONUS_BPS: u64 = 500;
+/// Upper bound on the borrow rate curve's maximum annual rate (1,000%).
+const MAX_BORROW_RATE_BPS: u64 = 100_000;
+/// Fixed-point scale of SST prices read from the USDC market's price feed (USDC per SST).
+const PRICE_SCALE: u128 = 1_000_000_000;
+/// Borrow index value of 1.0 in Q64.64; the index starts here and only grows.
+const BORROW_INDEX_ONE: u128 = 1 << 64;
+/// Basis-point denominator (100% = 10,000 bps).
+const BPS_DENOMINATOR: u64 = 10_000;
+/// Share of the votes cast that must be in favour for a proposal to pass, unless governance overrides it.
+const DEFAULT_APPROVAL_THRESHOLD_BPS: u64 = 5_000;
+/// Lock periods (in seconds) accepted for time-locked staking: 30, 90 and 180 days.
+const ALLOWED_LOCK_PERIODS: [u64; 3] = [
+    30 * 24 * 60 * 60,
+    90 * 24 * 60 * 60,
+    180 * 24 * 60 * 60,
+];
+/// Upper bound on a lock tier's reward multiplier (5x, in basis points).
+const MAX_LOCK_REWARD_MULTIPLIER_BPS: u64 = 50_000;
+/// Maximum number of tranches in a single lock ladder.
+const MAX_LADDER_TRANCHES: usize = 6;
+/// Maximum number of parameter changes carried by a single proposal.
+const MAX_PARAMETER_CHANGES: usize = 4;
+/// Bounds on the instructions a proposal can carry for `execute_proposal`.
+const MAX_PROPOSAL_INSTRUCTIONS: u8 = 4;
+const MAX_PROPOSAL_INSTRUCTION_ACCOUNTS: usize = 16;
+const MAX_PROPOSAL_INSTRUCTION_DATA: usize = 512;
+/// Seed of the PDA that signs the CPIs of executed proposals.
+const GOVERNANCE_SIGNER_SEED: &[u8] = b"governance_signer";
+/// Maximum programs on the flash-loan callback allowlist.
+const MAX_FLASH_LOAN_PROGRAMS: usize = 16;
+/// Maximum members of the security council.
+const MAX_COUNCIL_MEMBERS: usize = 7;
+/// Votes cast (in bps of total staked) above which a proposal can no longer be cancelled.
+const CANCEL_VOTE_LIMIT_BPS: u64 = 100;
+/// Seconds after the execution delay during which a passed proposal can still be executed (14 days)
+const PROPOSAL_EXECUTION_WINDOW: i64 = 14 * 24 * 60 * 60;
+/// Hard cap (in bytes) on proposal description URIs, bounded by the space reserved in `Proposal`.
+const MAX_PROPOSAL_URI_LEN: usize = 128;
+/// Bounds on the options of a multi-option proposal.
+const MAX_PROPOSAL_OPTIONS: usize = 8;
+const MAX_PROPOSAL_OPTION_LEN: usize = 32;
+/// Maximum length of the forum URI prefix in the description policy.
+const MAX_FORUM_URI_PREFIX_LEN: usize = 64;
+/// Length of a hex-encoded forum post hash (32 bytes).
+const FORUM_POST_HASH_LEN: usize = 64;
+/// Maximum number of banned terms in the description policy.
+const MAX_BANNED_TERMS: usize = 8;
+/// Maximum length of a single banned term.
+const MAX_BANNED_TERM_LEN: usize = 16;
+/// Maximum number of step-downs in the reward emission schedule.
+const MAX_EMISSION_STEPS: usize = 8;
+/// Length (in seconds) of a reward epoch; deposits start earning at the next epoch boundary (1 day)
+const REWARD_EPOCH_DURATION: i64 = 24 * 60 * 60;
+/// Number of epoch-boundary accumulator checkpoints retained in `ProtocolStats`.
+const REWARD_CHECKPOINT_HISTORY: usize = 16;
+/// Maximum number of positions tracked by a wallet's position index.
+const MAX_INDEXED_POSITIONS: usize = 16;
+/// Maximum number of positions credited by a single `distribute_bonus` call.
+const MAX_BONUS_RECIPIENTS: usize = 10;
+/// Minimum seconds between two compounds of the same position (1 hour).
+const MIN_COMPOUND_INTERVAL: i64 = 60 * 60;
+/// Maximum number of positions processed by a single `claim_all` call.
+const MAX_CLAIM_ALL_POSITIONS: usize = 8;
+/// Maximum number of swap-on-claim payout routes.
+const MAX_PAYOUT_ROUTES: usize = 8;
+/// Maximum number of approved LP mints.
+const MAX_LP_MINTS: usize = 8;
+/// Upper bound on an LP mint's boost weight (3x).
+const MAX_LP_BOOST_WEIGHT_BPS: u64 = 30_000;
+/// Maximum number of partner reward tokens in the registry.
+const MAX_REWARD_TOKENS: usize = 4;
+/// Period (in seconds) over which escrowed rewards vest when reward vesting is enabled (30 days)
+const REWARD_VESTING_PERIOD: i64 = 30 * 24 * 60 * 60;
+/// Vesting tranches kept per escrow; further claims merge into the newest one.
+const REWARD_VESTING_TRANCHES: usize = 8;
+/// Maximum number of vault token accounts moved by a single `migrate_vault_batch` call.
+const MAX_VAULT_MIGRATION_BATCH: usize = 8;
+/// Current `StakeInfo` layout version; bump whenever fields are appended.
+const STAKE_INFO_VERSION: u8 = 30;
+/// Boost campaigns a single position can hold weight in at once.
+const MAX_BOOST_ENROLLMENTS: usize = 4;
+/// Latest fee-discount curve version; 1 is the original curve.
+const LATEST_FEE_DISCOUNT_VERSION: u8 = 2;
+/// Achievement bits recorded in `StakeInfo::achievements`.
+const ACHIEVEMENT_FIRST_STAKE: u64 = 1 << 0;
+const ACHIEVEMENT_STAKED_100K: u64 = 1 << 1;
+const ACHIEVEMENT_ONE_YEAR_STAKED: u64 = 1 << 2;
+const ACHIEVEMENT_TEN_VOTES: u64 = 1 << 3;
+/// Lifetime stake for the 100k milestone: 100,000 SST (assuming 6 decimals)
+const ACHIEVEMENT_STAKE_MILESTONE: u64 = 100_000 * 1_000_000;
+/// Proposals voted on for the governance milestone.
+const ACHIEVEMENT_VOTES_MILESTONE: u64 = 10;
+/// Metadata symbol of position receipt NFTs.
+const RECEIPT_SYMBOL: &str = "SSTP";
+/// Stake earning one loyalty point per day at a 1x lock multiplier (1 SST for one day).
+const LOYALTY_TOKEN_DAY: u64 = 1_000_000 * 24 * 60 * 60;
+/// Number of ranked participants paid when a season is finalized.
+const SEASON_WINNERS: usize = 5;
+/// Season score per whole SST held for one second.
+const SEASON_SCORE_UNIT: u64 = 1_000_000;
+/// Time after a season ends during which late score syncs still land before it can be finalized.
+const SEASON_FINALIZE_DELAY: i64 = 24 * 60 * 60;
+/// Upper bound on the referral share of a claim (20%).
+const MAX_REFERRAL_BPS: u64 = 2_000;
 
 #[program]
 pub mod sst {
@@ -14,9 +174,13 @@ pub mod sst {
 
     /// Standard staking instruction (no lock period).
     pub fn stake(ctx: Context<StakeAccounts>, amount: u64) -> Result<()> {
+        require!(amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
         let stake_info = &mut ctx.accounts.stake_info;
         let clock = Clock::get()?;
         require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+        ensure_no_incident(&ctx.accounts.config)?;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let from_state = stake_info.state(clock.unix_timestamp);
         stake_info.locked = true;
 
         let cpi_accounts = Transfer {
@@ -28,27 +192,43 @@ pub mod sst {
         token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
 
         stake_info.staker = ctx.accounts.staker.key();
+        stake_info.version = STAKE_INFO_VERSION;
+        record_referral(ctx.accounts.referral_account.as_deref_mut(), stake_info)?;
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        accrue_loyalty(&mut ctx.accounts.loyalty_account, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
         stake_info.amount = stake_info.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_staked = stake_info.lifetime_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, clock.unix_timestamp);
         stake_info.last_staked_time = clock.unix_timestamp;
         stake_info.lock_period = 0;
         stake_info.locked_until = clock.unix_timestamp;
         stake_info.borrowed_amount = 0;
         stake_info.locked = false;
         stake_info.auto_restake = false;
-        Ok(())
+        stake_info.receipt_mint = ctx.accounts.receipt_mint.key();
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_pending_stake(protocol_stats, stake_info, amount, clock.unix_timestamp)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        record_integration_deposit(ctx.accounts.integration_stats.as_deref_mut(), stake_info.staker, amount, clock.unix_timestamp)?;
+        enforce_stake_caps(&ctx.accounts.config, stake_info.amount, protocol_stats.total_staked)?;
+        mint_position_receipt(&ctx.accounts, ctx.bumps.vault_authority, amount, 0)
     }
 
     /// Staking instruction with a lock period (30, 90, or 180 days).
     pub fn stake_with_lock(ctx: Context<StakeAccounts>, amount: u64, lock_period: u64) -> Result<()> {
-        let allowed_periods: Vec<u64> = vec![
-            30 * 24 * 60 * 60,
-            90 * 24 * 60 * 60,
-            180 * 24 * 60 * 60,
-        ];
-        require!(allowed_periods.contains(&lock_period), ErrorCode::InvalidLockPeriod);
+        require!(ALLOWED_LOCK_PERIODS.contains(&lock_period), ErrorCode::InvalidLockPeriod);
+        require!(amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
         let stake_info = &mut ctx.accounts.stake_info;
         let clock = Clock::get()?;
         require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+        ensure_no_incident(&ctx.accounts.config)?;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let from_state = stake_info.state(clock.unix_timestamp);
         stake_info.locked = true;
 
         let cpi_accounts = Transfer {
@@ -60,34 +240,97 @@ pub mod sst {
         token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
 
         stake_info.staker = ctx.accounts.staker.key();
+        stake_info.version = STAKE_INFO_VERSION;
+        record_referral(ctx.accounts.referral_account.as_deref_mut(), stake_info)?;
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        accrue_loyalty(&mut ctx.accounts.loyalty_account, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
         stake_info.amount = stake_info.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_staked = stake_info.lifetime_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, clock.unix_timestamp);
         stake_info.last_staked_time = clock.unix_timestamp;
         stake_info.lock_period = lock_period;
         stake_info.locked_until = clock.unix_timestamp.checked_add(lock_period as i64).ok_or(ErrorCode::Overflow)?;
+        stake_info.vesting_principal = stake_info.amount;
+        stake_info.vested_withdrawn = 0;
         stake_info.borrowed_amount = 0;
         stake_info.locked = false;
         stake_info.auto_restake = false;
-        Ok(())
+        stake_info.receipt_mint = ctx.accounts.receipt_mint.key();
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_pending_stake(protocol_stats, stake_info, amount, clock.unix_timestamp)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        record_integration_deposit(ctx.accounts.integration_stats.as_deref_mut(), stake_info.staker, amount, clock.unix_timestamp)?;
+        enforce_stake_caps(&ctx.accounts.config, stake_info.amount, protocol_stats.total_staked)?;
+        mint_position_receipt(&ctx.accounts, ctx.bumps.vault_authority, amount, lock_period)
     }
 
     /// Unstake instruction with progressive (linear vesting) unlocking.
+    /// A remainder below the configured minimum stake is swept out with the withdrawal.
+    /// A full unstake burns the position's receipt NFT.
     pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let co_signer = ctx.accounts.second_signer.as_ref().map(|signer| signer.key());
+        ensure_no_incident(&ctx.accounts.config)?;
         let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
         let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
         require!(stake_info.amount >= amount, ErrorCode::InsufficientStakedAmount);
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let amount = apply_dust_sweep(&ctx.accounts.config, stake_info.amount, amount)?;
+        require!(
+            stake_info.second_factor_satisfied(amount, co_signer, clock.unix_timestamp),
+            ErrorCode::SecondSignerRequired
+        );
+        require!(
+            stake_info.amount - amount >= stake_info.unvested_gift_amount(clock.unix_timestamp),
+            ErrorCode::GiftStillVesting
+        );
+        stake_info.accrue_borrow_interest(ctx.accounts.protocol_stats.borrow_index)?;
+        let usdc = usdc_loan_terms(
+            stake_info,
+            &ctx.accounts.config,
+            ctx.accounts.usdc_market.as_deref(),
+            ctx.accounts.price_feed.as_deref(),
+        )?
+        .map(|(price, params)| (price, params.max_ltv_bps));
+        stake_info.ensure_collateralized(&ctx.accounts.config, stake_info.amount - amount, clock.unix_timestamp, usdc)?;
+        accrue_loyalty(&mut ctx.accounts.loyalty_account, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let remaining = stake_info.amount - amount;
+        record_season_score(
+            ctx.accounts.season.as_mut(),
+            ctx.accounts.season_score.as_mut(),
+            stake_info,
+            remaining,
+            clock.unix_timestamp,
+        )?;
 
-        if stake_info.lock_period > 0 {
-            let time_elapsed = clock.unix_timestamp
-                .checked_sub(stake_info.last_staked_time)
-                .ok_or(ErrorCode::Underflow)?;
-            let unlock_ratio = if time_elapsed >= stake_info.lock_period as i64 { 
-                1.0 
-            } else {
-                time_elapsed as f64 / stake_info.lock_period as f64
-            };
-            let unlocked_amount = (stake_info.amount as f64 * unlock_ratio).floor() as u64;
-            require!(amount <= unlocked_amount, ErrorCode::TokensLocked);
-            let seeds = &[b"vault".as_ref()];
+        let amount_to_transfer = if stake_info.lock_period > 0 {
+            require!(amount <= stake_info.unlocked_amount(clock.unix_timestamp), ErrorCode::TokensLocked);
+            stake_info.vested_withdrawn = stake_info.vested_withdrawn.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+            amount
+        } else if clock.unix_timestamp - stake_info.last_staked_time < MIN_NON_LOCKED_STAKE_DURATION {
+            let penalty = mul_div_ceil(amount as u128, 2, 100)?;
+            stake_info.lifetime_penalties = stake_info.lifetime_penalties.checked_add(penalty).ok_or(ErrorCode::Overflow)?;
+            msg!("Early unstake penalty applied: {} tokens withheld", penalty);
+            amount.checked_sub(penalty).ok_or(ErrorCode::Underflow)?
+        } else {
+            amount
+        };
+
+        if ctx.accounts.staker_token_account.is_frozen() {
+            // A frozen destination must not block settlement: escrow the payout until unfrozen.
+            stake_info.pending_withdrawal = stake_info.pending_withdrawal
+                .checked_add(amount_to_transfer)
+                .ok_or(ErrorCode::Overflow)?;
+            stake_info.pending_withdrawal_account = ctx.accounts.staker_token_account.key();
+            msg!("Staker token account frozen: {} tokens held as pending withdrawal", amount_to_transfer);
+        } else {
+            let seeds = ctx.accounts.config.vault_signer_seeds();
             let signer = &[&seeds[..]];
             let cpi_accounts = Transfer {
                 from: ctx.accounts.vault_token_account.to_account_info(),
@@ -95,52 +338,108 @@ pub mod sst {
                 authority: ctx.accounts.vault_authority.to_account_info(),
             };
             let cpi_program = ctx.accounts.token_program.to_account_info();
-            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
-        } else {
-            if clock.unix_timestamp - stake_info.last_staked_time < MIN_NON_LOCKED_STAKE_DURATION {
-                let penalty = amount.checked_mul(2).ok_or(ErrorCode::Overflow)?
-                    .checked_div(100).ok_or(ErrorCode::Underflow)?;
-                msg!("Early unstake penalty applied: {} tokens withheld", penalty);
-                let amount_to_transfer = amount.checked_sub(penalty).ok_or(ErrorCode::Underflow)?;
-                let seeds = &[b"vault".as_ref()];
-                let signer = &[&seeds[..]];
-                let cpi_accounts = Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
-                    to: ctx.accounts.staker_token_account.to_account_info(),
-                    authority: ctx.accounts.vault_authority.to_account_info(),
-                };
-                let cpi_program = ctx.accounts.token_program.to_account_info();
-                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount_to_transfer)?;
-            } else {
-                let seeds = &[b"vault".as_ref()];
-                let signer = &[&seeds[..]];
-                let cpi_accounts = Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
-                    to: ctx.accounts.staker_token_account.to_account_info(),
-                    authority: ctx.accounts.vault_authority.to_account_info(),
-                };
-                let cpi_program = ctx.accounts.token_program.to_account_info();
-                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
-            }
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount_to_transfer)?;
         }
         stake_info.amount = stake_info.amount.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+
+        if stake_info.amount == 0 {
+            burn_position_receipt(
+                stake_info,
+                ctx.accounts.receipt_mint.as_ref().map(|mint| mint.to_account_info()),
+                ctx.accounts.receipt_token_account.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.receipt_authority.as_ref().map(|authority| authority.to_account_info()),
+                Some(ctx.accounts.staker.to_account_info()),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.program_id,
+            )?;
+        }
+
+        let penalty = amount - amount_to_transfer;
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        // The penalty stays in the stake vault until `fund_lottery` splits it.
+        protocol_stats.penalties_withheld = protocol_stats.penalties_withheld
+            .checked_add(penalty)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Create the early-unstake penalty bonus pool (config authority only).
+    pub fn initialize_bonus_pool(ctx: Context<InitializeBonusPool>) -> Result<()> {
+        let bonus_pool = &mut ctx.accounts.bonus_pool;
+        bonus_pool.total_collected = 0;
+        bonus_pool.total_distributed = 0;
+        bonus_pool.undistributed = 0;
+        msg!("Bonus pool initialized");
         Ok(())
     }
 
+    /// Streaming withdrawal for vesting positions: withdraws everything vested so far that has
+    /// not already been withdrawn, going through the same checks as `unstake`.
+    pub fn claim_unlocked(ctx: Context<Unstake>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let clock = Clock::get()?;
+        let stake_info = &ctx.accounts.stake_info;
+        require!(stake_info.lock_period > 0, ErrorCode::PositionNotLocked);
+        let unlocked = stake_info.unlocked_amount(clock.unix_timestamp);
+        require!(unlocked > 0, ErrorCode::TokensLocked);
+        msg!("Claiming {} unlocked tokens", unlocked);
+        unstake(ctx, unlocked)
+    }
+
     /// Execute trade instruction: applies dynamic fee discounts based on staking, VIP boost,
     /// duration bonus, and extra bonus for ultra-fast execution.
+    /// If an active fee-tier lease is supplied, the lessor's position determines the fee tier.
     pub fn execute_trade(ctx: Context<ExecuteTrade>, order_execution_time: u64) -> Result<()> {
-        let stake_info = &mut ctx.accounts.stake_info;
+        ensure_no_incident(&ctx.accounts.config)?;
         let clock = Clock::get()?;
+        let (tier_amount, tier_lock_period, tier_last_staked_time) =
+            match (&ctx.accounts.fee_lease, &ctx.accounts.lessor_stake_info) {
+                (Some(fee_lease), Some(lessor_stake_info)) => {
+                    require!(fee_lease.lessee == ctx.accounts.stake_info.staker, ErrorCode::InvalidLease);
+                    require!(lessor_stake_info.staker == fee_lease.lessor, ErrorCode::InvalidLease);
+                    require!(fee_lease.is_active(clock.unix_timestamp), ErrorCode::LeaseInactive);
+                    msg!("Using fee tier leased from {}", fee_lease.lessor);
+                    (lessor_stake_info.amount, lessor_stake_info.lock_period, lessor_stake_info.last_staked_time)
+                }
+                (None, None) => (
+                    ctx.accounts.stake_info.amount,
+                    ctx.accounts.stake_info.lock_period,
+                    ctx.accounts.stake_info.last_staked_time,
+                ),
+                _ => return err!(ErrorCode::InvalidLease),
+            };
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.trade_count = stake_info.trade_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
         let staking_duration = clock.unix_timestamp
-            .checked_sub(stake_info.last_staked_time)
+            .checked_sub(tier_last_staked_time)
             .unwrap_or(0);
-        let fee_discount = if stake_info.lock_period > 0 {
-            calculate_fee_discount(stake_info.amount, staking_duration)
+        let config = &ctx.accounts.config;
+        let fee_discount = if tier_lock_period > 0 {
+            fee_discount_for_version(config.fee_discount_version, tier_amount, staking_duration)
         } else {
             0
         };
-        let vip_mult = vip_multiplier(stake_info.amount);
+        if config.shadow_fee_discount_version > 0 {
+            // Shadow mode: evaluate the candidate curve and report it, but never apply it.
+            let shadow_discount = if tier_lock_period > 0 {
+                fee_discount_for_version(config.shadow_fee_discount_version, tier_amount, staking_duration)
+            } else {
+                0
+            };
+            emit!(FeeDiscountComputed {
+                staker: stake_info.staker,
+                active_version: config.fee_discount_version,
+                active_discount: fee_discount,
+                shadow_version: config.shadow_fee_discount_version,
+                shadow_discount,
+            });
+        }
+        let vip_mult = vip_multiplier(tier_amount);
         let mut adjusted_fee_discount = fee_discount * vip_mult / 100;
         msg!("Base fee discount: {}%, VIP multiplier: {}%", fee_discount, vip_mult);
 
@@ -156,14 +455,21 @@ pub mod sst {
         adjusted_fee_discount = adjusted_fee_discount.checked_add(duration_priority_bonus).ok_or(ErrorCode::Overflow)?;
         msg!("Duration priority bonus: {}%", duration_priority_bonus);
 
-        if stake_info.amount >= VIP_THRESHOLD {
+        if is_vip(&ctx.accounts.config, tier_amount, ctx.accounts.price_feed.as_deref())? {
             adjusted_fee_discount = adjusted_fee_discount.checked_add(10).ok_or(ErrorCode::Overflow)?;
             msg!("Institutional VIP boost applied.");
         }
 
+        if let Some(loyalty_account) = &ctx.accounts.loyalty_account {
+            let loyalty_discount = loyalty_fee_discount(loyalty_account.points);
+            adjusted_fee_discount = adjusted_fee_discount.checked_add(loyalty_discount).ok_or(ErrorCode::Overflow)?;
+            msg!("Loyalty discount: {}% ({} points)", loyalty_discount, loyalty_account.points);
+        }
+
         if order_execution_time <= 50 {
             msg!("Ultra-fast execution (<= 50ms) achieved: extra bonus applied.");
             adjusted_fee_discount = adjusted_fee_discount.checked_add(5).ok_or(ErrorCode::Overflow)?;
+            stake_info.checkpoint_voting_power(clock.unix_timestamp);
             stake_info.amount = stake_info.amount.checked_add(20).ok_or(ErrorCode::Overflow)?;
         } else if order_execution_time <= 100 {
             msg!("Trade executed within 100ms: bonus incentives applied.");
@@ -175,22 +481,53 @@ pub mod sst {
     }
 
     /// Claim rewards instruction with auto-compounding and progressive APY scaling.
-    pub fn claim_rewards(ctx: Context<ClaimRewards>, liquidity_provided: u64) -> Result<()> {
+    /// May be called by the position's operator; operator claims can only pay out to the staker.
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
         let stake_info = &mut ctx.accounts.stake_info;
         let clock = Clock::get()?;
-        let staking_duration = clock.unix_timestamp
-            .checked_sub(stake_info.last_staked_time)
-            .unwrap_or(0);
-        let months = staking_duration / (30 * 24 * 60 * 60);
-        let progressive_bonus = months * 10;
-        let base_reward: i64 = 100 + progressive_bonus;
-        let lp_boost: u64 = lp_reward_boost(liquidity_provided);
-        let total_reward: i64 = base_reward.checked_add(lp_boost.try_into().unwrap()).ok_or(ErrorCode::Overflow)?;
+        if ctx.accounts.authority.key() != stake_info.staker
+            && stake_info.reward_beneficiary == Pubkey::default()
+            && stake_info.withdrawal_address == Pubkey::default()
+        {
+            require!(
+                ctx.accounts.staker_token_account.owner == stake_info.staker,
+                ErrorCode::InvalidTokenAccountOwner
+            );
+        }
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        accrue_loyalty(&mut ctx.accounts.loyalty_account, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let (requested_base, requested_boost, _) = calculate_claim_reward(stake_info.accrued_rewards, stake_info.take_lp_boost(clock.unix_timestamp)?)?;
+        let (base_reward, lp_boost, total_reward) = cap_claim(
+            &mut ctx.accounts.protocol_stats,
+            &ctx.accounts.config,
+            stake_info,
+            requested_base,
+            requested_boost,
+            ctx.accounts.reward_vault.amount,
+            clock.unix_timestamp,
+        )?;
+        emit!(RewardsClaimed {
+            staker: stake_info.staker,
+            base_reward,
+            lp_boost,
+            total_reward,
+            compounded: stake_info.auto_restake,
+        });
+        stake_info.lifetime_rewards = stake_info.lifetime_rewards.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
         if stake_info.auto_restake {
-            stake_info.amount = stake_info.amount.checked_add(total_reward.try_into().unwrap()).ok_or(ErrorCode::Overflow)?;
+            stake_info.checkpoint_voting_power(clock.unix_timestamp);
+            stake_info.amount = stake_info.amount.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
             msg!("Rewards auto-compounded: {} tokens added (Base: {}, LP Boost: {})", total_reward, base_reward, lp_boost);
+        } else if ctx.accounts.config.reward_vesting_enabled {
+            let reward_vesting = ctx.accounts.reward_vesting.as_mut().ok_or(ErrorCode::RewardVestingRequired)?;
+            reward_vesting.apply_incident_offset(&ctx.accounts.config);
+            reward_vesting.deposit(total_reward, clock.unix_timestamp)?;
+            msg!("Rewards claimed into vesting: {} tokens vest until {}", total_reward, reward_vesting.end);
         } else {
-            let seeds = &[b"vault".as_ref()];
+            require!(stake_info.claim_as_mint == Pubkey::default(), ErrorCode::ClaimAsModeActive);
+            let seeds = ctx.accounts.config.vault_signer_seeds();
             let signer = &[&seeds[..]];
             let cpi_accounts = Transfer {
                 from: ctx.accounts.reward_vault.to_account_info(),
@@ -198,445 +535,12638 @@ pub mod sst {
                 authority: ctx.accounts.vault_authority.to_account_info(),
             };
             let cpi_program = ctx.accounts.token_program.to_account_info();
-            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_reward.try_into().unwrap())?;
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_reward)?;
             msg!("Rewards claimed: {} tokens transferred", total_reward);
         }
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_distributed = protocol_stats.total_rewards_distributed
+            .checked_add(total_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        if stake_info.auto_restake {
+            protocol_stats.total_staked = protocol_stats.total_staked.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        }
+
+        // Partner reward tokens are paid when (vault, destination) pairs are passed in registry order
+        // through `remaining_accounts`; otherwise they stay accrued on the position.
+        if !ctx.remaining_accounts.is_empty() {
+            let reward_tokens = &ctx.accounts.reward_tokens;
+            require!(
+                ctx.remaining_accounts.len() == reward_tokens.tokens.len() * 2,
+                ErrorCode::InvalidRewardTokenAccounts
+            );
+            let seeds = ctx.accounts.config.vault_signer_seeds();
+            let signer = &[&seeds[..]];
+            for (slot, (reward_token, pair)) in reward_tokens.tokens.iter().zip(ctx.remaining_accounts.chunks(2)).enumerate() {
+                let owed = stake_info.reward_token_accrued[slot];
+                if owed == 0 {
+                    continue;
+                }
+                require_keys_eq!(pair[0].key(), reward_token.vault, ErrorCode::InvalidRewardTokenAccounts);
+                let destination = TokenAccount::try_deserialize(&mut &pair[1].try_borrow_data()?[..])?;
+                require!(
+                    destination.mint == reward_token.mint && destination.owner == ctx.accounts.staker_token_account.owner,
+                    ErrorCode::InvalidRewardTokenAccounts
+                );
+                let cpi_accounts = Transfer {
+                    from: pair[0].clone(),
+                    to: pair[1].clone(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), owed)?;
+                stake_info.reward_token_accrued[slot] = 0;
+                msg!("Partner rewards claimed: {} tokens of mint {}", owed, reward_token.mint);
+            }
+        }
+        // The claim itself may already have been paid out of the reward vault.
+        let vault_remaining = ctx.accounts.reward_vault.amount.saturating_sub(total_reward);
+        credit_referral(
+            &ctx.accounts.config,
+            &ctx.accounts.stake_info,
+            ctx.accounts.referral_account.as_deref_mut(),
+            ctx.accounts.referral_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            total_reward,
+            vault_remaining,
+        )?;
         Ok(())
     }
 
     /// Governance instruction: creates a proposal for protocol changes.
-    pub fn create_proposal(ctx: Context<CreateProposal>, description: String) -> Result<()> {
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        description_hash: [u8; 32],
+        description_uri: String,
+    ) -> Result<()> {
+        ctx.accounts.governance_config.description_policy.validate(&description_hash, &description_uri)?;
+        ensure_proposer_stake(&ctx.accounts.governance_config, ctx.accounts.proposer_stake_info.as_deref())?;
+        let proposer = ctx.accounts.proposer.key();
+        let index = ctx.accounts.proposer_record.take_index(proposer)?;
+        let bond = take_proposal_bond(
+            &ctx.accounts.governance_config,
+            &ctx.accounts.proposer,
+            ctx.accounts.proposer_token_account.as_deref(),
+            ctx.accounts.bond_vault.as_deref(),
+            &ctx.accounts.token_program,
+        )?;
         let proposal = &mut ctx.accounts.proposal;
-        proposal.proposer = ctx.accounts.proposer.key();
-        proposal.description = description;
-        proposal.votes_for = 0;
-        proposal.votes_against = 0;
-        proposal.created_at = Clock::get()?.unix_timestamp;
+        proposal.init(proposer, index, description_hash, description_uri, bond, Clock::get()?.unix_timestamp)?;
         msg!("New governance proposal created");
         Ok(())
     }
 
-    /// Vote on a proposal.
-    pub fn vote_proposal(ctx: Context<VoteProposal>, support: bool) -> Result<()> {
-        let voting_power = calculate_voting_power(&ctx.accounts.stake_info);
+    /// Vote on a proposal. A delegate also casts the power of up to `MAX_DELEGATORS_PER_VOTE`
+    /// delegators, passed as (delegator `StakeInfo`, delegator vote record PDA) pairs in
+    /// `remaining_accounts`; each delegator gets a vote record so their power is only counted once.
+    pub fn vote_proposal(ctx: Context<VoteProposal>, choice: VoteChoice) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(ctx.accounts.stake_info.delegate == Pubkey::default(), ErrorCode::VotingPowerDelegated);
+        let pair_count = ctx.remaining_accounts.len() / 2;
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0 && pair_count <= MAX_DELEGATORS_PER_VOTE,
+            ErrorCode::InvalidDelegatorAccounts
+        );
+        let proposal_key = ctx.accounts.proposal.key();
+        let voter = ctx.accounts.voter.key();
+        let snapshot_at = ctx.accounts.proposal.created_at;
+        let own_power = calculate_voting_power(&ctx.accounts.stake_info, snapshot_at)?;
+        let mut voting_power = own_power;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (delegator, delegated_power) = open_delegator_record(
+                pair,
+                b"vote",
+                proposal_key,
+                voter,
+                snapshot_at,
+                &ctx.accounts.voter,
+                &ctx.accounts.system_program,
+                8 + VoteRecord::LEN,
+            )?;
+            let delegator_record = VoteRecord {
+                proposal: proposal_key,
+                voter: delegator,
+                choice,
+                voting_power: delegated_power,
+                delegate: voter,
+                delegator_count: 0,
+            };
+            delegator_record.try_serialize(&mut &mut pair[1].try_borrow_mut_data()?[..])?;
+            voting_power = voting_power.checked_add(delegated_power).ok_or(ErrorCode::Overflow)?;
+        }
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Active && now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+        require!(proposal.options.is_empty(), ErrorCode::InvalidVoteOptions);
+        proposal.add_votes(choice, voting_power)?;
+        // Delegated power is recorded on each delegator's own vote record.
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.proposal = proposal.key();
+        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.choice = choice;
+        vote_record.voting_power = own_power;
+        vote_record.delegate = Pubkey::default();
+        vote_record.delegator_count = pair_count as u8;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.proposals_voted = stake_info.proposals_voted.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, now);
+        msg!("Vote cast with power: {} ({} delegators)", voting_power, pair_count);
+        Ok(())
+    }
+
+    /// Create a proposal with 2 to `MAX_PROPOSAL_OPTIONS` options, voted on with `vote_options`.
+    pub fn create_multi_option_proposal(
+        ctx: Context<CreateProposal>,
+        description_hash: [u8; 32],
+        description_uri: String,
+        options: Vec<String>,
+    ) -> Result<()> {
+        require!(
+            options.len() >= 2
+                && options.len() <= MAX_PROPOSAL_OPTIONS
+                && options.iter().all(|option| !option.is_empty() && option.len() <= MAX_PROPOSAL_OPTION_LEN),
+            ErrorCode::InvalidVoteOptions
+        );
+        ctx.accounts.governance_config.description_policy.validate(&description_hash, &description_uri)?;
+        ensure_proposer_stake(&ctx.accounts.governance_config, ctx.accounts.proposer_stake_info.as_deref())?;
+        let proposer = ctx.accounts.proposer.key();
+        let index = ctx.accounts.proposer_record.take_index(proposer)?;
+        let bond = take_proposal_bond(
+            &ctx.accounts.governance_config,
+            &ctx.accounts.proposer,
+            ctx.accounts.proposer_token_account.as_deref(),
+            ctx.accounts.bond_vault.as_deref(),
+            &ctx.accounts.token_program,
+        )?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.init(proposer, index, description_hash, description_uri, bond, Clock::get()?.unix_timestamp)?;
+        proposal.option_votes = vec![0; options.len()];
+        proposal.options = options;
+        msg!("New proposal created with {} options", proposal.options.len());
+        Ok(())
+    }
+
+    /// Propose slashing `slash_percentage` percent of `staker`'s stake; `slash_stake` can only
+    /// act on such a proposal once it has passed.
+    pub fn create_slash_proposal(
+        ctx: Context<CreateProposal>,
+        description_hash: [u8; 32],
+        description_uri: String,
+        staker: Pubkey,
+        slash_percentage: u64,
+    ) -> Result<()> {
+        require!(slash_percentage > 0 && slash_percentage <= 100, ErrorCode::InvalidAmount);
+        ctx.accounts.governance_config.description_policy.validate(&description_hash, &description_uri)?;
+        ensure_proposer_stake(&ctx.accounts.governance_config, ctx.accounts.proposer_stake_info.as_deref())?;
+        let proposer = ctx.accounts.proposer.key();
+        let index = ctx.accounts.proposer_record.take_index(proposer)?;
+        let bond = take_proposal_bond(
+            &ctx.accounts.governance_config,
+            &ctx.accounts.proposer,
+            ctx.accounts.proposer_token_account.as_deref(),
+            ctx.accounts.bond_vault.as_deref(),
+            &ctx.accounts.token_program,
+        )?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.init(proposer, index, description_hash, description_uri, bond, Clock::get()?.unix_timestamp)?;
+        proposal.slash = Some(SlashTarget {
+            staker,
+            slash_percentage,
+            executed: false,
+        });
+        msg!("New slash proposal created: {}% of {}", slash_percentage, staker);
+        Ok(())
+    }
+
+    /// Vote on a multi-option proposal, splitting voting power across options by `weights_bps`
+    /// (one weight per option, summing to at most 100%). Voting again replaces the previous split.
+    /// A delegate also casts the power of up to `MAX_DELEGATORS_PER_VOTE` delegators, passed as
+    /// (delegator `StakeInfo`, delegator option vote PDA) pairs in `remaining_accounts`.
+    pub fn vote_options(ctx: Context<VoteOptions>, weights_bps: Vec<u64>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(ctx.accounts.stake_info.delegate == Pubkey::default(), ErrorCode::VotingPowerDelegated);
+        let pair_count = ctx.remaining_accounts.len() / 2;
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0 && pair_count <= MAX_DELEGATORS_PER_VOTE,
+            ErrorCode::InvalidDelegatorAccounts
+        );
+        let proposal_key = ctx.accounts.proposal.key();
+        let voter = ctx.accounts.voter.key();
+        let snapshot_at = ctx.accounts.proposal.created_at;
+        let mut delegated_power: u64 = 0;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (delegator, power) = open_delegator_record(
+                pair,
+                b"option_vote",
+                proposal_key,
+                voter,
+                snapshot_at,
+                &ctx.accounts.voter,
+                &ctx.accounts.system_program,
+                8 + OptionVoteRecord::LEN,
+            )?;
+            // Delegator records only mark the power as cast; it is tallied under the delegate's split.
+            let delegator_record = OptionVoteRecord {
+                proposal: proposal_key,
+                voter: delegator,
+                voting_power: power,
+                weights_bps: Vec::new(),
+                delegate: voter,
+            };
+            delegator_record.try_serialize(&mut &mut pair[1].try_borrow_mut_data()?[..])?;
+            delegated_power = delegated_power.checked_add(power).ok_or(ErrorCode::Overflow)?;
+        }
+
         let proposal = &mut ctx.accounts.proposal;
-        if support {
-            proposal.votes_for = proposal.votes_for.checked_add(voting_power).ok_or(ErrorCode::Overflow)?;
+        require!(proposal.status == ProposalStatus::Active && now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+        let total_weight = weights_bps.iter().try_fold(0u64, |sum, weight| sum.checked_add(*weight));
+        require!(
+            !proposal.options.is_empty()
+                && weights_bps.len() == proposal.options.len()
+                && matches!(total_weight, Some(total) if total > 0 && total <= BPS_DENOMINATOR),
+            ErrorCode::InvalidVoteOptions
+        );
+
+        let option_vote = &mut ctx.accounts.option_vote;
+        if option_vote.voter != Pubkey::default() {
+            require!(option_vote.delegate == Pubkey::default(), ErrorCode::VotingPowerDelegated);
+            proposal.apply_option_votes(&option_vote.weights_bps, option_vote.voting_power, false)?;
         } else {
-            proposal.votes_against = proposal.votes_against.checked_add(voting_power).ok_or(ErrorCode::Overflow)?;
+            option_vote.proposal = proposal.key();
+            option_vote.voter = ctx.accounts.voter.key();
+            option_vote.voting_power = calculate_voting_power(&ctx.accounts.stake_info, proposal.created_at)?;
+            option_vote.delegate = Pubkey::default();
         }
-        msg!("Vote cast with power: {}", voting_power);
+        option_vote.voting_power = option_vote.voting_power.checked_add(delegated_power).ok_or(ErrorCode::Overflow)?;
+        proposal.apply_option_votes(&weights_bps, option_vote.voting_power, true)?;
+        option_vote.weights_bps = weights_bps;
+        msg!("Voting power {} split across {} options", option_vote.voting_power, proposal.options.len());
         Ok(())
     }
 
-    /// Borrow instruction: allows borrowing up to 50% of staked SST.
-    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
-        let stake_info = &mut ctx.accounts.stake_info;
-        let max_borrow = stake_info.amount.checked_div(2).ok_or(ErrorCode::Overflow)?;
-        require!(amount <= max_borrow, ErrorCode::BorrowLimitExceeded);
-        stake_info.borrowed_amount = stake_info.borrowed_amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
-        msg!("Borrowed {} tokens against stake", amount);
+    /// Move a cast vote to another choice while voting is open. A delegate passes the delegator
+    /// vote records cast with the vote in `remaining_accounts`; their power moves with it.
+    pub fn change_vote(ctx: Context<ChangeVote>, choice: VoteChoice) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Active && Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            ErrorCode::VotingClosed
+        );
+        let vote_record = &mut ctx.accounts.vote_record;
+        require!(proposal.options.is_empty(), ErrorCode::InvalidVoteOptions);
+        require!(vote_record.delegate == Pubkey::default(), ErrorCode::VotingPowerDelegated);
+        let delegated_power = update_delegator_votes(
+            ctx.remaining_accounts,
+            proposal.key(),
+            vote_record,
+            Some(choice),
+            &ctx.accounts.voter.to_account_info(),
+        )?;
+        let voting_power = vote_record.voting_power.checked_add(delegated_power).ok_or(ErrorCode::Overflow)?;
+        proposal.remove_votes(vote_record.choice, voting_power)?;
+        proposal.add_votes(choice, voting_power)?;
+        vote_record.choice = choice;
+        msg!("Vote changed to {:?} with power {}", choice, voting_power);
         Ok(())
     }
 
-    /// Toggle the auto-restake option.
-    pub fn toggle_auto_restake(ctx: Context<ToggleAutoRestake>, enabled: bool) -> Result<()> {
+    /// Withdraw a cast vote while voting is open, removing its power from the tallies and closing
+    /// the vote record. A delegate passes the delegator vote records cast with the vote in
+    /// `remaining_accounts`; they are closed too, so the delegators' power can be cast again.
+    pub fn withdraw_vote(ctx: Context<WithdrawVote>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Active && Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            ErrorCode::VotingClosed
+        );
+        let vote_record = &ctx.accounts.vote_record;
+        require!(vote_record.delegate == Pubkey::default(), ErrorCode::VotingPowerDelegated);
+        let delegated_power = update_delegator_votes(
+            ctx.remaining_accounts,
+            proposal.key(),
+            vote_record,
+            None,
+            &ctx.accounts.voter.to_account_info(),
+        )?;
+        let voting_power = vote_record.voting_power.checked_add(delegated_power).ok_or(ErrorCode::Overflow)?;
+        proposal.remove_votes(vote_record.choice, voting_power)?;
+        msg!("Vote withdrawn: {} voting power removed", voting_power);
+        Ok(())
+    }
+
+    /// Delegate this position's voting power to another staker, who must hold an active position
+    /// and not delegate their own power.
+    pub fn delegate_votes(ctx: Context<DelegateVotes>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
         let stake_info = &mut ctx.accounts.stake_info;
-        stake_info.auto_restake = enabled;
-        msg!("Auto-restake toggled to: {}", enabled);
+        let delegate_stake_info = &mut ctx.accounts.delegate_stake_info;
+        require!(
+            stake_info.delegate == Pubkey::default()
+                && stake_info.delegator_count == 0
+                && delegate_stake_info.staker != stake_info.staker
+                && delegate_stake_info.amount > 0
+                && delegate_stake_info.delegate == Pubkey::default(),
+            ErrorCode::InvalidDelegate
+        );
+        stake_info.delegate = delegate_stake_info.staker;
+        delegate_stake_info.delegator_count = delegate_stake_info.delegator_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        emit!(VotesDelegated {
+            delegator: stake_info.staker,
+            delegate: stake_info.delegate,
+        });
+        msg!("Voting power delegated to {}", stake_info.delegate);
         Ok(())
     }
 
-    /// Dual staking pool: stake both SST and USDC.
-    pub fn stake_dual(ctx: Context<StakeDual>, sst_amount: u64, usdc_amount: u64) -> Result<()> {
+    /// Take back delegated voting power. Votes the delegate already cast with it stand.
+    pub fn undelegate_votes(ctx: Context<UndelegateVotes>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
         let stake_info = &mut ctx.accounts.stake_info;
-        let clock = Clock::get()?;
-        // Transfer SST.
-        let cpi_accounts_sst = Transfer {
-            from: ctx.accounts.staker_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.staker.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts_sst), sst_amount)?;
-        // Transfer USDC.
-        let cpi_accounts_usdc = Transfer {
-            from: ctx.accounts.staker_usdc_token_account.to_account_info(),
-            to: ctx.accounts.vault_usdc_token_account.to_account_info(),
-            authority: ctx.accounts.staker.to_account_info(),
-        };
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts_usdc), usdc_amount)?;
-        stake_info.staker = ctx.accounts.staker.key();
-        stake_info.amount = stake_info.amount.checked_add(sst_amount).ok_or(ErrorCode::Overflow)?;
-        stake_info.usdc_amount = stake_info.usdc_amount.checked_add(usdc_amount).ok_or(ErrorCode::Overflow)?;
-        stake_info.last_staked_time = clock.unix_timestamp;
-        stake_info.auto_restake = false;
+        let delegate_stake_info = &mut ctx.accounts.delegate_stake_info;
+        delegate_stake_info.delegator_count = delegate_stake_info.delegator_count.checked_sub(1).ok_or(ErrorCode::Underflow)?;
+        emit!(VotesUndelegated {
+            delegator: stake_info.staker,
+            delegate: stake_info.delegate,
+        });
+        stake_info.delegate = Pubkey::default();
+        msg!("Voting power undelegated");
         Ok(())
     }
 
-    /// Deposit LP tokens for yield farming.
-    pub fn deposit_lp(ctx: Context<DepositLP>, lp_amount: u64) -> Result<()> {
+    /// Lock SST in the vote escrow until `unlock_at` (at most `MAX_VE_LOCK_DURATION` ahead),
+    /// adding `amount` to any existing lock. The lock can only be extended, never shortened.
+    pub fn lock_ve(ctx: Context<VeLock>, amount: u64, unlock_at: i64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let now = Clock::get()?.unix_timestamp;
         let stake_info = &mut ctx.accounts.stake_info;
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.staker_lp_token_account.to_account_info(),
-            to: ctx.accounts.vault_lp_token_account.to_account_info(),
-            authority: ctx.accounts.staker.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts), lp_amount)?;
-        stake_info.lp_deposit = stake_info.lp_deposit.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        require!(
+            unlock_at > now
+                && unlock_at <= now.saturating_add(MAX_VE_LOCK_DURATION)
+                && unlock_at >= stake_info.ve_unlock_at,
+            ErrorCode::InvalidLockPeriod
+        );
+        require!(amount > 0 || stake_info.ve_locked > 0, ErrorCode::InvalidAmount);
+        if amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.staker_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.staker.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        }
+        stake_info.ve_locked = stake_info.ve_locked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.ve_unlock_at = unlock_at;
+        stake_info.ve_updated_at = now;
+        emit!(VeLockUpdated {
+            staker: stake_info.staker,
+            locked: stake_info.ve_locked,
+            unlock_at,
+            ve_balance: stake_info.ve_balance_at(now.saturating_add(1))?,
+        });
+        msg!("veSST lock: {} SST until {}", stake_info.ve_locked, unlock_at);
         Ok(())
     }
 
-    /// Flash loan: borrow tokens instantly against staked SST.
-    pub fn flash_loan(ctx: Context<FlashLoan>, amount: u64) -> Result<()> {
+    /// Withdraw the SST of an expired vote-escrow lock.
+    pub fn withdraw_ve(ctx: Context<VeLock>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
         let stake_info = &mut ctx.accounts.stake_info;
-        let max_flash = stake_info.amount.checked_div(2).ok_or(ErrorCode::Overflow)?;
-        require!(amount <= max_flash, ErrorCode::BorrowLimitExceeded);
-        let seeds = &[b"vault".as_ref()];
+        let amount = stake_info.ve_locked;
+        require!(amount > 0, ErrorCode::InsufficientStakedAmount);
+        require!(Clock::get()?.unix_timestamp >= stake_info.ve_unlock_at, ErrorCode::TokensLocked);
+        let seeds = ctx.accounts.config.vault_signer_seeds();
         let signer = &[&seeds[..]];
         let cpi_accounts = Transfer {
             from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.borrower_token_account.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
             authority: ctx.accounts.vault_authority.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
         token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
-        stake_info.borrowed_amount = stake_info.borrowed_amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.ve_locked = 0;
+        stake_info.ve_unlock_at = 0;
+        msg!("veSST lock withdrawn: {} SST", amount);
         Ok(())
     }
 
-    /// Slash stake as a penalty for Sybil attacks (governance only).
-    pub fn slash_stake(ctx: Context<SlashStake>, slash_percentage: u64) -> Result<()> {
-        let stake_info = &mut ctx.accounts.stake_info;
-        let slash_amount = stake_info.amount.checked_mul(slash_percentage).ok_or(ErrorCode::Overflow)?
-            .checked_div(100).ok_or(ErrorCode::Underflow)?;
-        stake_info.amount = stake_info.amount.checked_sub(slash_amount).ok_or(ErrorCode::Underflow)?;
-        msg!("Slashed {} tokens from stake", slash_amount);
+    /// Attach an instruction to the proposer's proposal, to be executed in order by
+    /// `execute_proposal` once it passes. Only possible before any vote is cast.
+    pub fn add_proposal_instruction(
+        ctx: Context<AddProposalInstruction>,
+        index: u8,
+        program_id: Pubkey,
+        accounts: Vec<ProposalAccountMeta>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Active
+                && proposal.votes_for == 0
+                && proposal.votes_against == 0
+                && proposal.votes_abstain == 0,
+            ErrorCode::VotingClosed
+        );
+        // Any winning option passes a multi-option proposal, so it cannot carry instructions.
+        require!(
+            proposal.options.is_empty()
+                && index == proposal.instruction_count
+                && index < MAX_PROPOSAL_INSTRUCTIONS
+                && accounts.len() <= MAX_PROPOSAL_INSTRUCTION_ACCOUNTS
+                && data.len() <= MAX_PROPOSAL_INSTRUCTION_DATA,
+            ErrorCode::InvalidProposalInstruction
+        );
+        let proposal_instruction = &mut ctx.accounts.proposal_instruction;
+        proposal_instruction.proposal = proposal.key();
+        proposal_instruction.index = index;
+        proposal_instruction.program_id = program_id;
+        proposal_instruction.accounts = accounts;
+        proposal_instruction.data = data;
+        proposal_instruction.executed_at = 0;
+        proposal.instruction_count = index + 1;
+        msg!("Instruction {} for program {} attached to proposal", index, program_id);
         Ok(())
     }
 
-    /// Donate to the governance-backed insurance fund.
-    pub fn donate_insurance(ctx: Context<DonateInsurance>, amount: u64) -> Result<()> {
-        let insurance_fund = &mut ctx.accounts.insurance_fund;
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.donor_token_account.to_account_info(),
-            to: ctx.accounts.insurance_fund_token_account.to_account_info(),
-            authority: ctx.accounts.donor.to_account_info(),
+    /// Permissionless: execute a passed proposal's next instruction once the timelock has elapsed,
+    /// signing with the governance PDA. `remaining_accounts` must hold the instruction's accounts
+    /// and its program.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        proposal.ensure_executable(now)?;
+        let proposal_instruction = &mut ctx.accounts.proposal_instruction;
+        require!(
+            proposal_instruction.index == proposal.executed_count && proposal_instruction.executed_at == 0,
+            ErrorCode::InvalidProposalInstruction
+        );
+
+        let instruction = Instruction {
+            program_id: proposal_instruction.program_id,
+            accounts: proposal_instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: proposal_instruction.data.clone(),
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
-        insurance_fund.balance = insurance_fund.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
-        msg!("Donated {} tokens to the insurance fund", amount);
+        let (_, bump) = Pubkey::find_program_address(&[GOVERNANCE_SIGNER_SEED], &crate::ID);
+        invoke_signed(&instruction, ctx.remaining_accounts, &[&[GOVERNANCE_SIGNER_SEED, &[bump]]])?;
+
+        proposal_instruction.executed_at = now;
+        proposal.executed_count = proposal.executed_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        msg!(
+            "Proposal instruction {} executed ({} of {})",
+            proposal_instruction.index,
+            proposal.executed_count,
+            proposal.instruction_count
+        );
         Ok(())
     }
-}
 
-/// Helper: calculates dynamic fee discount.
-fn calculate_fee_discount(staked_amount: u64, staking_duration: i64) -> u64 {
-    let base_discount = staked_amount / 1000;
-    let duration_bonus = (staking_duration / (30 * 24 * 60 * 60)) as u64;
-    std::cmp::min(base_discount + duration_bonus, 50)
-}
+    /// Permissionless: write a passed parameter-change proposal's changes to the config once the
+    /// timelock has elapsed. Fails if a parameter changed since the proposal was created.
+    pub fn execute_parameter_changes(ctx: Context<ExecuteParameterChanges>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.ensure_executable(Clock::get()?.unix_timestamp)?;
+        require!(
+            !proposal.parameters_applied && !proposal.parameter_changes.is_empty(),
+            ErrorCode::InvalidParameterChanges
+        );
+        let config = &mut ctx.accounts.config;
+        for change in proposal.parameter_changes.iter() {
+            require!(config.parameter_value(change.parameter) == change.old_value, ErrorCode::StaleParameterChange);
+            config.set_parameter(change.parameter, change.new_value)?;
+            msg!("{:?}: {} -> {}", change.parameter, change.old_value, change.new_value);
+        }
+        proposal.parameters_applied = true;
+        msg!("Applied {} parameter changes", proposal.parameter_changes.len());
+        Ok(())
+    }
 
-/// Helper: calculates LP yield boost.
-fn lp_reward_boost(liquidity_provided: u64) -> u64 {
-    let boost = liquidity_provided / 10_000;
-    std::cmp::min(boost, 20)
-}
+    /// Create the protocol treasury around a vault-authority-owned token account (governance
+    /// config authority only). `epoch_spend_limit` caps what proposals can spend per reward epoch.
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>, epoch_spend_limit: u64) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.vault = ctx.accounts.treasury_vault.key();
+        treasury.epoch_spend_limit = epoch_spend_limit;
+        treasury.spend_epoch = 0;
+        treasury.epoch_spent = 0;
+        msg!("Treasury initialized with an epoch spend limit of {}", epoch_spend_limit);
+        Ok(())
+    }
 
-/// Helper: returns a VIP multiplier based on staked amount.
-fn vip_multiplier(staked_amount: u64) -> u64 {
-    if staked_amount >= 10_000 * 1_000_000 {
-        130
-    } else if staked_amount >= 5_000 * 1_000_000 {
-        115
-    } else if staked_amount >= 1_000 * 1_000_000 {
-        105
-    } else {
-        100
+    /// Change the treasury's per-epoch spend limit (governance config authority only).
+    pub fn set_treasury_spend_limit(ctx: Context<SetTreasurySpendLimit>, epoch_spend_limit: u64) -> Result<()> {
+        ctx.accounts.treasury.epoch_spend_limit = epoch_spend_limit;
+        msg!("Treasury epoch spend limit set to {}", epoch_spend_limit);
+        Ok(())
     }
-}
 
-/// Helper: calculates voting power based on staked amount and duration.
-fn calculate_voting_power(stake_info: &StakeInfo) -> u64 {
-    let clock = Clock::get().unwrap();
-    let duration = clock.unix_timestamp.checked_sub(stake_info.last_staked_time).unwrap_or(0);
-    let base_power = stake_info.amount;
-    let bonus = base_power * ((duration / (30 * 24 * 60 * 60)) as u64) / 100;
-    base_power.checked_add(bonus).unwrap_or(base_power)
-}
+    /// Propose paying `amount` from the treasury vault to the `recipient` token account.
+    pub fn create_treasury_spend_proposal(
+        ctx: Context<CreateTreasurySpendProposal>,
+        description_hash: [u8; 32],
+        description_uri: String,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.treasury.epoch_spend_limit,
+            ErrorCode::TreasurySpendLimitExceeded
+        );
+        ctx.accounts.governance_config.description_policy.validate(&description_hash, &description_uri)?;
+        ensure_proposer_stake(&ctx.accounts.governance_config, ctx.accounts.proposer_stake_info.as_deref())?;
+        let proposer = ctx.accounts.proposer.key();
+        let index = ctx.accounts.proposer_record.take_index(proposer)?;
+        let bond = take_proposal_bond(
+            &ctx.accounts.governance_config,
+            &ctx.accounts.proposer,
+            ctx.accounts.proposer_token_account.as_deref(),
+            ctx.accounts.bond_vault.as_deref(),
+            &ctx.accounts.token_program,
+        )?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.init(proposer, index, description_hash, description_uri, bond, Clock::get()?.unix_timestamp)?;
+        proposal.treasury_spend = Some(TreasurySpend {
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            executed: false,
+        });
+        msg!("New treasury-spend proposal created: {} to {}", amount, ctx.accounts.recipient.key());
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct StakeAccounts<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+    /// Permissionless: pay out a passed treasury-spend proposal once the timelock has elapsed,
+    /// within the treasury's spend limit for the current reward epoch.
+    pub fn execute_treasury_spend(ctx: Context<ExecuteTreasurySpend>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        proposal.ensure_executable(now)?;
+        let spend = proposal.treasury_spend.as_mut().ok_or(ErrorCode::InvalidTreasurySpend)?;
+        require!(
+            !spend.executed && spend.recipient == ctx.accounts.recipient.key(),
+            ErrorCode::InvalidTreasurySpend
+        );
 
-    #[account(
-        init,
-        payer = staker,
-        space = 8 + StakeInfo::LEN,
-        seeds = [b"stake", staker.key().as_ref()],
-        bump
-    )]
-    pub stake_info: Account<'info, StakeInfo>,
+        let treasury = &mut ctx.accounts.treasury;
+        let epoch = reward_epoch(now);
+        if epoch != treasury.spend_epoch {
+            treasury.spend_epoch = epoch;
+            treasury.epoch_spent = 0;
+        }
+        treasury.epoch_spent = treasury.epoch_spent.checked_add(spend.amount).ok_or(ErrorCode::Overflow)?;
+        require!(treasury.epoch_spent <= treasury.epoch_spend_limit, ErrorCode::TreasurySpendLimitExceeded);
 
-    #[account(mut)]
-    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury_vault.to_account_info(),
+            to: ctx.accounts.recipient.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), spend.amount)?;
+        spend.executed = true;
+        msg!(
+            "Treasury spend executed: {} ({} of {} spent this epoch)",
+            spend.amount,
+            treasury.epoch_spent,
+            treasury.epoch_spend_limit
+        );
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+    /// Cancel a proposal still open for voting (proposer or governance config authority) while the votes cast
+    /// stay below `CANCEL_VOTE_LIMIT_BPS` of total staked. Refunds the bond and closes the account.
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            authority == proposal.proposer || authority == ctx.accounts.governance_config.authority,
+            ErrorCode::Unauthorized
+        );
+        // Once voting ends the outcome (and a quorum slash of the bond) is left to finalization.
+        require!(
+            proposal.status == ProposalStatus::Active && Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            ErrorCode::VotingClosed
+        );
+        let votes_cast = proposal.votes_for as u128
+            + proposal.votes_against as u128
+            + proposal.votes_abstain as u128
+            + proposal.option_votes.iter().map(|votes| *votes as u128).sum::<u128>();
+        require!(
+            votes_cast * (BPS_DENOMINATOR as u128)
+                < ctx.accounts.protocol_stats.total_staked as u128 * CANCEL_VOTE_LIMIT_BPS as u128,
+            ErrorCode::ProposalHasVotes
+        );
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+        if proposal.bond > 0 {
+            let (Some(bond_vault), Some(proposer_token_account)) =
+                (ctx.accounts.bond_vault.as_ref(), ctx.accounts.proposer_token_account.as_ref())
+            else {
+                return err!(ErrorCode::ProposalBondRequired);
+            };
+            let seeds = ctx.accounts.config.vault_signer_seeds();
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: bond_vault.to_account_info(),
+                to: proposer_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), proposal.bond)?;
+        }
+        msg!("Proposal {} cancelled; {} bond refunded", proposal.key(), proposal.bond);
+        Ok(())
+    }
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Permissionless: close a settled proposal and return its rent to the proposer. Passed
+    /// proposals must be fully executed or expired, vetoed ones past their execution window so the
+    /// veto can still be overturned; any bond must have been settled.
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        let done = match proposal.status {
+            ProposalStatus::Active => false,
+            ProposalStatus::Passed => {
+                (now >= proposal.executable_at && proposal.fully_executed()) || now >= proposal.expires_at()
+            }
+            ProposalStatus::Failed => true,
+            // Governance gets the execution window to overturn a veto before the proposal goes.
+            ProposalStatus::Vetoed => now >= proposal.expires_at(),
+        };
+        require!(done, ErrorCode::ProposalNotClosable);
+        require!(proposal.bond == 0 || proposal.bond_settled, ErrorCode::ProposalNotClosable);
+        msg!("Proposal {} closed", proposal.key());
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+    /// Permissionless: once voting has ended, record whether the proposal passed or failed.
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Active, ErrorCode::ProposalAlreadyFinalized);
+        require!(Clock::get()?.unix_timestamp >= proposal.voting_ends_at, ErrorCode::VotingStillOpen);
+        proposal.quorum_reached = proposal.quorum_met(&ctx.accounts.governance_config, ctx.accounts.protocol_stats.total_staked);
+        let passed = if proposal.options.is_empty() {
+            proposal.passed(&ctx.accounts.governance_config, ctx.accounts.protocol_stats.total_staked)
+        } else {
+            proposal.winning_option = proposal.leading_option(&ctx.accounts.governance_config, ctx.accounts.protocol_stats.total_staked);
+            proposal.winning_option.is_some()
+        };
+        proposal.status = if passed { ProposalStatus::Passed } else { ProposalStatus::Failed };
+        if passed {
+            // Emergency proposals were co-signed by the council and wait only the short emergency delay.
+            let delay = if proposal.emergency {
+                EMERGENCY_EXECUTION_DELAY
+            } else {
+                ctx.accounts.governance_config.execution_delay
+            };
+            proposal.executable_at = Clock::get()?.unix_timestamp
+                .checked_add(delay)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        emit!(ProposalFinalized {
+            proposal: proposal.key(),
+            status: proposal.status,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            votes_abstain: proposal.votes_abstain,
+        });
+        msg!("Proposal finalized: {:?}", proposal.status);
+        Ok(())
+    }
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+    /// Borrow instruction: borrows against staked SST up to the position's collateral factor, paid
+    /// out of the borrow vault less the origination fee, which goes to the treasury. Stake backing
+    /// the debt is reserved and cannot be unstaked until the loan is repaid.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.accrue_borrow_index(&ctx.accounts.config.borrow_rate_curve, ctx.accounts.borrow_vault.amount, now)?;
+        stake_info.accrue_borrow_interest(protocol_stats.borrow_index)?;
+        stake_info.borrowed_amount = stake_info.borrowed_amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        let total_borrowed = protocol_stats.total_borrowed.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        enforce_borrow_caps(
+            ctx.accounts.config.global_borrow_cap,
+            ctx.accounts.config.max_borrow_per_wallet,
+            total_borrowed,
+            stake_info.borrowed_amount,
+        )?;
+        let collateral_factor_bps = ctx.accounts.config.collateral_factor_bps(stake_info, now);
+        let usdc = usdc_loan_terms(
+            stake_info,
+            &ctx.accounts.config,
+            ctx.accounts.usdc_market.as_deref(),
+            ctx.accounts.price_feed.as_deref(),
+        )?
+        .map(|(price, params)| (price, params.max_ltv_bps));
+        require!(
+            amount > 0 && stake_info.total_collateral_required(collateral_factor_bps, usdc)? <= stake_info.amount,
+            ErrorCode::BorrowLimitExceeded
+        );
+        stake_info.borrow_collateral_factor_bps = collateral_factor_bps;
 
-    #[account(mut)]
-    pub staker_token_account: Box<Account<'info, TokenAccount>>,
-    
-    #[account(mut)]
-    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+        let config = &ctx.accounts.config;
+        let vip = is_vip(config, stake_info.amount, ctx.accounts.price_feed.as_deref())?;
+        let fee = config.borrow_origination_fee(amount, vip)?;
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+        let seeds = config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.borrow_vault.to_account_info(),
+            to: ctx.accounts.borrower_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), amount - fee)?;
+        if fee > 0 {
+            let treasury = ctx.accounts.treasury.as_ref().ok_or(ErrorCode::InvalidTreasuryVault)?;
+            let treasury_vault = ctx.accounts.treasury_vault.as_ref().ok_or(ErrorCode::InvalidTreasuryVault)?;
+            require_keys_eq!(treasury_vault.key(), treasury.vault, ErrorCode::InvalidTreasuryVault);
+            require_keys_eq!(treasury_vault.mint, ctx.accounts.borrow_vault.mint, ErrorCode::InvalidMint);
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.borrow_vault.to_account_info(),
+                to: treasury_vault.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), fee)?;
+        }
+
+        protocol_stats.total_borrowed = total_borrowed;
+        finish_transition(stake_info, from_state, now)?;
+        msg!("Borrowed {} tokens against stake ({} origination fee to the treasury)", amount, fee);
+        Ok(())
+    }
+
+    /// Repay up to `amount` of a loan into the borrow vault. Interest accrued through the borrow
+    /// index is paid first, then principal; the collateral reserved for the repaid principal
+    /// becomes withdrawable again.
+    pub fn repay_borrow(ctx: Context<RepayBorrow>, amount: u64) -> Result<()> {
+        ensure_debt_settleable(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.accrue_borrow_index(&ctx.accounts.config.borrow_rate_curve, ctx.accounts.borrow_vault.amount, now)?;
+        stake_info.accrue_borrow_interest(protocol_stats.borrow_index)?;
+        let amount = amount.min(stake_info.debt()?);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.borrower_token_account.to_account_info(),
+            to: ctx.accounts.borrow_vault.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let (interest_paid, principal_paid) = stake_info.apply_repayment(amount)?;
+        protocol_stats.total_borrowed = protocol_stats.total_borrowed.saturating_sub(principal_paid);
+        finish_transition(stake_info, from_state, now)?;
+        msg!(
+            "Repaid {} tokens ({} interest, {} principal); {} principal outstanding",
+            amount,
+            interest_paid,
+            principal_paid,
+            stake_info.borrowed_amount
+        );
+        Ok(())
+    }
+
+    /// Liquidate an unhealthy position (both loans checked together, the USDC loan at the feed
+    /// price): the liquidator repays up to `LIQUIDATION_CLOSE_FACTOR_BPS`
+    /// of its debt and receives that much staked SST plus `LIQUIDATION_BONUS_BPS`. Permissionless.
+    pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+        ensure_debt_settleable(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.accrue_borrow_index(&ctx.accounts.config.borrow_rate_curve, ctx.accounts.borrow_vault.amount, now)?;
+        stake_info.accrue_borrow_interest(protocol_stats.borrow_index)?;
+        let collateral_factor_bps = ctx.accounts.config.liquidation_factor_bps(stake_info, now);
+        let usdc = usdc_loan_terms(
+            stake_info,
+            &ctx.accounts.config,
+            ctx.accounts.usdc_market.as_deref(),
+            ctx.accounts.price_feed.as_deref(),
+        )?
+        .map(|(price, params)| (price, params.liquidation_ltv_bps));
+        require!(stake_info.is_liquidatable(collateral_factor_bps, usdc)?, ErrorCode::PositionHealthy);
+
+        let max_repay = mul_div_ceil(stake_info.debt()? as u128, LIQUIDATION_CLOSE_FACTOR_BPS as u128, BPS_DENOMINATOR as u128)?;
+        let seize_cap = mul_div_floor(
+            stake_info.amount as u128,
+            BPS_DENOMINATOR as u128,
+            (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+        )?;
+        let repay_amount = repay_amount.min(max_repay).min(seize_cap);
+        require!(repay_amount > 0, ErrorCode::InvalidAmount);
+        let seized = mul_div_floor(
+            repay_amount as u128,
+            (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+            BPS_DENOMINATOR as u128,
+        )?;
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.liquidator_token_account.to_account_info(),
+            to: ctx.accounts.borrow_vault.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        };
+        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts), repay_amount)?;
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), seized)?;
+
+        settle_rewards(protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, now)?;
+        let (_, principal_paid) = stake_info.apply_repayment(repay_amount)?;
+        stake_info.amount = stake_info.amount.checked_sub(seized).ok_or(ErrorCode::Underflow)?;
+        protocol_stats.total_borrowed = protocol_stats.total_borrowed.saturating_sub(principal_paid);
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(seized).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, now)?;
+        finish_transition(stake_info, from_state, now)?;
+        emit!(PositionLiquidated {
+            staker: stake_info.staker,
+            liquidator: ctx.accounts.liquidator.key(),
+            repaid: repay_amount,
+            seized,
+        });
+        msg!("Liquidated {}: {} debt repaid for {} staked SST", stake_info.staker, repay_amount, seized);
+        Ok(())
+    }
+
+    /// Create the USDC debt market lending from `usdc_vault` against staked SST (config authority only).
+    pub fn initialize_usdc_market(ctx: Context<InitializeUsdcMarket>, params: UsdcMarketParams) -> Result<()> {
+        params.check_bounds()?;
+        let usdc_market = &mut ctx.accounts.usdc_market;
+        usdc_market.usdc_vault = ctx.accounts.usdc_vault.key();
+        usdc_market.params = params;
+        usdc_market.total_borrowed = 0;
+        usdc_market.borrow_index = BORROW_INDEX_ONE;
+        usdc_market.borrow_index_updated_at = Clock::get()?.unix_timestamp;
+        msg!("USDC market initialized with a borrow cap of {}", usdc_market.params.borrow_cap);
+        Ok(())
+    }
+
+    /// Update the USDC market's price feed, caps, loan-to-value limits and rate curve (config
+    /// authority only). Interest accrued so far is kept at the previous curve.
+    pub fn set_usdc_market_params(ctx: Context<SetUsdcMarketParams>, params: UsdcMarketParams) -> Result<()> {
+        params.check_bounds()?;
+        let usdc_market = &mut ctx.accounts.usdc_market;
+        usdc_market.accrue_borrow_index(ctx.accounts.usdc_vault.amount, Clock::get()?.unix_timestamp)?;
+        usdc_market.params = params;
+        msg!("USDC market parameters updated");
+        Ok(())
+    }
+
+    /// Borrow USDC from the USDC market against staked SST, up to the market's loan-to-value at
+    /// the feed price. The origination fee is withheld from the USDC paid out.
+    pub fn borrow_usdc(ctx: Context<BorrowUsdc>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let price = read_sst_price(&ctx.accounts.price_feed, &ctx.accounts.config.price_oracle)?;
+        let usdc_market = &mut ctx.accounts.usdc_market;
+        usdc_market.accrue_borrow_index(ctx.accounts.usdc_vault.amount, now)?;
+        stake_info.accrue_usdc_interest(usdc_market.borrow_index)?;
+        let total_borrowed = usdc_market.total_borrowed.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.usdc_borrowed = stake_info.usdc_borrowed.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        enforce_borrow_caps(
+            usdc_market.params.borrow_cap,
+            usdc_market.params.max_borrow_per_wallet,
+            total_borrowed,
+            stake_info.usdc_borrowed,
+        )?;
+        stake_info.usdc_collateral = usdc_collateral_required(stake_info.usdc_debt()?, price, usdc_market.params.max_ltv_bps)?;
+        let collateral_factor_bps = ctx.accounts.config.collateral_factor_bps(stake_info, now);
+        let required =
+            stake_info.total_collateral_required(collateral_factor_bps, Some((price, usdc_market.params.max_ltv_bps)))?;
+        require!(amount > 0 && required <= stake_info.amount, ErrorCode::BorrowLimitExceeded);
+        stake_info.borrow_collateral_factor_bps = collateral_factor_bps;
+
+        let config = &ctx.accounts.config;
+        let vip = is_vip(config, stake_info.amount, Some(&*ctx.accounts.price_feed))?;
+        let fee = config.borrow_origination_fee(amount, vip)?;
+
+        // The fee stays in the USDC vault as market revenue; the treasury vault holds another mint.
+        let seeds = config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.usdc_vault.to_account_info(),
+            to: ctx.accounts.borrower_usdc_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount - fee)?;
+
+        usdc_market.total_borrowed = total_borrowed;
+        finish_transition(stake_info, from_state, now)?;
+        msg!(
+            "Borrowed {} USDC against {} reserved SST ({} origination fee kept by the market)",
+            amount,
+            stake_info.usdc_collateral,
+            fee
+        );
+        Ok(())
+    }
+
+    /// Repay up to `amount` of a USDC loan; interest first, then principal. The reserved SST is
+    /// released in proportion to the debt repaid.
+    pub fn repay_usdc(ctx: Context<RepayUsdc>, amount: u64) -> Result<()> {
+        ensure_debt_settleable(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let usdc_market = &mut ctx.accounts.usdc_market;
+        usdc_market.accrue_borrow_index(ctx.accounts.usdc_vault.amount, now)?;
+        stake_info.accrue_usdc_interest(usdc_market.borrow_index)?;
+        let amount = amount.min(stake_info.usdc_debt()?);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.borrower_usdc_account.to_account_info(),
+            to: ctx.accounts.usdc_vault.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let (interest_paid, principal_paid) = stake_info.apply_usdc_repayment(amount)?;
+        usdc_market.total_borrowed = usdc_market.total_borrowed.saturating_sub(principal_paid);
+        finish_transition(stake_info, from_state, now)?;
+        msg!(
+            "Repaid {} USDC ({} interest, {} principal); {} SST still reserved",
+            amount,
+            interest_paid,
+            principal_paid,
+            stake_info.usdc_collateral
+        );
+        Ok(())
+    }
+
+    /// Liquidate an unhealthy USDC loan: once both loans together exceed what the stake supports
+    /// (the USDC loan at the market's liquidation loan-to-value and the feed price), the liquidator repays up to `LIQUIDATION_CLOSE_FACTOR_BPS`
+    /// of it in USDC and receives the equivalent staked SST plus `LIQUIDATION_BONUS_BPS`.
+    pub fn liquidate_usdc(ctx: Context<LiquidateUsdc>, repay_amount: u64) -> Result<()> {
+        ensure_debt_settleable(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let price = read_sst_price(&ctx.accounts.price_feed, &ctx.accounts.config.price_oracle)?;
+        let usdc_market = &mut ctx.accounts.usdc_market;
+        usdc_market.accrue_borrow_index(ctx.accounts.usdc_vault.amount, now)?;
+        stake_info.accrue_usdc_interest(usdc_market.borrow_index)?;
+        stake_info.accrue_borrow_interest(ctx.accounts.protocol_stats.borrow_index)?;
+        let debt = stake_info.usdc_debt()?;
+        let collateral_factor_bps = ctx.accounts.config.liquidation_factor_bps(stake_info, now);
+        require!(
+            debt > 0
+                && stake_info.is_liquidatable(collateral_factor_bps, Some((price, usdc_market.params.liquidation_ltv_bps)))?,
+            ErrorCode::PositionHealthy
+        );
+
+        let max_repay = mul_div_ceil(debt as u128, LIQUIDATION_CLOSE_FACTOR_BPS as u128, BPS_DENOMINATOR as u128)?;
+        let mut repay_amount = repay_amount.min(max_repay);
+        let mut seized = mul_div_floor(
+            repay_amount as u128,
+            PRICE_SCALE * (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+            price * BPS_DENOMINATOR as u128,
+        )?;
+        if seized > stake_info.amount {
+            // Not enough collateral left: seize all of it and repay only what it covers.
+            seized = stake_info.amount;
+            repay_amount = mul_div_ceil(
+                seized as u128,
+                price * BPS_DENOMINATOR as u128,
+                PRICE_SCALE * (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+            )?
+            .min(repay_amount);
+        }
+        require!(repay_amount > 0 && seized > 0, ErrorCode::InvalidAmount);
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.liquidator_usdc_account.to_account_info(),
+            to: ctx.accounts.usdc_vault.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        };
+        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts), repay_amount)?;
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), seized)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        settle_rewards(protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, now)?;
+        let (_, principal_paid) = stake_info.apply_usdc_repayment(repay_amount)?;
+        stake_info.amount = stake_info.amount.checked_sub(seized).ok_or(ErrorCode::Underflow)?;
+        stake_info.usdc_collateral = stake_info.usdc_collateral.min(stake_info.amount);
+        usdc_market.total_borrowed = usdc_market.total_borrowed.saturating_sub(principal_paid);
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(seized).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, now)?;
+        finish_transition(stake_info, from_state, now)?;
+        emit!(PositionLiquidated {
+            staker: stake_info.staker,
+            liquidator: ctx.accounts.liquidator.key(),
+            repaid: repay_amount,
+            seized,
+        });
+        msg!("Liquidated {}: {} USDC debt repaid for {} staked SST", stake_info.staker, repay_amount, seized);
+        Ok(())
+    }
+
+    /// Toggle the auto-restake option (owner or operator).
+    pub fn toggle_auto_restake(ctx: Context<ToggleAutoRestake>, enabled: bool) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.auto_restake = enabled;
+        msg!("Auto-restake toggled to: {}", enabled);
+        Ok(())
+    }
+
+    /// Set the policy the compounding crank follows for an auto-restake position (owner or
+    /// operator).
+    pub fn set_compound_policy(ctx: Context<ToggleAutoRestake>, policy: CompoundPolicy) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(policy.compound_interval >= MIN_COMPOUND_INTERVAL, ErrorCode::InvalidCompoundPolicy);
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.compound_policy = policy;
+        msg!(
+            "Compound policy set (min {}, every {}s, LP rewards included: {})",
+            policy.min_compound_amount,
+            policy.compound_interval,
+            policy.include_lp_rewards
+        );
+        Ok(())
+    }
+
+    /// Crank: compounds an auto-restake position's rewards into its stake according to the
+    /// position's compound policy. Permissionless so keepers can service opted-in stakers.
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(stake_info.auto_restake, ErrorCode::AutoRestakeDisabled);
+        let policy = stake_info.compound_policy;
+        // Positions whose policy predates the minimum are held to it as well.
+        require!(
+            clock.unix_timestamp.saturating_sub(stake_info.last_compounded_at)
+                >= policy.compound_interval.max(MIN_COMPOUND_INTERVAL),
+            ErrorCode::CompoundTooSoon
+        );
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        require!(stake_info.accrued_rewards > 0, ErrorCode::BelowCompoundMinimum);
+        let lp_boost = if policy.include_lp_rewards { stake_info.take_lp_boost(clock.unix_timestamp)? } else { 0 };
+        let (requested_base, requested_boost, _) = calculate_claim_reward(stake_info.accrued_rewards, lp_boost)?;
+        let (base_reward, lp_boost, total_reward) = cap_claim(
+            &mut ctx.accounts.protocol_stats,
+            &ctx.accounts.config,
+            stake_info,
+            requested_base,
+            requested_boost,
+            ctx.accounts.reward_vault.amount,
+            clock.unix_timestamp,
+        )?;
+        require!(
+            total_reward > 0 && total_reward >= policy.min_compound_amount,
+            ErrorCode::BelowCompoundMinimum
+        );
+        emit!(RewardsClaimed {
+            staker: stake_info.staker,
+            base_reward,
+            lp_boost,
+            total_reward,
+            compounded: true,
+        });
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_reward)?;
+
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
+        stake_info.amount = stake_info.amount.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_rewards = stake_info.lifetime_rewards.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        stake_info.last_compounded_at = clock.unix_timestamp;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_distributed = protocol_stats.total_rewards_distributed
+            .checked_add(total_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        credit_referral(
+            &ctx.accounts.config,
+            &ctx.accounts.stake_info,
+            ctx.accounts.referral_account.as_deref_mut(),
+            ctx.accounts.referral_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            total_reward,
+            ctx.accounts.reward_vault.amount.saturating_sub(total_reward),
+        )?;
+        msg!("Rewards compounded: {} tokens (Base: {}, LP Boost: {})", total_reward, base_reward, lp_boost);
+        Ok(())
+    }
+
+    /// Set the auto-relock option for a locked position.
+    pub fn set_auto_relock(ctx: Context<SetAutoRelock>, enabled: bool) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(!enabled || stake_info.lock_period > 0, ErrorCode::PositionNotLocked);
+        stake_info.auto_relock = enabled;
+        if enabled {
+            stake_info.unstake_at_maturity = false;
+            stake_info.scheduled_withdrawal_at = 0;
+        }
+        msg!("Auto-relock set to: {}", enabled);
+        Ok(())
+    }
+
+    /// Crank: re-locks a matured position that opted into auto-relock for the same lock period.
+    /// Permissionless so keepers can service set-and-forget stakers. `last_staked_time` is left
+    /// untouched so the staking-duration bonus keeps accruing across lock cycles.
+    pub fn relock_matured(ctx: Context<RelockMatured>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        require!(stake_info.auto_relock, ErrorCode::AutoRelockDisabled);
+        require!(stake_info.lock_period > 0, ErrorCode::PositionNotLocked);
+        require!(clock.unix_timestamp >= stake_info.locked_until, ErrorCode::LockNotMatured);
+        stake_info.locked_until = clock.unix_timestamp
+            .checked_add(stake_info.lock_period as i64)
+            .ok_or(ErrorCode::Overflow)?;
+        stake_info.vesting_principal = stake_info.amount;
+        stake_info.vested_withdrawn = 0;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        msg!("Position re-locked for {}s until {}", stake_info.lock_period, stake_info.locked_until);
+        Ok(())
+    }
+
+    /// Pre-authorize (or cancel, with `timestamp == 0`) a full unstake that any keeper may execute
+    /// once both `timestamp` and lock maturity have passed. Tokens go to the registered withdrawal
+    /// address, or else to the provided staker-owned token account. Clears auto-relock. The
+    /// position receipt, if any, is approved for the keeper's burn.
+    pub fn schedule_withdrawal(ctx: Context<ScheduleWithdrawal>, timestamp: i64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let enabled = timestamp != 0;
+        require!(timestamp >= 0, ErrorCode::InvalidAmount);
+        stake_info.unstake_at_maturity = enabled;
+        stake_info.scheduled_withdrawal_at = timestamp;
+        if enabled {
+            approve_receipt_burn(
+                stake_info,
+                ctx.accounts.receipt_mint.as_ref().map(|mint| mint.to_account_info()),
+                ctx.accounts.receipt_token_account.as_ref().map(|account| account.to_account_info()),
+                ctx.accounts.receipt_authority.as_ref().map(|authority| authority.to_account_info()),
+                ctx.accounts.staker.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.program_id,
+            )?;
+            stake_info.maturity_payout_account = ctx.accounts.staker_token_account.key();
+            stake_info.auto_relock = false;
+            msg!(
+                "Withdrawal scheduled for {} (lock matures at {})",
+                timestamp.max(stake_info.locked_until),
+                stake_info.locked_until
+            );
+        } else {
+            stake_info.maturity_payout_account = Pubkey::default();
+            msg!("Scheduled withdrawal cancelled");
+        }
+        finish_transition(stake_info, from_state, now)?;
+        Ok(())
+    }
+
+    /// Crank: executes a scheduled withdrawal, pushing the whole position to its payout account.
+    pub fn execute_matured_unstake(ctx: Context<ExecuteMaturedUnstake>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        require!(stake_info.unstake_at_maturity, ErrorCode::NoScheduledUnstake);
+        require!(clock.unix_timestamp >= stake_info.locked_until, ErrorCode::LockNotMatured);
+        require!(clock.unix_timestamp >= stake_info.scheduled_withdrawal_at, ErrorCode::WithdrawalNotDue);
+        require!(
+            stake_info.lock_period > 0
+                || clock.unix_timestamp - stake_info.last_staked_time >= MIN_NON_LOCKED_STAKE_DURATION,
+            ErrorCode::WithdrawalNotDue
+        );
+        require!(stake_info.unvested_gift_amount(clock.unix_timestamp) == 0, ErrorCode::GiftStillVesting);
+        require!(!stake_info.has_debt(), ErrorCode::OutstandingBorrow);
+        let amount = stake_info.amount;
+        let co_signer = ctx.accounts.second_signer.as_ref().map(|signer| signer.key());
+        require!(
+            stake_info.second_factor_satisfied(amount, co_signer, clock.unix_timestamp),
+            ErrorCode::SecondSignerRequired
+        );
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+
+        if ctx.accounts.payout_token_account.is_frozen() {
+            stake_info.pending_withdrawal = stake_info.pending_withdrawal.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+            stake_info.pending_withdrawal_account = ctx.accounts.payout_token_account.key();
+            msg!("Payout account frozen: {} tokens held as pending withdrawal", amount);
+        } else {
+            let seeds = ctx.accounts.config.vault_signer_seeds();
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.payout_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        }
+
+        stake_info.amount = 0;
+        stake_info.unstake_at_maturity = false;
+        stake_info.maturity_payout_account = Pubkey::default();
+        stake_info.scheduled_withdrawal_at = 0;
+        burn_position_receipt(
+            stake_info,
+            ctx.accounts.receipt_mint.as_ref().map(|mint| mint.to_account_info()),
+            ctx.accounts.receipt_token_account.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.receipt_authority.as_ref().map(|authority| authority.to_account_info()),
+            None,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        msg!("Matured position unstaked: {} tokens sent to {}", amount, ctx.accounts.payout_token_account.key());
+        Ok(())
+    }
+
+    /// Dual staking pool: stake both SST and USDC.
+    pub fn stake_dual(ctx: Context<StakeDual>, sst_amount: u64, usdc_amount: u64) -> Result<()> {
+        require!(sst_amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        // Transfer SST.
+        let cpi_accounts_sst = Transfer {
+            from: ctx.accounts.staker_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts_sst), sst_amount)?;
+        // Transfer USDC.
+        let cpi_accounts_usdc = Transfer {
+            from: ctx.accounts.staker_usdc_token_account.to_account_info(),
+            to: ctx.accounts.vault_usdc_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts_usdc), usdc_amount)?;
+        stake_info.staker = ctx.accounts.staker.key();
+        stake_info.version = STAKE_INFO_VERSION;
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
+        stake_info.amount = stake_info.amount.checked_add(sst_amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_staked = stake_info.lifetime_staked.checked_add(sst_amount).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, clock.unix_timestamp);
+        stake_info.usdc_amount = stake_info.usdc_amount.checked_add(usdc_amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.last_staked_time = clock.unix_timestamp;
+        stake_info.auto_restake = false;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(sst_amount).ok_or(ErrorCode::Overflow)?;
+        protocol_stats.total_usdc_staked = protocol_stats.total_usdc_staked.checked_add(usdc_amount).ok_or(ErrorCode::Overflow)?;
+        record_pending_stake(protocol_stats, stake_info, sst_amount, clock.unix_timestamp)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        record_integration_deposit(ctx.accounts.integration_stats.as_deref_mut(), stake_info.staker, sst_amount, clock.unix_timestamp)?;
+        enforce_stake_caps(&ctx.accounts.config, stake_info.amount, protocol_stats.total_staked)?;
+        Ok(())
+    }
+
+    /// Deposit LP tokens for yield farming.
+    pub fn deposit_lp(ctx: Context<DepositLP>, lp_amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.accrue_lp_boost(Clock::get()?.unix_timestamp)?;
+        record_lp_deposit(
+            &mut ctx.accounts.lp_mints,
+            stake_info,
+            ctx.accounts.staker_lp_token_account.mint,
+            ctx.accounts.vault_lp_token_account.key(),
+            lp_amount,
+        )?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.staker_lp_token_account.to_account_info(),
+            to: ctx.accounts.vault_lp_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), lp_amount)?;
+        stake_info.lp_deposit = stake_info.lp_deposit.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lp_deposited_at = Clock::get()?.unix_timestamp;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_lp_deposited = protocol_stats.total_lp_deposited.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Flash loan: borrow tokens instantly against staked SST. Must be invoked directly by the
+    /// transaction, which must repay the loan with a later `repay_flash_loan` for the same position
+    /// and vault, or the loan fails; while the allowlist is enforced, only allowlisted programs may
+    /// be invoked in between.
+    pub fn flash_loan(ctx: Context<FlashLoan>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(stake_info.flash_loan_outstanding == 0, ErrorCode::FlashLoanOutstanding);
+        let max_flash = stake_info.amount.checked_div(2).ok_or(ErrorCode::Overflow)?;
+        require!(amount > 0 && amount <= max_flash, ErrorCode::BorrowLimitExceeded);
+        let allowlist_info = ctx.accounts.flash_loan_allowlist.to_account_info();
+        let allowlist = if allowlist_info.data_is_empty() {
+            None
+        } else {
+            require_keys_eq!(*allowlist_info.owner, crate::ID, ErrorCode::InvalidFlashLoanAllowlist);
+            Some(FlashLoanAllowlist::try_deserialize(&mut &allowlist_info.try_borrow_data()?[..])?)
+        };
+        require_flash_loan_repayment(
+            &ctx.accounts.instructions,
+            allowlist.as_ref(),
+            stake_info.key(),
+            ctx.accounts.vault_token_account.key(),
+        )?;
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.borrower_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        stake_info.flash_loan_outstanding = amount;
+        msg!("Flash loan of {} tokens", amount);
+        Ok(())
+    }
+
+    /// Repay the position's outstanding flash loan into the vault it was drawn from.
+    pub fn repay_flash_loan(ctx: Context<RepayFlashLoan>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let amount = stake_info.flash_loan_outstanding;
+        require!(amount > 0, ErrorCode::NoFlashLoan);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.borrower_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        stake_info.flash_loan_outstanding = 0;
+        msg!("Flash loan of {} tokens repaid", amount);
+        Ok(())
+    }
+
+    /// Slash stake as a penalty for Sybil attacks (governance config authority only), executing a
+    /// passed slash proposal that targets this staker.
+    pub fn slash_stake(ctx: Context<SlashStake>) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.ensure_executable(clock.unix_timestamp)?;
+        let slash = proposal.slash.as_mut().ok_or(ErrorCode::InvalidSlashProposal)?;
+        require!(
+            !slash.executed && slash.staker == ctx.accounts.staker.key(),
+            ErrorCode::InvalidSlashProposal
+        );
+        slash.executed = true;
+        let slash_percentage = slash.slash_percentage;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let slash_amount = mul_div_ceil(stake_info.amount as u128, slash_percentage as u128, 100)?;
+        stake_info.amount = stake_info.amount.checked_sub(slash_amount).ok_or(ErrorCode::Underflow)?;
+        stake_info.lifetime_penalties = stake_info.lifetime_penalties.checked_add(slash_amount).ok_or(ErrorCode::Overflow)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(slash_amount).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        msg!("Slashed {} tokens from stake", slash_amount);
+        Ok(())
+    }
+
+    /// Donate to the governance-backed insurance fund.
+    pub fn donate_insurance(ctx: Context<DonateInsurance>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.donor_token_account.to_account_info(),
+            to: ctx.accounts.insurance_fund_token_account.to_account_info(),
+            authority: ctx.accounts.donor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        insurance_fund.balance = insurance_fund.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        msg!("Donated {} tokens to the insurance fund", amount);
+        Ok(())
+    }
+
+    /// Initialize the global protocol statistics account.
+    pub fn initialize_protocol_stats(ctx: Context<InitializeProtocolStats>) -> Result<()> {
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        let clock = Clock::get()?;
+        protocol_stats.total_staked = 0;
+        protocol_stats.total_usdc_staked = 0;
+        protocol_stats.total_lp_deposited = 0;
+        protocol_stats.total_rewards_distributed = 0;
+        protocol_stats.last_snapshot_epoch = clock.epoch;
+        protocol_stats.last_snapshot_time = clock.unix_timestamp;
+        protocol_stats.rewards_at_last_snapshot = 0;
+        protocol_stats.snapshot_count = 0;
+        protocol_stats.reward_per_token_stored = 0;
+        protocol_stats.last_reward_update = clock.unix_timestamp;
+        protocol_stats.penalties_withheld = 0;
+        protocol_stats.total_rewards_funded = 0;
+        protocol_stats.rewards_outstanding = 0;
+        protocol_stats.reward_dust = 0;
+        protocol_stats.pending_stake_total = 0;
+        protocol_stats.reward_checkpoints = [RewardCheckpoint::default(); REWARD_CHECKPOINT_HISTORY];
+        protocol_stats.next_reward_checkpoint = 0;
+        protocol_stats.reward_cap_epoch = 0;
+        protocol_stats.epoch_rewards_paid = 0;
+        protocol_stats.total_borrowed = 0;
+        protocol_stats.borrow_index = BORROW_INDEX_ONE;
+        protocol_stats.borrow_index_updated_at = clock.unix_timestamp;
+        msg!("Protocol stats initialized at epoch {}", clock.epoch);
+        Ok(())
+    }
+
+    /// Snapshot the current protocol metrics into an append-only, per-epoch history account.
+    /// Permissionless: anyone may crank this once per epoch.
+    pub fn snapshot_protocol_stats(ctx: Context<SnapshotProtocolStats>, epoch: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(epoch == clock.epoch, ErrorCode::InvalidEpoch);
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        let snapshot = &mut ctx.accounts.snapshot;
+
+        let elapsed = clock.unix_timestamp
+            .checked_sub(protocol_stats.last_snapshot_time)
+            .ok_or(ErrorCode::Underflow)?;
+        let period_rewards = protocol_stats.total_rewards_distributed
+            .checked_sub(protocol_stats.rewards_at_last_snapshot)
+            .ok_or(ErrorCode::Underflow)?;
+        let apr_bps = calculate_apr_bps(period_rewards, protocol_stats.total_staked, elapsed)?;
+
+        snapshot.epoch = epoch;
+        snapshot.timestamp = clock.unix_timestamp;
+        snapshot.previous_epoch = protocol_stats.last_snapshot_epoch;
+        snapshot.total_staked = protocol_stats.total_staked;
+        snapshot.total_usdc_staked = protocol_stats.total_usdc_staked;
+        snapshot.total_lp_deposited = protocol_stats.total_lp_deposited;
+        snapshot.total_rewards_distributed = protocol_stats.total_rewards_distributed;
+        snapshot.period_rewards = period_rewards;
+        snapshot.apr_bps = apr_bps;
+
+        protocol_stats.last_snapshot_epoch = epoch;
+        protocol_stats.last_snapshot_time = clock.unix_timestamp;
+        protocol_stats.rewards_at_last_snapshot = protocol_stats.total_rewards_distributed;
+        protocol_stats.snapshot_count = protocol_stats.snapshot_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        msg!("Epoch {} snapshot: TVL {} SST, APR {} bps", epoch, snapshot.total_staked, apr_bps);
+        Ok(())
+    }
+
+    /// Initialize the protocol configuration. The signer becomes the config authority.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        penalty_destination: Pubkey,
+        early_unlock_penalty_bps: u64,
+    ) -> Result<()> {
+        require!(early_unlock_penalty_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.penalty_destination = penalty_destination;
+        config.early_unlock_penalty_bps = early_unlock_penalty_bps;
+        config.max_stake_per_wallet = 0;
+        config.global_stake_cap = 0;
+        config.min_stake_amount = 0;
+        config.min_withdrawal_amount = 0;
+        config.fee_discount_version = 1;
+        config.shadow_fee_discount_version = 0;
+        config.reward_vesting_enabled = false;
+        config.reward_vesting_haircut_bps = 0;
+        let (vault_authority, vault_bump) = Pubkey::find_program_address(&[b"vault".as_ref()], ctx.program_id);
+        config.vault_authority = vault_authority;
+        config.vault_generation = 0;
+        config.vault_bump = vault_bump;
+        config.lock_reward_multipliers_bps = [10_000, 15_000, 20_000];
+        config.incident_started_at = 0;
+        config.last_incident_started_at = 0;
+        config.incident_offset = 0;
+        config.reengagement_bonus = 0;
+        config.reengagement_budget = 0;
+        config.epoch_reward_cap = 0;
+        config.referral_bps = 0;
+        config.referral_vault = Pubkey::default();
+        config.borrow_vault = Pubkey::default();
+        config.borrow_rate_curve = BorrowRateCurve {
+            base_rate_bps: 200,
+            kink_utilization_bps: 8_000,
+            slope_low_bps: 800,
+            slope_high_bps: 10_000,
+        };
+        config.lp_lockup_period = 0;
+        config.lp_lock_multipliers_bps = [12_500, 15_000, 20_000];
+        config.flexible_collateral_factor_bps = DEFAULT_COLLATERAL_FACTOR_BPS;
+        config.lock_collateral_factors_bps = [5_000, 5_500, 6_500];
+        config.price_oracle = PriceOracle::default();
+        config.vip_threshold_usd = 0;
+        config.borrow_origination_fee_bps = 0;
+        config.vip_borrow_fee_discount_bps = 0;
+        config.global_borrow_cap = 0;
+        config.max_borrow_per_wallet = 0;
+        config.stake_vault = Pubkey::default();
+        config.reward_vault = Pubkey::default();
+        config.sst_mint = Pubkey::default();
+        config.ve_vault = Pubkey::default();
+        config.vip_threshold = DEFAULT_VIP_THRESHOLD;
+        config.guardian = Pubkey::default();
+        config.paused = false;
+        config.pending_authority = Pubkey::default();
+        msg!("Protocol config initialized");
+        Ok(())
+    }
+
+    /// Register the SST stake vault and reward vault, and optionally the vote-escrow vault (config
+    /// authority only). All must be SST accounts owned by the vault authority; the stake and
+    /// vote-escrow vaults cannot be replaced once set, and the vote-escrow vault is kept apart.
+    pub fn set_protocol_vaults(ctx: Context<SetProtocolVaults>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let stake_vault = &ctx.accounts.stake_vault;
+        require!(
+            (config.stake_vault == Pubkey::default() || config.stake_vault == stake_vault.key())
+                && stake_vault.key() != config.borrow_vault,
+            ErrorCode::InvalidStakeVault
+        );
+        config.stake_vault = stake_vault.key();
+        config.sst_mint = stake_vault.mint;
+        config.reward_vault = ctx.accounts.reward_vault.key();
+        if let Some(ve_vault) = &ctx.accounts.ve_vault {
+            require!(
+                (config.ve_vault == Pubkey::default() || config.ve_vault == ve_vault.key())
+                    && ve_vault.key() != config.stake_vault
+                    && ve_vault.key() != config.reward_vault
+                    && ve_vault.key() != config.borrow_vault,
+                ErrorCode::InvalidVeVault
+            );
+            config.ve_vault = ve_vault.key();
+        }
+        msg!("Stake vault {}, reward vault {}", config.stake_vault, config.reward_vault);
+        Ok(())
+    }
+
+    /// Update protocol configuration values (config authority only). Unset fields are left unchanged.
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigUpdate) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        if let Some(penalty_destination) = params.penalty_destination {
+            config.penalty_destination = penalty_destination;
+        }
+        if let Some(early_unlock_penalty_bps) = params.early_unlock_penalty_bps {
+            require!(early_unlock_penalty_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+            config.early_unlock_penalty_bps = early_unlock_penalty_bps;
+        }
+        if let Some(max_stake_per_wallet) = params.max_stake_per_wallet {
+            config.max_stake_per_wallet = max_stake_per_wallet;
+        }
+        if let Some(global_stake_cap) = params.global_stake_cap {
+            config.global_stake_cap = global_stake_cap;
+        }
+        if let Some(min_stake_amount) = params.min_stake_amount {
+            config.min_stake_amount = min_stake_amount;
+        }
+        if let Some(min_withdrawal_amount) = params.min_withdrawal_amount {
+            config.min_withdrawal_amount = min_withdrawal_amount;
+        }
+        if let Some(version) = params.fee_discount_version {
+            config.set_parameter(ConfigParameter::FeeDiscountVersion, version as u64)?;
+        }
+        if let Some(version) = params.shadow_fee_discount_version {
+            config.set_parameter(ConfigParameter::ShadowFeeDiscountVersion, version as u64)?;
+        }
+        if let Some(reward_vesting_enabled) = params.reward_vesting_enabled {
+            config.reward_vesting_enabled = reward_vesting_enabled;
+        }
+        if let Some(haircut_bps) = params.reward_vesting_haircut_bps {
+            config.set_parameter(ConfigParameter::RewardVestingHaircutBps, haircut_bps)?;
+        }
+        if let Some(multipliers) = params.lock_reward_multipliers_bps {
+            require!(
+                multipliers
+                    .iter()
+                    .all(|bps| *bps >= BPS_DENOMINATOR && *bps <= MAX_LOCK_REWARD_MULTIPLIER_BPS),
+                ErrorCode::InvalidRewardMultiplier
+            );
+            config.lock_reward_multipliers_bps = multipliers;
+        }
+        if let Some(reengagement_bonus) = params.reengagement_bonus {
+            config.reengagement_bonus = reengagement_bonus;
+        }
+        if let Some(epoch_reward_cap) = params.epoch_reward_cap {
+            config.epoch_reward_cap = epoch_reward_cap;
+        }
+        if let Some(referral_bps) = params.referral_bps {
+            require!(referral_bps <= MAX_REFERRAL_BPS, ErrorCode::InvalidBasisPoints);
+            config.referral_bps = referral_bps;
+        }
+        if let Some(referral_vault) = params.referral_vault {
+            config.referral_vault = referral_vault;
+        }
+        if let Some(borrow_vault) = params.borrow_vault {
+            // The lending pool must be its own SST account, never one holding principal or rewards.
+            if borrow_vault != Pubkey::default() {
+                let account = ctx.accounts.borrow_vault.as_ref().ok_or(ErrorCode::InvalidBorrowVault)?;
+                require!(
+                    account.key() == borrow_vault
+                        && borrow_vault != config.stake_vault
+                        && borrow_vault != config.reward_vault
+                        && borrow_vault != config.ve_vault,
+                    ErrorCode::InvalidBorrowVault
+                );
+            }
+            config.borrow_vault = borrow_vault;
+        }
+        if let Some(borrow_rate_curve) = params.borrow_rate_curve {
+            borrow_rate_curve.check_bounds()?;
+            config.borrow_rate_curve = borrow_rate_curve;
+        }
+        if let Some(lp_lockup_period) = params.lp_lockup_period {
+            require!(lp_lockup_period >= 0, ErrorCode::InvalidAmount);
+            config.lp_lockup_period = lp_lockup_period;
+        }
+        if let Some(multipliers) = params.lp_lock_multipliers_bps {
+            require!(
+                multipliers
+                    .iter()
+                    .all(|bps| *bps >= BPS_DENOMINATOR && *bps <= MAX_LOCK_REWARD_MULTIPLIER_BPS),
+                ErrorCode::InvalidRewardMultiplier
+            );
+            config.lp_lock_multipliers_bps = multipliers;
+        }
+        if let Some(flexible_factor_bps) = params.flexible_collateral_factor_bps {
+            config.flexible_collateral_factor_bps = flexible_factor_bps;
+        }
+        if let Some(lock_factors_bps) = params.lock_collateral_factors_bps {
+            config.lock_collateral_factors_bps = lock_factors_bps;
+        }
+        if let Some(price_oracle) = params.price_oracle {
+            require!(price_oracle.max_confidence_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+            config.price_oracle = price_oracle;
+        }
+        if let Some(vip_threshold_usd) = params.vip_threshold_usd {
+            config.vip_threshold_usd = vip_threshold_usd;
+        }
+        if let Some(fee_bps) = params.borrow_origination_fee_bps {
+            require!(fee_bps <= MAX_BORROW_ORIGINATION_FEE_BPS, ErrorCode::InvalidBasisPoints);
+            config.borrow_origination_fee_bps = fee_bps;
+        }
+        if let Some(discount_bps) = params.vip_borrow_fee_discount_bps {
+            require!(discount_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+            config.vip_borrow_fee_discount_bps = discount_bps;
+        }
+        if let Some(global_borrow_cap) = params.global_borrow_cap {
+            config.global_borrow_cap = global_borrow_cap;
+        }
+        if let Some(max_borrow_per_wallet) = params.max_borrow_per_wallet {
+            config.max_borrow_per_wallet = max_borrow_per_wallet;
+        }
+        if params.flexible_collateral_factor_bps.is_some() || params.lock_collateral_factors_bps.is_some() {
+            // Locked stake may never back less than flexible stake, and liquidation must stay below 100%.
+            let max_factor_bps = BPS_DENOMINATOR - LIQUIDATION_LTV_MARGIN_BPS;
+            require!(
+                config.flexible_collateral_factor_bps > 0
+                    && config
+                        .lock_collateral_factors_bps
+                        .iter()
+                        .all(|bps| *bps >= config.flexible_collateral_factor_bps && *bps < max_factor_bps),
+                ErrorCode::InvalidBasisPoints
+            );
+        }
+        msg!("Protocol config updated");
+        Ok(())
+    }
+
+    /// Early unlock: releases the still-locked portion of a locked position for a penalty
+    /// proportional to the lock time remaining. The penalty is sent to the configured destination
+    /// and the remaining stake becomes a regular (unlocked) position.
+    pub fn early_unlock(ctx: Context<EarlyUnlock>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let config = &ctx.accounts.config;
+        ensure_no_incident(config)?;
+        stake_info.apply_incident_offset(config);
+        let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        require!(stake_info.lock_period > 0, ErrorCode::PositionNotLocked);
+        require!(clock.unix_timestamp < stake_info.locked_until, ErrorCode::PositionNotLocked);
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+
+        let time_remaining = stake_info.locked_until
+            .checked_sub(clock.unix_timestamp)
+            .ok_or(ErrorCode::Underflow)?;
+        let locked_amount = stake_info.amount.saturating_sub(stake_info.unlocked_amount(clock.unix_timestamp));
+        let penalty = calculate_early_unlock_penalty(
+            locked_amount,
+            time_remaining,
+            stake_info.lock_period,
+            config.early_unlock_penalty_bps,
+        )?;
+
+        if penalty > 0 {
+            let seeds = ctx.accounts.config.vault_signer_seeds();
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.penalty_destination.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), penalty)?;
+        }
+
+        stake_info.amount = stake_info.amount.checked_sub(penalty).ok_or(ErrorCode::Underflow)?;
+        stake_info.lifetime_penalties = stake_info.lifetime_penalties.checked_add(penalty).ok_or(ErrorCode::Overflow)?;
+        stake_info.lock_period = 0;
+        stake_info.locked_until = clock.unix_timestamp;
+        // The released stake now only supports the flexible collateral factor.
+        stake_info.accrue_borrow_interest(ctx.accounts.protocol_stats.borrow_index)?;
+        let usdc = usdc_loan_terms(stake_info, config, ctx.accounts.usdc_market.as_deref(), ctx.accounts.price_feed.as_deref())?
+            .map(|(price, params)| (price, params.max_ltv_bps));
+        stake_info.ensure_collateralized(config, stake_info.amount, clock.unix_timestamp, usdc)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(penalty).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        msg!("Early unlock: {} locked tokens released, {} tokens penalty ({}s remaining)", locked_amount, penalty, time_remaining);
+        Ok(())
+    }
+
+    /// Initialize the insurance fund together with its underwriting receipt mint (config authority only).
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.balance = 0;
+        insurance_fund.total_shares = 0;
+        insurance_fund.receipt_mint = ctx.accounts.receipt_mint.key();
+        insurance_fund.token_account = ctx.accounts.insurance_fund_token_account.key();
+        msg!("Insurance fund initialized with receipt mint {}", insurance_fund.receipt_mint);
+        Ok(())
+    }
+
+    /// Underwrite the insurance fund: deposit SST and receive transferable receipt tokens
+    /// representing a pro-rata share of the fund. Donations grow the fund without minting
+    /// shares, so receipts appreciate over time; covered losses reduce what receipts redeem for.
+    pub fn underwrite_insurance(ctx: Context<UnderwriteInsurance>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        let shares = calculate_underwriting_shares(amount, insurance_fund.total_shares, insurance_fund.balance)?;
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underwriter_token_account.to_account_info(),
+            to: ctx.accounts.insurance_fund_token_account.to_account_info(),
+            authority: ctx.accounts.underwriter.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts), amount)?;
+
+        let bump = ctx.bumps.insurance_fund;
+        let seeds = &[b"insurance_fund".as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            to: ctx.accounts.underwriter_receipt_account.to_account_info(),
+            authority: insurance_fund.to_account_info(),
+        };
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), shares)?;
+
+        insurance_fund.balance = insurance_fund.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        insurance_fund.total_shares = insurance_fund.total_shares.checked_add(shares).ok_or(ErrorCode::Overflow)?;
+        msg!("Underwrote {} tokens for {} receipt shares", amount, shares);
+        Ok(())
+    }
+
+    /// Redeem underwriting receipts: burns the shares and pays out their pro-rata portion of the
+    /// current fund balance, net of any losses the fund has covered.
+    pub fn redeem_insurance_receipt(ctx: Context<RedeemInsuranceReceipt>, shares: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        let payout = calculate_underwriting_redemption(shares, insurance_fund.total_shares, insurance_fund.balance)?;
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            from: ctx.accounts.underwriter_receipt_account.to_account_info(),
+            authority: ctx.accounts.underwriter.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::burn(CpiContext::new(cpi_program.clone(), cpi_accounts), shares)?;
+
+        if payout > 0 {
+            let bump = ctx.bumps.insurance_fund;
+            let seeds = &[b"insurance_fund".as_ref(), &[bump]];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.insurance_fund_token_account.to_account_info(),
+                to: ctx.accounts.underwriter_token_account.to_account_info(),
+                authority: insurance_fund.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), payout)?;
+        }
+
+        insurance_fund.balance = insurance_fund.balance.checked_sub(payout).ok_or(ErrorCode::Underflow)?;
+        insurance_fund.total_shares = insurance_fund.total_shares.checked_sub(shares).ok_or(ErrorCode::Underflow)?;
+        msg!("Redeemed {} receipt shares for {} tokens", shares, payout);
+        Ok(())
+    }
+
+    /// Draw on the insurance fund to cover a protocol loss (config authority only).
+    /// Underwriters absorb the loss first through a lower redemption value per share.
+    pub fn cover_insurance_loss(ctx: Context<CoverInsuranceLoss>, amount: u64) -> Result<()> {
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        require!(amount <= insurance_fund.balance, ErrorCode::InsuranceFundDepleted);
+        let bump = ctx.bumps.insurance_fund;
+        let seeds = &[b"insurance_fund".as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.insurance_fund_token_account.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: insurance_fund.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        insurance_fund.balance = insurance_fund.balance.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        msg!("Insurance fund covered a loss of {} tokens", amount);
+        Ok(())
+    }
+
+    /// Lock ladder builder: splits one deposit into equal tranches, each locked for its own
+    /// period (e.g. 30/90/180 days), so a withdrawal ladder can be built in a single call.
+    /// Any rounding remainder is added to the final tranche. Tranches earn no rewards, so they
+    /// stay out of `total_staked`, but count toward the wallet cap with the staker's position.
+    pub fn build_lock_ladder(ctx: Context<BuildLockLadder>, amount: u64, lock_periods: Vec<u64>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(
+            !lock_periods.is_empty() && lock_periods.len() <= MAX_LADDER_TRANCHES,
+            ErrorCode::InvalidTrancheCount
+        );
+        for lock_period in lock_periods.iter() {
+            require!(ALLOWED_LOCK_PERIODS.contains(lock_period), ErrorCode::InvalidLockPeriod);
+        }
+        let tranche_count = lock_periods.len() as u64;
+        let tranche_amount = amount.checked_div(tranche_count).ok_or(ErrorCode::Underflow)?;
+        require!(tranche_amount > 0, ErrorCode::InvalidAmount);
+        require!(tranche_amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
+        let remainder = amount.checked_rem(tranche_count).ok_or(ErrorCode::Underflow)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.staker_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let clock = Clock::get()?;
+        let ladder = &mut ctx.accounts.ladder;
+        ladder.staker = ctx.accounts.staker.key();
+        ladder.tranches = Vec::with_capacity(lock_periods.len());
+        for (i, lock_period) in lock_periods.iter().enumerate() {
+            let mut tranche_total = tranche_amount;
+            if i == lock_periods.len() - 1 {
+                tranche_total = tranche_total.checked_add(remainder).ok_or(ErrorCode::Overflow)?;
+            }
+            ladder.tranches.push(LadderTranche {
+                amount: tranche_total,
+                lock_period: *lock_period,
+                locked_until: clock.unix_timestamp.checked_add(*lock_period as i64).ok_or(ErrorCode::Overflow)?,
+                withdrawn: false,
+            });
+        }
+
+        let stake_info = ctx.accounts.stake_info.to_account_info();
+        let staked = if stake_info.data_is_empty() {
+            0
+        } else {
+            require_keys_eq!(*stake_info.owner, crate::ID, ErrorCode::InvalidStakeAccount);
+            StakeInfo::try_deserialize(&mut &stake_info.try_borrow_data()?[..])?.amount
+        };
+        enforce_stake_caps(
+            &ctx.accounts.config,
+            staked.checked_add(amount).ok_or(ErrorCode::Overflow)?,
+            ctx.accounts.protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?,
+        )?;
+        msg!("Lock ladder built: {} tokens across {} tranches", amount, tranche_count);
+        Ok(())
+    }
+
+    /// Withdraw a matured lock ladder tranche back to the staker.
+    pub fn withdraw_ladder_tranche(ctx: Context<WithdrawLadderTranche>, index: u8) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let ladder = &mut ctx.accounts.ladder;
+        let clock = Clock::get()?;
+        let tranche = ladder.tranches.get_mut(index as usize).ok_or(ErrorCode::InvalidTrancheIndex)?;
+        require!(!tranche.withdrawn, ErrorCode::TrancheAlreadyWithdrawn);
+        require!(clock.unix_timestamp >= tranche.locked_until, ErrorCode::TokensLocked);
+        tranche.withdrawn = true;
+        let amount = tranche.amount;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        msg!("Ladder tranche {} withdrawn: {} tokens", index, amount);
+        Ok(())
+    }
+
+    /// Atomically claim pending rewards and re-stake them into the caller's existing position.
+    /// Rewards move straight from the reward vault to the staking vault and the current lock
+    /// terms are left untouched, so compounding never routes through the staker's wallet.
+    pub fn claim_and_restake(ctx: Context<ClaimAndRestake>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+        stake_info.locked = true;
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let (requested_base, requested_boost, _) = calculate_claim_reward(stake_info.accrued_rewards, stake_info.take_lp_boost(clock.unix_timestamp)?)?;
+        let (base_reward, lp_boost, total_reward) = cap_claim(
+            &mut ctx.accounts.protocol_stats,
+            &ctx.accounts.config,
+            stake_info,
+            requested_base,
+            requested_boost,
+            ctx.accounts.reward_vault.amount,
+            clock.unix_timestamp,
+        )?;
+        emit!(RewardsClaimed {
+            staker: stake_info.staker,
+            base_reward,
+            lp_boost,
+            total_reward,
+            compounded: true,
+        });
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_reward)?;
+
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
+        stake_info.amount = stake_info.amount.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_rewards = stake_info.lifetime_rewards.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        stake_info.locked = false;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_distributed = protocol_stats.total_rewards_distributed
+            .checked_add(total_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        credit_referral(
+            &ctx.accounts.config,
+            &ctx.accounts.stake_info,
+            ctx.accounts.referral_account.as_deref_mut(),
+            ctx.accounts.referral_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            total_reward,
+            ctx.accounts.reward_vault.amount.saturating_sub(total_reward),
+        )?;
+        msg!("Rewards claimed and restaked: {} tokens (Base: {}, LP Boost: {})", total_reward, base_reward, lp_boost);
+        Ok(())
+    }
+
+    /// Register a tiny unlock beacon for a locked position so notification services can scan
+    /// upcoming unlocks (by day bucket) without fetching every StakeInfo.
+    pub fn register_unlock_beacon(ctx: Context<RegisterUnlockBeacon>) -> Result<()> {
+        let stake_info = &ctx.accounts.stake_info;
+        require!(stake_info.lock_period > 0, ErrorCode::PositionNotLocked);
+        let beacon = &mut ctx.accounts.beacon;
+        beacon.staker = ctx.accounts.staker.key();
+        beacon.stake_info = stake_info.key();
+        beacon.unlock_at = stake_info.locked_until;
+        beacon.unlock_day = unlock_day_bucket(stake_info.locked_until);
+        msg!("Unlock beacon registered for {}", beacon.unlock_at);
+        Ok(())
+    }
+
+    /// Crank: re-syncs a beacon with its position's current unlock time (e.g. after a relock).
+    pub fn sync_unlock_beacon(ctx: Context<SyncUnlockBeacon>) -> Result<()> {
+        let locked_until = ctx.accounts.stake_info.locked_until;
+        let beacon = &mut ctx.accounts.beacon;
+        beacon.unlock_at = locked_until;
+        beacon.unlock_day = unlock_day_bucket(locked_until);
+        msg!("Unlock beacon synced to {}", beacon.unlock_at);
+        Ok(())
+    }
+
+    /// Crank: closes an expired beacon, returning its rent to the staker.
+    pub fn prune_unlock_beacon(ctx: Context<PruneUnlockBeacon>) -> Result<()> {
+        let clock = Clock::get()?;
+        let beacon = &ctx.accounts.beacon;
+        require!(clock.unix_timestamp >= beacon.unlock_at, ErrorCode::LockNotMatured);
+        require!(clock.unix_timestamp >= ctx.accounts.stake_info.locked_until, ErrorCode::LockNotMatured);
+        msg!("Unlock beacon pruned for {}", beacon.staker);
+        Ok(())
+    }
+
+    /// Offer to lease this position's VIP fee tier to `lessee` for `duration` seconds at `fee` SST.
+    pub fn offer_fee_tier_lease(
+        ctx: Context<OfferFeeTierLease>,
+        lessee: Pubkey,
+        fee: u64,
+        duration: i64,
+    ) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(
+            is_vip(&ctx.accounts.config, ctx.accounts.stake_info.amount, ctx.accounts.price_feed.as_deref())?,
+            ErrorCode::NotVipStaker
+        );
+        require!(duration > 0, ErrorCode::InvalidLeaseTerms);
+        require!(lessee != ctx.accounts.lessor.key(), ErrorCode::InvalidLeaseTerms);
+        let fee_lease = &mut ctx.accounts.fee_lease;
+        fee_lease.lessor = ctx.accounts.lessor.key();
+        fee_lease.lessee = lessee;
+        fee_lease.fee = fee;
+        fee_lease.duration = duration;
+        fee_lease.starts_at = 0;
+        fee_lease.expires_at = 0;
+        fee_lease.accepted = false;
+        msg!("Fee tier lease offered to {} for {}s at {} tokens", lessee, duration, fee);
+        Ok(())
+    }
+
+    /// Accept a fee-tier lease offer, paying the lease fee to the lessor. The term starts now.
+    pub fn accept_fee_tier_lease(ctx: Context<AcceptFeeTierLease>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let fee_lease = &mut ctx.accounts.fee_lease;
+        let clock = Clock::get()?;
+        require!(!fee_lease.accepted, ErrorCode::LeaseAlreadyAccepted);
+        if fee_lease.fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.lessee_token_account.to_account_info(),
+                to: ctx.accounts.lessor_token_account.to_account_info(),
+                authority: ctx.accounts.lessee.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), fee_lease.fee)?;
+        }
+        fee_lease.accepted = true;
+        fee_lease.starts_at = clock.unix_timestamp;
+        fee_lease.expires_at = clock.unix_timestamp.checked_add(fee_lease.duration).ok_or(ErrorCode::Overflow)?;
+        msg!("Fee tier lease accepted until {}", fee_lease.expires_at);
+        Ok(())
+    }
+
+    /// Revoke (or close) a fee-tier lease. Revoking during an active term refunds the lessee
+    /// the unused portion of the fee pro-rata; the lease account's rent returns to the lessor.
+    pub fn revoke_fee_tier_lease(ctx: Context<RevokeFeeTierLease>) -> Result<()> {
+        let fee_lease = &ctx.accounts.fee_lease;
+        let clock = Clock::get()?;
+        let refund = if fee_lease.is_active(clock.unix_timestamp) {
+            let remaining = fee_lease.expires_at.checked_sub(clock.unix_timestamp).ok_or(ErrorCode::Underflow)?;
+            mul_div_floor(fee_lease.fee as u128, remaining as u128, fee_lease.duration as u128)?
+        } else {
+            0
+        };
+        if refund > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.lessor_token_account.to_account_info(),
+                to: ctx.accounts.lessee_token_account.to_account_info(),
+                authority: ctx.accounts.lessor.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), refund)?;
+        }
+        msg!("Fee tier lease revoked, {} tokens refunded", refund);
+        Ok(())
+    }
+
+    /// Claim an unstake payout that was escrowed because the staker's token account was frozen.
+    /// It is paid to the registered withdrawal address, or else to the account it was held for.
+    pub fn claim_pending_withdrawal(ctx: Context<ClaimPendingWithdrawal>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        let from_state = stake_info.state(now);
+        let amount = stake_info.pending_withdrawal;
+        require!(amount > 0, ErrorCode::NoPendingWithdrawal);
+        let co_signer = ctx.accounts.second_signer.as_ref().map(|signer| signer.key());
+        require!(stake_info.second_factor_satisfied(amount, co_signer, now), ErrorCode::SecondSignerRequired);
+        require!(!ctx.accounts.staker_token_account.is_frozen(), ErrorCode::TokenAccountFrozen);
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        stake_info.pending_withdrawal = 0;
+        stake_info.pending_withdrawal_account = Pubkey::default();
+        finish_transition(stake_info, from_state, now)?;
+        msg!("Pending withdrawal of {} tokens claimed", amount);
+        Ok(())
+    }
+
+    /// Set the token account that receives this position's claimed rewards (e.g. a DAO treasury).
+    /// Omitting the beneficiary account clears it.
+    pub fn set_reward_beneficiary(ctx: Context<SetRewardBeneficiary>) -> Result<()> {
+        let beneficiary = match &ctx.accounts.beneficiary_token_account {
+            Some(beneficiary_token_account) => beneficiary_token_account.key(),
+            None => Pubkey::default(),
+        };
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.reward_beneficiary = beneficiary;
+        msg!("Reward beneficiary set to: {}", beneficiary);
+        Ok(())
+    }
+
+    /// Register the position's withdrawal address. The first registration applies immediately;
+    /// later changes are staged behind a timelock so a compromised key cannot redirect funds at once.
+    pub fn set_withdrawal_address(ctx: Context<SetWithdrawalAddress>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        let new_address = ctx.accounts.withdrawal_token_account.key();
+        require!(!stake_info.withdrawal_address_immutable, ErrorCode::WithdrawalAddressImmutable);
+        if stake_info.withdrawal_address == Pubkey::default() {
+            stake_info.withdrawal_address = new_address;
+            msg!("Withdrawal address set to: {}", new_address);
+        } else {
+            stake_info.pending_withdrawal_address = new_address;
+            stake_info.withdrawal_address_change_at = clock.unix_timestamp
+                .checked_add(WITHDRAWAL_ADDRESS_CHANGE_DELAY)
+                .ok_or(ErrorCode::Overflow)?;
+            msg!("Withdrawal address change to {} staged until {}", new_address, stake_info.withdrawal_address_change_at);
+        }
+        Ok(())
+    }
+
+    /// Apply a staged withdrawal address change once its timelock has elapsed.
+    pub fn apply_withdrawal_address_change(ctx: Context<ApplyWithdrawalAddressChange>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(stake_info.pending_withdrawal_address != Pubkey::default(), ErrorCode::NoPendingAddressChange);
+        require!(!stake_info.withdrawal_address_immutable, ErrorCode::WithdrawalAddressImmutable);
+        require!(clock.unix_timestamp >= stake_info.withdrawal_address_change_at, ErrorCode::TimelockNotElapsed);
+        stake_info.withdrawal_address = stake_info.pending_withdrawal_address;
+        stake_info.pending_withdrawal_address = Pubkey::default();
+        stake_info.withdrawal_address_change_at = 0;
+        msg!("Withdrawal address changed to: {}", stake_info.withdrawal_address);
+        Ok(())
+    }
+
+    /// Permanently freeze the current withdrawal address, discarding any staged change.
+    pub fn lock_withdrawal_address(ctx: Context<ApplyWithdrawalAddressChange>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(stake_info.withdrawal_address != Pubkey::default(), ErrorCode::WithdrawalAddressMismatch);
+        stake_info.withdrawal_address_immutable = true;
+        stake_info.pending_withdrawal_address = Pubkey::default();
+        stake_info.withdrawal_address_change_at = 0;
+        msg!("Withdrawal address locked: {}", stake_info.withdrawal_address);
+        Ok(())
+    }
+
+    /// Register (or clear, with the default pubkey) an operator for a cold-wallet position.
+    /// Only the owner can call this; operators cannot unstake or change the operator.
+    pub fn set_operator(ctx: Context<SetOperator>, operator: Pubkey) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.operator = operator;
+        msg!("Operator set to: {}", operator);
+        Ok(())
+    }
+
+    /// Enable, change or disable (default pubkey) two-factor unstaking. Once enabled, changes
+    /// must be co-signed by the current second signer unless the bypass timelock has elapsed.
+    pub fn configure_two_factor(ctx: Context<ConfigureTwoFactor>, second_signer: Pubkey, threshold: u64) -> Result<()> {
+        let co_signer = ctx.accounts.current_second_signer.as_ref().map(|signer| signer.key());
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        if stake_info.second_signer != Pubkey::default() {
+            require!(
+                stake_info.second_factor_satisfied(u64::MAX, co_signer, clock.unix_timestamp),
+                ErrorCode::SecondSignerRequired
+            );
+        }
+        stake_info.second_signer = second_signer;
+        stake_info.two_factor_threshold = threshold;
+        stake_info.two_factor_bypass_at = 0;
+        msg!("Two-factor unstake configured: signer {}, threshold {}", second_signer, threshold);
+        Ok(())
+    }
+
+    /// Start the two-factor bypass timelock for a lost second key.
+    pub fn request_two_factor_bypass(ctx: Context<RequestTwoFactorBypass>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(stake_info.second_signer != Pubkey::default(), ErrorCode::TwoFactorNotEnabled);
+        stake_info.two_factor_bypass_at = clock.unix_timestamp
+            .checked_add(TWO_FACTOR_BYPASS_DELAY)
+            .ok_or(ErrorCode::Overflow)?;
+        msg!("Two-factor bypass available from {}", stake_info.two_factor_bypass_at);
+        Ok(())
+    }
+
+    /// Cancel a pending two-factor bypass (second signer only), e.g. if the owner key was compromised.
+    pub fn cancel_two_factor_bypass(ctx: Context<CancelTwoFactorBypass>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.two_factor_bypass_at = 0;
+        msg!("Two-factor bypass cancelled");
+        Ok(())
+    }
+
+    /// Rage quit: a staker who voted against a passed proposal may withdraw their whole position
+    /// during the timelock, ignoring locks and early-unstake penalties but forfeiting accrued rewards.
+    pub fn rage_quit(ctx: Context<RageQuit>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(ctx.accounts.vote_record.choice == VoteChoice::Against, ErrorCode::RageQuitNotEligible);
+        require!(ctx.accounts.proposal.in_timelock(clock.unix_timestamp), ErrorCode::RageQuitNotEligible);
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let from_state = stake_info.state(clock.unix_timestamp);
+        require!(!stake_info.has_debt(), ErrorCode::OutstandingBorrow);
+        require!(stake_info.unvested_gift_amount(clock.unix_timestamp) == 0, ErrorCode::GiftStillVesting);
+        let amount = stake_info.amount;
+        let co_signer = ctx.accounts.second_signer.as_ref().map(|signer| signer.key());
+        require!(
+            stake_info.second_factor_satisfied(amount, co_signer, clock.unix_timestamp),
+            ErrorCode::SecondSignerRequired
+        );
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        stake_info.amount = 0;
+        stake_info.lock_period = 0;
+        stake_info.locked_until = clock.unix_timestamp;
+        // Rewards accrued on the position are forfeited.
+        stake_info.accrued_rewards = 0;
+        stake_info.reward_token_accrued = [0; MAX_REWARD_TOKENS];
+        stake_info.last_staked_time = clock.unix_timestamp;
+        stake_info.auto_relock = false;
+        stake_info.unstake_at_maturity = false;
+        stake_info.scheduled_withdrawal_at = 0;
+        burn_position_receipt(
+            stake_info,
+            ctx.accounts.receipt_mint.as_ref().map(|mint| mint.to_account_info()),
+            ctx.accounts.receipt_token_account.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.receipt_authority.as_ref().map(|authority| authority.to_account_info()),
+            Some(ctx.accounts.staker.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        clamp_pending_stake(protocol_stats, stake_info, stake_info.amount)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        msg!("Rage quit: {} tokens withdrawn without penalty", amount);
+        Ok(())
+    }
+
+    /// Gift stake to another wallet, creating their position if needed. An optional vesting
+    /// duration keeps the gifted tokens from being unstaked until they vest linearly.
+    pub fn gift_stake(ctx: Context<GiftStake>, amount: u64, vesting_duration: i64) -> Result<()> {
+        require!(amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
+        require!(vesting_duration >= 0, ErrorCode::InvalidAmount);
+        ensure_no_incident(&ctx.accounts.config)?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.gifter_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.gifter.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let clock = Clock::get()?;
+        let from_state = stake_info.state(clock.unix_timestamp);
+        if stake_info.staker == Pubkey::default() {
+            stake_info.staker = ctx.accounts.recipient.key();
+            stake_info.version = STAKE_INFO_VERSION;
+            stake_info.last_staked_time = clock.unix_timestamp;
+            stake_info.locked_until = clock.unix_timestamp;
+        }
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
+        stake_info.amount = stake_info.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_staked = stake_info.lifetime_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, clock.unix_timestamp);
+        if vesting_duration > 0 {
+            require!(stake_info.unvested_gift_amount(clock.unix_timestamp) == 0, ErrorCode::GiftStillVesting);
+            stake_info.gift_vesting_amount = amount;
+            stake_info.gift_vesting_start = clock.unix_timestamp;
+            stake_info.gift_vesting_end = clock.unix_timestamp.checked_add(vesting_duration).ok_or(ErrorCode::Overflow)?;
+        }
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_pending_stake(protocol_stats, stake_info, amount, clock.unix_timestamp)?;
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        record_integration_deposit(ctx.accounts.integration_stats.as_deref_mut(), stake_info.staker, amount, clock.unix_timestamp)?;
+        enforce_stake_caps(&ctx.accounts.config, stake_info.amount, protocol_stats.total_staked)?;
+        msg!("Gifted {} tokens to {} (vesting {}s)", amount, stake_info.staker, vesting_duration);
+        Ok(())
+    }
+
+    /// Add stake to an existing position. The lock and staking clock are left as they are; a
+    /// position flagged as dormant earns the re-engagement bonus.
+    pub fn top_up_stake(ctx: Context<TopUpStake>, amount: u64) -> Result<()> {
+        require!(amount >= ctx.accounts.config.min_stake_amount, ErrorCode::BelowMinimumStake);
+        ensure_no_incident(&ctx.accounts.config)?;
+        let stake_info = &mut ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+        stake_info.apply_incident_offset(&ctx.accounts.config);
+        let from_state = stake_info.state(clock.unix_timestamp);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.staker_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        accrue_loyalty(&mut ctx.accounts.loyalty_account, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let topped_up = stake_info.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_season_score(
+            ctx.accounts.season.as_mut(),
+            ctx.accounts.season_score.as_mut(),
+            stake_info,
+            topped_up,
+            clock.unix_timestamp,
+        )?;
+        stake_info.checkpoint_voting_power(clock.unix_timestamp);
+        stake_info.amount = stake_info.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.lifetime_staked = stake_info.lifetime_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_achievements(stake_info, clock.unix_timestamp);
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        record_pending_stake(protocol_stats, stake_info, amount, clock.unix_timestamp)?;
+        grant_reengagement_bonus(&mut ctx.accounts.config, protocol_stats, stake_info)?;
+        record_activity(stake_info, clock.unix_timestamp);
+        sync_boost_enrollments(ctx.remaining_accounts, stake_info, clock.unix_timestamp)?;
+        finish_transition(stake_info, from_state, clock.unix_timestamp)?;
+        enforce_stake_caps(&ctx.accounts.config, stake_info.amount, protocol_stats.total_staked)?;
+        msg!("Position of {} topped up with {} tokens", stake_info.staker, amount);
+        Ok(())
+    }
+
+    /// Fund the re-engagement bonus budget (config authority only). The tokens go into the reward
+    /// vault and count as funded rewards once granted.
+    pub fn fund_reengagement_budget(ctx: Context<FundReengagementBudget>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_funded = protocol_stats.total_rewards_funded
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let config = &mut ctx.accounts.config;
+        config.reengagement_budget = config.reengagement_budget.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        msg!("Re-engagement budget funded with {} tokens ({} available)", amount, config.reengagement_budget);
+        Ok(())
+    }
+
+    /// Governance instruction: creates a parameter-change proposal. The current ("before") value
+    /// of each parameter is captured alongside the proposed ("after") value.
+    pub fn create_parameter_proposal(
+        ctx: Context<CreateParameterProposal>,
+        description_hash: [u8; 32],
+        description_uri: String,
+        changes: Vec<ParameterChangeRequest>,
+    ) -> Result<()> {
+        require!(
+            !changes.is_empty() && changes.len() <= MAX_PARAMETER_CHANGES,
+            ErrorCode::InvalidParameterChanges
+        );
+        ctx.accounts.governance_config.description_policy.validate(&description_hash, &description_uri)?;
+        let config = &ctx.accounts.config;
+        let mut preview = (**config).clone();
+        let mut parameter_changes = Vec::with_capacity(changes.len());
+        for change in changes.iter() {
+            // Validate the new value the same way execution will.
+            preview.set_parameter(change.parameter, change.new_value)?;
+            parameter_changes.push(ParameterChange {
+                parameter: change.parameter,
+                old_value: config.parameter_value(change.parameter),
+                new_value: change.new_value,
+            });
+        }
+
+        ensure_proposer_stake(&ctx.accounts.governance_config, ctx.accounts.proposer_stake_info.as_deref())?;
+        let proposer = ctx.accounts.proposer.key();
+        let index = ctx.accounts.proposer_record.take_index(proposer)?;
+        let bond = take_proposal_bond(
+            &ctx.accounts.governance_config,
+            &ctx.accounts.proposer,
+            ctx.accounts.proposer_token_account.as_deref(),
+            ctx.accounts.bond_vault.as_deref(),
+            &ctx.accounts.token_program,
+        )?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.init(proposer, index, description_hash, description_uri, bond, Clock::get()?.unix_timestamp)?;
+        proposal.parameter_changes = parameter_changes;
+        msg!("New parameter-change proposal created with {} changes", proposal.parameter_changes.len());
+        Ok(())
+    }
+
+    /// View: returns (via return data) the protocol config as it would look after the proposal's
+    /// parameter changes are applied, logging each before/after pair.
+    pub fn preview_config_after(ctx: Context<PreviewConfigAfter>) -> Result<()> {
+        let mut preview = (*ctx.accounts.config).clone();
+        for change in ctx.accounts.proposal.parameter_changes.iter() {
+            msg!(
+                "{:?}: {} (proposed from {}) -> {}",
+                change.parameter,
+                preview.parameter_value(change.parameter),
+                change.old_value,
+                change.new_value
+            );
+            preview.set_parameter(change.parameter, change.new_value)?;
+        }
+        set_return_data(&preview.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Initialize the governance config with its proposal description policy. The signer becomes
+    /// the governance config authority.
+    pub fn initialize_governance_config(
+        ctx: Context<InitializeGovernanceConfig>,
+        description_policy: DescriptionPolicy,
+    ) -> Result<()> {
+        description_policy.check_bounds()?;
+        let governance_config = &mut ctx.accounts.governance_config;
+        governance_config.authority = ctx.accounts.authority.key();
+        governance_config.description_policy = description_policy;
+        governance_config.quorum_bps = 0;
+        governance_config.approval_threshold_bps = DEFAULT_APPROVAL_THRESHOLD_BPS;
+        governance_config.proposal_bond = 0;
+        governance_config.bond_vault = Pubkey::default();
+        governance_config.min_proposer_stake = 0;
+        governance_config.execution_delay = DEFAULT_EXECUTION_DELAY;
+        governance_config.pending_authority = Pubkey::default();
+        msg!("Governance config initialized");
+        Ok(())
+    }
+
+    /// Nominate a new governance authority (governance config authority only). The transfer only
+    /// completes once the nominee signs `accept_authority`; `Pubkey::default()` clears the nomination.
+    pub fn propose_new_authority(ctx: Context<SetDescriptionPolicy>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.governance_config.pending_authority = new_authority;
+        msg!("Governance authority transfer proposed to {}", new_authority);
+        Ok(())
+    }
+
+    /// Complete a governance authority transfer (nominated authority only).
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let governance_config = &mut ctx.accounts.governance_config;
+        let previous = governance_config.authority;
+        governance_config.authority = governance_config.pending_authority;
+        governance_config.pending_authority = Pubkey::default();
+        emit!(AuthorityTransferred {
+            config: governance_config.key(),
+            previous_authority: previous,
+            new_authority: governance_config.authority,
+        });
+        msg!("Governance authority transferred to {}", governance_config.authority);
+        Ok(())
+    }
+
+    /// Set the quorum and approval threshold checked by `finalize_proposal` (governance config authority only).
+    pub fn set_voting_thresholds(ctx: Context<SetDescriptionPolicy>, quorum_bps: u64, approval_threshold_bps: u64) -> Result<()> {
+        require!(
+            quorum_bps <= BPS_DENOMINATOR && approval_threshold_bps < BPS_DENOMINATOR,
+            ErrorCode::InvalidBasisPoints
+        );
+        let governance_config = &mut ctx.accounts.governance_config;
+        governance_config.quorum_bps = quorum_bps;
+        governance_config.approval_threshold_bps = approval_threshold_bps;
+        msg!("Voting thresholds set: quorum {} bps, approval {} bps", quorum_bps, approval_threshold_bps);
+        Ok(())
+    }
+
+    /// Set the delay between a proposal passing and its execution (governance config authority only).
+    pub fn set_execution_delay(ctx: Context<SetDescriptionPolicy>, execution_delay: i64) -> Result<()> {
+        require!(execution_delay >= 0, ErrorCode::InvalidAmount);
+        ctx.accounts.governance_config.execution_delay = execution_delay;
+        msg!("Proposal execution delay set to {} seconds", execution_delay);
+        Ok(())
+    }
+
+    /// Veto a passed proposal during its execution delay (governance config authority only).
+    pub fn veto_proposal(ctx: Context<VetoProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Passed, ErrorCode::ProposalNotPassed);
+        require!(Clock::get()?.unix_timestamp < proposal.executable_at, ErrorCode::VetoWindowClosed);
+        proposal.status = ProposalStatus::Vetoed;
+        msg!("Proposal {} vetoed", proposal.key());
+        Ok(())
+    }
+
+    /// Set the security council's members and the number of them needed to veto (governance
+    /// config authority only).
+    pub fn set_security_council(ctx: Context<SetSecurityCouncil>, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require!(
+            members.len() <= MAX_COUNCIL_MEMBERS
+                && threshold > 0
+                && threshold as usize <= members.len()
+                && members.iter().enumerate().all(|(i, member)| !members[..i].contains(member)),
+            ErrorCode::InvalidSecurityCouncil
+        );
+        let council = &mut ctx.accounts.security_council;
+        council.members = members;
+        council.threshold = threshold;
+        msg!("Security council set: {} of {} members", threshold, council.members.len());
+        Ok(())
+    }
+
+    /// Replace the programs that may be invoked between a flash loan and its repayment
+    /// (governance config authority only). With `enforced` unset any program may be invoked.
+    pub fn set_flash_loan_allowlist(
+        ctx: Context<SetFlashLoanAllowlist>,
+        programs: Vec<Pubkey>,
+        enforced: bool,
+    ) -> Result<()> {
+        require!(
+            programs.len() <= MAX_FLASH_LOAN_PROGRAMS
+                && programs.iter().enumerate().all(|(i, program)| !programs[..i].contains(program)),
+            ErrorCode::InvalidFlashLoanAllowlist
+        );
+        let allowlist = &mut ctx.accounts.flash_loan_allowlist;
+        allowlist.programs = programs;
+        allowlist.enforced = enforced;
+        msg!("Flash-loan allowlist set: {} programs, enforced: {}", allowlist.programs.len(), enforced);
+        Ok(())
+    }
+
+    /// Approve a veto of a passed proposal during its execution delay (security council member only).
+    /// The proposal is vetoed once `threshold` current members have approved. The proposal's
+    /// instruction accounts are passed in index order in `remaining_accounts`; a proposal that
+    /// overturns a council veto cannot itself be vetoed by the council.
+    pub fn council_veto(ctx: Context<CouncilVeto>) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        let council = &ctx.accounts.security_council;
+        require!(council.members.contains(&member), ErrorCode::Unauthorized);
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Passed, ErrorCode::ProposalNotPassed);
+        require!(now < proposal.executable_at, ErrorCode::VetoWindowClosed);
+        require!(
+            !overturns_council_veto(proposal.key(), proposal.instruction_count, ctx.remaining_accounts)?,
+            ErrorCode::VetoWindowClosed
+        );
+
+        let veto = &mut ctx.accounts.council_veto;
+        // A veto overturned by governance cannot be raised again.
+        require!(!veto.overturned, ErrorCode::VetoWindowClosed);
+        veto.proposal = proposal.key();
+        require!(!veto.approvals.contains(&member), ErrorCode::AlreadyApproved);
+        veto.approvals.push(member);
+        // Approvals from members since removed by `set_security_council` no longer count.
+        let approvals = veto.approvals.iter().filter(|approver| council.members.contains(approver)).count();
+        if approvals >= council.threshold as usize {
+            veto.vetoed_at = now;
+            proposal.status = ProposalStatus::Vetoed;
+            emit!(ProposalVetoedByCouncil {
+                proposal: proposal.key(),
+                approvals: approvals as u8,
+            });
+            msg!("Proposal vetoed by the security council");
+        } else {
+            msg!("Council veto approval {} of {}", approvals, council.threshold);
+        }
+        Ok(())
+    }
+
+    /// Governance review of a council veto: reinstates the proposal as passed and immediately
+    /// executable. Only callable by the governance signer, i.e. through `execute_proposal`.
+    pub fn overturn_council_veto(ctx: Context<OverturnCouncilVeto>) -> Result<()> {
+        let veto = &mut ctx.accounts.council_veto;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.status == ProposalStatus::Vetoed && veto.vetoed_at != 0 && !veto.overturned,
+            ErrorCode::ProposalNotPassed
+        );
+        veto.overturned = true;
+        proposal.status = ProposalStatus::Passed;
+        proposal.executable_at = Clock::get()?.unix_timestamp;
+        msg!("Council veto of {} overturned by governance", proposal.key());
+        Ok(())
+    }
+
+    /// Approve fast-tracking an active proposal as an emergency (security council members only).
+    /// Once the council threshold is reached, voting closes `EMERGENCY_VOTING_PERIOD` after creation
+    /// but no sooner than `EMERGENCY_MIN_VOTING_WINDOW` from now, and the proposal needs
+    /// `EMERGENCY_APPROVAL_THRESHOLD_BPS` of the decided votes to pass.
+    pub fn fast_track_proposal(ctx: Context<FastTrackProposal>) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        require!(ctx.accounts.security_council.members.contains(&member), ErrorCode::Unauthorized);
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Active, ErrorCode::ProposalAlreadyFinalized);
+        require!(now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+        require!(!proposal.emergency && proposal.options.is_empty(), ErrorCode::InvalidEmergencyProposal);
+
+        let fast_track = &mut ctx.accounts.fast_track;
+        fast_track.proposal = proposal.key();
+        require!(!fast_track.approvals.contains(&member), ErrorCode::AlreadyApproved);
+        fast_track.approvals.push(member);
+        let council = &ctx.accounts.security_council;
+        let approvals = fast_track.approvals.iter().filter(|approver| council.members.contains(approver)).count();
+        if approvals >= council.threshold as usize {
+            let emergency_end = proposal.created_at.checked_add(EMERGENCY_VOTING_PERIOD).ok_or(ErrorCode::Overflow)?;
+            let min_end = now.checked_add(EMERGENCY_MIN_VOTING_WINDOW).ok_or(ErrorCode::Overflow)?;
+            proposal.emergency = true;
+            proposal.voting_ends_at = proposal.voting_ends_at.min(emergency_end.max(min_end));
+            emit!(ProposalFastTracked {
+                proposal: proposal.key(),
+                approvals: approvals as u8,
+                voting_ends_at: proposal.voting_ends_at,
+            });
+            msg!("Proposal fast-tracked as an emergency");
+        } else {
+            msg!("Fast-track approval {} of {}", approvals, council.threshold);
+        }
+        Ok(())
+    }
+
+    /// Set the minimum stake a proposer must hold (governance config authority only).
+    pub fn set_min_proposer_stake(ctx: Context<SetDescriptionPolicy>, min_proposer_stake: u64) -> Result<()> {
+        ctx.accounts.governance_config.min_proposer_stake = min_proposer_stake;
+        msg!("Minimum proposer stake set to {}", min_proposer_stake);
+        Ok(())
+    }
+
+    /// Set the SST bond required to create a proposal and the account that holds it (governance
+    /// config authority only). The bond vault is fixed once set and kept apart from the protocol vaults.
+    pub fn set_proposal_bond(ctx: Context<SetProposalBond>, proposal_bond: u64) -> Result<()> {
+        let bond_vault = ctx.accounts.bond_vault.key();
+        let governance_config = &mut ctx.accounts.governance_config;
+        // Bonds sit in their own account so they never mix with staked principal or rewards.
+        require!(
+            (governance_config.bond_vault == Pubkey::default() || governance_config.bond_vault == bond_vault)
+                && !ctx.accounts.config.pinned_vaults().contains(&bond_vault),
+            ErrorCode::InvalidBondVault
+        );
+        governance_config.proposal_bond = proposal_bond;
+        governance_config.bond_vault = bond_vault;
+        msg!("Proposal bond set to {}", proposal_bond);
+        Ok(())
+    }
+
+    /// Permissionless: after finalization, refund the proposal bond to the proposer if the vote
+    /// reached quorum, or slash it to the treasury vault otherwise.
+    pub fn settle_proposal_bond(ctx: Context<SettleProposalBond>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status != ProposalStatus::Active, ErrorCode::VotingStillOpen);
+        require!(!proposal.bond_settled && proposal.bond > 0, ErrorCode::InvalidAmount);
+        let destination = if proposal.quorum_reached {
+            ctx.accounts.proposer_token_account.to_account_info()
+        } else {
+            ctx.accounts.treasury_vault.to_account_info()
+        };
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.bond_vault.to_account_info(),
+            to: destination,
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), proposal.bond)?;
+        proposal.bond_settled = true;
+        if proposal.quorum_reached {
+            msg!("Proposal bond of {} refunded", proposal.bond);
+        } else {
+            msg!("Proposal bond of {} slashed to the treasury", proposal.bond);
+        }
+        Ok(())
+    }
+
+    /// Grow the governance config to the current layout, zero-filling appended fields (the payer
+    /// covers extra rent). Permissionless.
+    pub fn migrate_governance_config(ctx: Context<MigrateGovernanceConfig>) -> Result<()> {
+        let info = ctx.accounts.governance_config.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::Unauthorized);
+        grow_account(&info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + GovernanceConfig::LEN)?;
+        // Deserializing also verifies the account discriminator.
+        GovernanceConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        msg!("Governance config migrated to {} bytes", 8 + GovernanceConfig::LEN);
+        Ok(())
+    }
+
+    /// Grow the protocol config to the current layout, zero-filling appended fields (the payer
+    /// covers extra rent). Permissionless.
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let info = ctx.accounts.config.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::Unauthorized);
+        grow_account(&info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + ProtocolConfig::LEN)?;
+        // Deserializing also verifies the account discriminator.
+        let mut config = ProtocolConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        if config.vault_generation == 0 {
+            // Configs created before the bump was recorded stored 0 for the original vault PDA.
+            config.vault_bump = receipt_authority_bump(ctx.program_id);
+            config.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+        msg!("Protocol config migrated to {} bytes", 8 + ProtocolConfig::LEN);
+        Ok(())
+    }
+
+    /// Rewrite a USDC market created before the per-wallet cap into the current layout, the cap
+    /// starting unlimited (the payer covers extra rent). Permissionless.
+    pub fn migrate_usdc_market(ctx: Context<MigrateUsdcMarket>) -> Result<()> {
+        let info = ctx.accounts.usdc_market.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::Unauthorized);
+        require!(info.data_len() == 8 + LegacyUsdcMarket::LEN, ErrorCode::AlreadyMigrated);
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..8] == UsdcMarket::DISCRIMINATOR,
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            LegacyUsdcMarket::deserialize(&mut &data[8..])
+                .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?
+        };
+        grow_account(&info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + UsdcMarket::LEN)?;
+        let usdc_market = UsdcMarket {
+            usdc_vault: legacy.usdc_vault,
+            params: UsdcMarketParams {
+                borrow_cap: legacy.borrow_cap,
+                max_ltv_bps: legacy.max_ltv_bps,
+                liquidation_ltv_bps: legacy.liquidation_ltv_bps,
+                rate_curve: legacy.rate_curve,
+                max_borrow_per_wallet: 0,
+            },
+            total_borrowed: legacy.total_borrowed,
+            borrow_index: legacy.borrow_index,
+            borrow_index_updated_at: legacy.borrow_index_updated_at,
+        };
+        usdc_market.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        msg!("USDC market migrated to {} bytes", 8 + UsdcMarket::LEN);
+        Ok(())
+    }
+
+    /// Replace the proposal description policy (governance config authority only).
+    pub fn set_description_policy(
+        ctx: Context<SetDescriptionPolicy>,
+        description_policy: DescriptionPolicy,
+    ) -> Result<()> {
+        description_policy.check_bounds()?;
+        ctx.accounts.governance_config.description_policy = description_policy;
+        msg!("Proposal description policy updated");
+        Ok(())
+    }
+
+    /// Migrate a `StakeInfo` account written by an older program version to the current layout.
+    /// Fields are only ever appended, so the account is grown (zero-filling the new fields, the
+    /// payer covering extra rent) and stamped with `STAKE_INFO_VERSION`. Permissionless.
+    pub fn migrate_stake_info(ctx: Context<MigrateStakeInfo>) -> Result<()> {
+        let info = ctx.accounts.stake_info.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidStakeAccount);
+        grow_account(&info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + StakeInfo::LEN)?;
+
+        // Deserializing also verifies the account discriminator.
+        let mut stake_info = StakeInfo::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require!(stake_info.version < STAKE_INFO_VERSION, ErrorCode::AlreadyMigrated);
+        let from_version = stake_info.version;
+        stake_info.version = STAKE_INFO_VERSION;
+        stake_info.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        msg!("StakeInfo migrated from v{} to v{}", from_version, STAKE_INFO_VERSION);
+        Ok(())
+    }
+
+    /// Initialize the per-epoch emission schedule. Its parameters are fixed at creation so the
+    /// emission of every epoch is a pure, publicly verifiable function of them.
+    pub fn initialize_epoch_emission(
+        ctx: Context<InitializeEpochEmission>,
+        initial_rate: u64,
+        decay_bps: u64,
+        floor_rate: u64,
+    ) -> Result<()> {
+        require!(decay_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+        require!(floor_rate <= initial_rate, ErrorCode::InvalidEmissionSchedule);
+        let schedule = &mut ctx.accounts.epoch_emission_schedule;
+        schedule.initial_rate = initial_rate;
+        schedule.decay_bps = decay_bps;
+        schedule.floor_rate = floor_rate;
+        schedule.start_epoch = Clock::get()?.epoch;
+        msg!(
+            "Emission schedule: {} per epoch from epoch {}, decaying {} bps per epoch to a floor of {}",
+            initial_rate,
+            schedule.start_epoch,
+            decay_bps,
+            floor_rate
+        );
+        Ok(())
+    }
+
+    /// View: returns (via return data) the scheduled emission for `epoch`.
+    pub fn get_epoch_emission(ctx: Context<GetEpochEmission>, epoch: u64) -> Result<()> {
+        let emission = ctx.accounts.epoch_emission_schedule.emission_for_epoch(epoch)?;
+        msg!("Emission for epoch {}: {}", epoch, emission);
+        set_return_data(&emission.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Set the program IDs positions may be exported to and imported from (config authority only).
+    /// `Pubkey::default()` disables the corresponding direction.
+    pub fn configure_migration(
+        ctx: Context<ConfigureMigration>,
+        target_program: Pubkey,
+        source_program: Pubkey,
+    ) -> Result<()> {
+        let migration_gate = &mut ctx.accounts.migration_gate;
+        migration_gate.target_program = target_program;
+        migration_gate.source_program = source_program;
+        msg!("Migration gate: export to {}, import from {}", target_program, source_program);
+        Ok(())
+    }
+
+    /// Export a position to the approved target program without unstaking. The raw `StakeInfo`
+    /// bytes and their hash are recorded in a `PositionExport` proof, the staked tokens move to
+    /// the target program's vault, and the local position is closed.
+    pub fn export_position(ctx: Context<ExportPosition>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let migration_gate = &ctx.accounts.migration_gate;
+        require!(migration_gate.target_program != Pubkey::default(), ErrorCode::MigrationDisabled);
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, &mut ctx.accounts.stake_info, Clock::get()?.unix_timestamp)?;
+        clamp_pending_stake(&mut ctx.accounts.protocol_stats, &mut ctx.accounts.stake_info, 0)?;
+        // The whole stake leaves, so release its boost campaign weight as if it were unstaked.
+        let exported_amount = ctx.accounts.stake_info.amount;
+        ctx.accounts.stake_info.amount = 0;
+        sync_boost_enrollments(ctx.remaining_accounts, &mut ctx.accounts.stake_info, Clock::get()?.unix_timestamp)?;
+        ctx.accounts.stake_info.amount = exported_amount;
+        let stake_info = &ctx.accounts.stake_info;
+        require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+        let from_state = stake_info.state(Clock::get()?.unix_timestamp);
+        require!(
+            from_state.can_transition_to(PositionState::Closed),
+            ErrorCode::InvalidStateTransition
+        );
+        require!(
+            !stake_info.has_debt()
+                && stake_info.pending_withdrawal == 0
+                && stake_info.usdc_amount == 0
+                && stake_info.lp_deposit == 0,
+            ErrorCode::PositionNotExportable
+        );
+        let amount = stake_info.amount;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.target_vault_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        // The receipt is burned before the state is recorded, so the export carries none.
+        burn_position_receipt(
+            &mut ctx.accounts.stake_info,
+            ctx.accounts.receipt_mint.as_ref().map(|mint| mint.to_account_info()),
+            ctx.accounts.receipt_token_account.as_ref().map(|account| account.to_account_info()),
+            ctx.accounts.receipt_authority.as_ref().map(|authority| authority.to_account_info()),
+            Some(ctx.accounts.staker.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+        let state = ctx.accounts.stake_info.try_to_vec()?;
+        let position_export = &mut ctx.accounts.position_export;
+        position_export.staker = ctx.accounts.staker.key();
+        position_export.source_program = crate::ID;
+        position_export.target_program = migration_gate.target_program;
+        position_export.exported_at = Clock::get()?.unix_timestamp;
+        position_export.state_hash = hash(&state).to_bytes();
+        position_export.state = state;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        msg!("Position of {} tokens exported to {}", amount, migration_gate.target_program);
+        Ok(())
+    }
+
+    /// Import a position exported by the approved source program. The export proof must be owned
+    /// by the source program at the staker's export PDA and its state must match the recorded hash;
+    /// the state is then restored as-is, so lock terms carry over unchanged.
+    pub fn import_position(ctx: Context<ImportPosition>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let source_program = ctx.accounts.migration_gate.source_program;
+        require!(source_program != Pubkey::default(), ErrorCode::MigrationDisabled);
+        let export_info = ctx.accounts.position_export.to_account_info();
+        require_keys_eq!(*export_info.owner, source_program, ErrorCode::InvalidMigrationProof);
+        let (expected_export, _) = Pubkey::find_program_address(
+            &[b"position_export", ctx.accounts.staker.key().as_ref()],
+            &source_program,
+        );
+        require_keys_eq!(export_info.key(), expected_export, ErrorCode::InvalidMigrationProof);
+
+        let position_export = PositionExport::try_deserialize(&mut &export_info.try_borrow_data()?[..])?;
+        require!(
+            position_export.staker == ctx.accounts.staker.key()
+                && position_export.source_program == source_program
+                && position_export.target_program == crate::ID
+                && hash(&position_export.state).to_bytes() == position_export.state_hash,
+            ErrorCode::InvalidMigrationProof
+        );
+
+        // Older layouts are a prefix of the current one; missing trailing fields default to zero.
+        let mut state = position_export.state.clone();
+        require!(state.len() <= StakeInfo::LEN, ErrorCode::InvalidMigrationProof);
+        state.resize(StakeInfo::LEN, 0);
+        let mut imported = StakeInfo::deserialize(&mut &state[..])?;
+        require!(imported.staker == ctx.accounts.staker.key(), ErrorCode::InvalidMigrationProof);
+        imported.version = STAKE_INFO_VERSION;
+        // The source program's accumulator index is meaningless here; start accruing from now.
+        let now = Clock::get()?.unix_timestamp;
+        // The imported stake arrives now, so it carries no voting power on earlier proposals.
+        imported.voting_checkpoints = [VotingCheckpoint::default(); VOTING_CHECKPOINTS];
+        imported.next_voting_checkpoint = 0;
+        imported.push_voting_checkpoint(VotingCheckpoint { amount: 0, last_staked_time: now, recorded_at: now });
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.update_reward_per_token(&ctx.accounts.emission_schedule, now)?;
+        imported.reward_per_token_paid = protocol_stats.reward_per_token_stored;
+        imported.rewards_settled_at = now;
+        imported.season_score = Pubkey::default();
+        imported.lp_boost_accrued = 0;
+        imported.lp_boost_updated_at = now;
+        let reward_tokens = &mut ctx.accounts.reward_tokens;
+        reward_tokens.update(protocol_stats.total_staked, now)?;
+        imported.reward_token_paid = [0; MAX_REWARD_TOKENS];
+        imported.reward_token_accrued = [0; MAX_REWARD_TOKENS];
+        imported.pending_stake = 0;
+        imported.pending_stake_epoch = 0;
+        imported.incident_offset_applied = ctx.accounts.config.incident_offset;
+        imported.last_activity_at = now;
+        imported.dormant_flagged_at = 0;
+        for (slot, token) in reward_tokens.tokens.iter().enumerate() {
+            imported.reward_token_paid[slot] = token.reward_per_token_stored;
+        }
+        let amount = imported.amount;
+        ctx.accounts.stake_info.set_inner(imported);
+
+        let position_import = &mut ctx.accounts.position_import;
+        position_import.position_export = export_info.key();
+        position_import.imported_at = Clock::get()?.unix_timestamp;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        msg!("Position of {} tokens imported from {}", amount, source_program);
+        Ok(())
+    }
+
+    /// Rebuild the owner's position index from the position accounts passed as remaining accounts.
+    /// Each entry must be a program-owned stake, ladder or fee-lease account at the owner's PDA,
+    /// so clients can enumerate a wallet's holdings by fetching one account.
+    pub fn sync_position_index(ctx: Context<SyncPositionIndex>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require!(ctx.remaining_accounts.len() <= MAX_INDEXED_POSITIONS, ErrorCode::TooManyPositions);
+        let mut positions: Vec<IndexedPosition> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidIndexedPosition);
+            let kind = PositionKind::from_discriminator(&info.try_borrow_data()?)
+                .ok_or(ErrorCode::InvalidIndexedPosition)?;
+            let (expected, _) = Pubkey::find_program_address(&[kind.seed(), owner.as_ref()], &crate::ID);
+            require_keys_eq!(info.key(), expected, ErrorCode::InvalidIndexedPosition);
+            require!(
+                !positions.iter().any(|position| position.address == expected),
+                ErrorCode::InvalidIndexedPosition
+            );
+            positions.push(IndexedPosition { kind, address: expected });
+        }
+
+        let position_index = &mut ctx.accounts.position_index;
+        position_index.owner = owner;
+        position_index.positions = positions;
+        position_index.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Position index for {} holds {} positions", owner, position_index.positions.len());
+        Ok(())
+    }
+
+
+    /// Create the reward emission schedule consumed by reward accrual (config authority only).
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+        params: EmissionScheduleParams,
+    ) -> Result<()> {
+        let schedule = &mut ctx.accounts.emission_schedule;
+        schedule.apply(params)?;
+        msg!(
+            "Emission schedule: {} tokens/s from {} until {} with {} step-downs",
+            schedule.tokens_per_second,
+            schedule.start_time,
+            schedule.end_time,
+            schedule.step_downs.len()
+        );
+        Ok(())
+    }
+
+    /// Replace the reward emission schedule (config authority only). Rewards are checkpointed under
+    /// the old schedule first, so the change only affects emissions from now on.
+    pub fn update_emission_schedule(
+        ctx: Context<UpdateEmissionSchedule>,
+        params: EmissionScheduleParams,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.protocol_stats.update_reward_per_token(&ctx.accounts.emission_schedule, now)?;
+        ctx.accounts.emission_schedule.apply(params)?;
+        msg!("Emission schedule updated at {}", now);
+        Ok(())
+    }
+
+    /// Initialize the opt-in staker lottery (config authority only). `penalty_share_bps` of the
+    /// early-unstake penalties withheld in the vault is routed to the lottery vault.
+    pub fn initialize_lottery(ctx: Context<InitializeLottery>, round_duration: i64, penalty_share_bps: u64) -> Result<()> {
+        require!(round_duration > 0, ErrorCode::InvalidAmount);
+        require!(penalty_share_bps <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.round = 0;
+        lottery.status = LotteryStatus::Open;
+        lottery.round_duration = round_duration;
+        lottery.draw_at = Clock::get()?.unix_timestamp.checked_add(round_duration).ok_or(ErrorCode::Overflow)?;
+        lottery.total_weight = 0;
+        lottery.penalty_share_bps = penalty_share_bps;
+        lottery.vault = ctx.accounts.lottery_vault.key();
+        lottery.randomness_account = Pubkey::default();
+        lottery.winning_weight = 0;
+        lottery.prize = 0;
+        lottery.claim_deadline = 0;
+        msg!("Lottery initialized: first draw at {}", lottery.draw_at);
+        Ok(())
+    }
+
+    /// Crank: splits withheld early-unstake penalties. The lottery's share moves into the lottery
+    /// vault, the rest into the reward vault, where it is shared by the remaining stakers.
+    pub fn fund_lottery(ctx: Context<FundLottery>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        let withheld = protocol_stats.penalties_withheld;
+        let amount = mul_div_floor(
+            withheld as u128,
+            ctx.accounts.lottery.penalty_share_bps as u128,
+            BPS_DENOMINATOR as u128,
+        )?;
+        let redistributed = withheld.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
+        protocol_stats.penalties_withheld = 0;
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        if amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                to: ctx.accounts.lottery_vault.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        }
+        if redistributed > 0 {
+            if ctx.accounts.reward_vault.key() != ctx.accounts.vault_token_account.key() {
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), redistributed)?;
+            }
+            protocol_stats.update_reward_per_token(&ctx.accounts.emission_schedule, now)?;
+            let bonus_pool = &mut ctx.accounts.bonus_pool;
+            bonus_pool.total_collected = bonus_pool.total_collected.checked_add(redistributed).ok_or(ErrorCode::Overflow)?;
+            bonus_pool.undistributed = bonus_pool.undistributed.checked_add(redistributed).ok_or(ErrorCode::Overflow)?;
+            redistribute_bonus_pool(bonus_pool, protocol_stats)?;
+        }
+        msg!("Penalties split: {} tokens to the lottery, {} tokens to stakers", amount, redistributed);
+        Ok(())
+    }
+
+    /// Opt into the current lottery round. Entry weight is stake × stake-days at entry time; the
+    /// entered stake must still be held when the prize is claimed.
+    pub fn enter_lottery(ctx: Context<EnterLottery>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let lottery = &mut ctx.accounts.lottery;
+        let stake_info = &ctx.accounts.stake_info;
+        let clock = Clock::get()?;
+        require!(lottery.status == LotteryStatus::Open, ErrorCode::LotteryNotOpen);
+        let staked_days = (clock.unix_timestamp.saturating_sub(stake_info.last_staked_time).max(0) / (24 * 60 * 60)) as u128;
+        let weight = (stake_info.amount as u128).checked_mul(staked_days).ok_or(ErrorCode::Overflow)?;
+        require!(weight > 0, ErrorCode::NoLotteryWeight);
+
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.round = lottery.round;
+        ticket.staker = stake_info.staker;
+        ticket.range_start = lottery.total_weight;
+        ticket.weight = weight;
+        ticket.amount = stake_info.amount;
+        lottery.total_weight = lottery.total_weight.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        msg!("Lottery entry for round {} with weight {}", lottery.round, weight);
+        Ok(())
+    }
+
+    /// Crank: once the round is due, commits to a fresh Switchboard randomness account.
+    /// The randomness must have been committed in the previous slot so nobody can know it yet.
+    pub fn request_lottery_draw(ctx: Context<RequestLotteryDraw>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        let clock = Clock::get()?;
+        require!(lottery.status == LotteryStatus::Open, ErrorCode::LotteryNotOpen);
+        require!(clock.unix_timestamp >= lottery.draw_at, ErrorCode::LotteryDrawNotDue);
+        require_keys_eq!(
+            *ctx.accounts.randomness_account.owner,
+            switchboard_on_demand::get_switchboard_on_demand_program_id(),
+            ErrorCode::InvalidRandomnessAccount
+        );
+        let randomness = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        require!(randomness.seed_slot == clock.slot.saturating_sub(1), ErrorCode::RandomnessExpired);
+        lottery.randomness_account = ctx.accounts.randomness_account.key();
+        lottery.status = LotteryStatus::Drawing;
+        msg!("Lottery round {} draw requested", lottery.round);
+        Ok(())
+    }
+
+    /// Crank: reads the revealed randomness and fixes the winning weight for the round.
+    /// A round without entries rolls its prize over to the next round.
+    pub fn settle_lottery_draw(ctx: Context<SettleLotteryDraw>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        let clock = Clock::get()?;
+        require!(lottery.status == LotteryStatus::Drawing, ErrorCode::LotteryNotOpen);
+        require_keys_eq!(
+            ctx.accounts.randomness_account.key(),
+            lottery.randomness_account,
+            ErrorCode::InvalidRandomnessAccount
+        );
+        let randomness = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        let value = randomness.get_value(&clock).map_err(|_| ErrorCode::RandomnessNotResolved)?;
+
+        if lottery.total_weight == 0 {
+            lottery.start_next_round(clock.unix_timestamp)?;
+            msg!("Lottery round had no entries; prize rolls over");
+            return Ok(());
+        }
+        let mut random_bytes = [0u8; 16];
+        random_bytes.copy_from_slice(&value[..16]);
+        lottery.winning_weight = u128::from_le_bytes(random_bytes) % lottery.total_weight;
+        lottery.prize = ctx.accounts.lottery_vault.amount;
+        lottery.status = LotteryStatus::Settled;
+        lottery.claim_deadline = clock.unix_timestamp.checked_add(LOTTERY_CLAIM_WINDOW).ok_or(ErrorCode::Overflow)?;
+        msg!("Lottery round {} settled: prize {}", lottery.round, lottery.prize);
+        Ok(())
+    }
+
+    /// Winner claims the prize with the ticket covering the winning weight; opens the next round.
+    /// The winning position must still hold the stake it entered with.
+    pub fn claim_lottery_prize(ctx: Context<ClaimLotteryPrize>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let lottery = &ctx.accounts.lottery;
+        let ticket = &ctx.accounts.ticket;
+        require!(lottery.status == LotteryStatus::Settled, ErrorCode::LotteryNotSettled);
+        require!(Clock::get()?.unix_timestamp <= lottery.claim_deadline, ErrorCode::LotteryPrizeExpired);
+        require!(ticket.round == lottery.round, ErrorCode::NotLotteryWinner);
+        require!(ctx.accounts.stake_info.amount >= ticket.amount, ErrorCode::LotteryStakeWithdrawn);
+        require!(
+            lottery.winning_weight >= ticket.range_start
+                && lottery.winning_weight < ticket.range_start.saturating_add(ticket.weight),
+            ErrorCode::NotLotteryWinner
+        );
+        let prize = lottery.prize;
+
+        let bump = ctx.bumps.lottery;
+        let seeds = &[b"lottery".as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.lottery_vault.to_account_info(),
+            to: ctx.accounts.winner_token_account.to_account_info(),
+            authority: ctx.accounts.lottery.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), prize)?;
+
+        let lottery = &mut ctx.accounts.lottery;
+        let round = lottery.round;
+        lottery.start_next_round(Clock::get()?.unix_timestamp)?;
+        msg!("Lottery round {} prize of {} claimed by {}", round, prize, ctx.accounts.winner.key());
+        Ok(())
+    }
+
+    /// Crank: opens the next round once a settled prize has gone unclaimed past its claim window.
+    /// The prize stays in the vault and rolls over.
+    pub fn expire_lottery_prize(ctx: Context<ExpireLotteryPrize>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        let now = Clock::get()?.unix_timestamp;
+        require!(lottery.status == LotteryStatus::Settled, ErrorCode::LotteryNotSettled);
+        require!(now > lottery.claim_deadline, ErrorCode::LotteryClaimWindowOpen);
+        let round = lottery.round;
+        lottery.start_next_round(now)?;
+        msg!("Lottery round {} prize unclaimed; it rolls over", round);
+        Ok(())
+    }
+
+    /// Crank: records time-based milestones (e.g. one year staked continuously) for a position.
+    pub fn check_achievements(ctx: Context<CheckAchievements>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        record_achievements(stake_info, Clock::get()?.unix_timestamp);
+        msg!("Achievements: {:#b}", stake_info.achievements);
+        Ok(())
+    }
+
+    /// Top up the reward vault. Permissionless: anyone (typically the treasury) may fund rewards.
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_funded = protocol_stats.total_rewards_funded
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        protocol_stats.rewards_outstanding = protocol_stats.rewards_outstanding
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        emit!(RewardsFunded {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            rewards_outstanding: protocol_stats.rewards_outstanding,
+        });
+        msg!("Reward vault funded with {} tokens ({} outstanding)", amount, protocol_stats.rewards_outstanding);
+        Ok(())
+    }
+
+    /// Open a bonus campaign (config authority only). The budget is moved into the reward vault
+    /// up front and counted as funded rewards; `distributor` may then credit it to positions.
+    pub fn create_bonus_campaign(
+        ctx: Context<CreateBonusCampaign>,
+        campaign_id: u64,
+        distributor: Pubkey,
+        budget: u64,
+    ) -> Result<()> {
+        require!(budget > 0, ErrorCode::InvalidAmount);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), budget)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_funded = protocol_stats.total_rewards_funded
+            .checked_add(budget)
+            .ok_or(ErrorCode::Overflow)?;
+        protocol_stats.rewards_outstanding = protocol_stats.rewards_outstanding
+            .checked_add(budget)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let campaign = &mut ctx.accounts.bonus_campaign;
+        campaign.campaign_id = campaign_id;
+        campaign.distributor = distributor;
+        campaign.budget = budget;
+        campaign.distributed = 0;
+        campaign.created_at = Clock::get()?.unix_timestamp;
+        msg!("Bonus campaign {} opened with a budget of {} tokens", campaign_id, budget);
+        Ok(())
+    }
+
+    /// Credit bonus rewards from a campaign budget to up to `MAX_BONUS_RECIPIENTS` positions, passed
+    /// as writable `StakeInfo` accounts in `remaining_accounts` in the same order as `amounts`.
+    /// Bonuses land in `accrued_rewards` and are paid out by the next claim.
+    pub fn distribute_bonus(ctx: Context<DistributeBonus>, amounts: Vec<u64>) -> Result<()> {
+        require!(
+            !amounts.is_empty()
+                && amounts.len() <= MAX_BONUS_RECIPIENTS
+                && amounts.len() == ctx.remaining_accounts.len(),
+            ErrorCode::InvalidBonusRecipients
+        );
+        let campaign = &mut ctx.accounts.bonus_campaign;
+        let mut total: u64 = 0;
+        for (info, amount) in ctx.remaining_accounts.iter().zip(amounts.iter()) {
+            require!(*amount > 0, ErrorCode::InvalidAmount);
+            require!(info.is_writable, ErrorCode::InvalidBonusRecipients);
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidBonusRecipients);
+            let mut data = info.try_borrow_mut_data()?;
+            let mut stake_info = StakeInfo::try_deserialize(&mut &data[..])?;
+            stake_info.accrued_rewards = stake_info.accrued_rewards.checked_add(*amount).ok_or(ErrorCode::Overflow)?;
+            stake_info.try_serialize(&mut &mut data[..])?;
+            total = total.checked_add(*amount).ok_or(ErrorCode::Overflow)?;
+            emit!(BonusDistributed {
+                campaign_id: campaign.campaign_id,
+                staker: stake_info.staker,
+                amount: *amount,
+            });
+        }
+
+        campaign.distributed = campaign.distributed.checked_add(total).ok_or(ErrorCode::Overflow)?;
+        require!(campaign.distributed <= campaign.budget, ErrorCode::CampaignBudgetExceeded);
+        msg!(
+            "Bonus campaign {}: {} tokens credited to {} positions ({} of {} used)",
+            campaign.campaign_id,
+            total,
+            amounts.len(),
+            campaign.distributed,
+            campaign.budget
+        );
+        Ok(())
+    }
+
+    /// Create the partner reward-token registry (config authority only).
+    pub fn initialize_reward_token_registry(ctx: Context<InitializeRewardTokenRegistry>) -> Result<()> {
+        ctx.accounts.reward_tokens.tokens = Vec::new();
+        msg!("Reward token registry initialized");
+        Ok(())
+    }
+
+    /// Register an additional reward mint with its own vault and emission rate (config authority
+    /// only). Registry slots are never reused, so per-position checkpoints stay aligned with them.
+    pub fn register_reward_token(ctx: Context<RegisterRewardToken>, tokens_per_second: u64) -> Result<()> {
+        let mint = ctx.accounts.reward_mint.key();
+        let reward_tokens = &mut ctx.accounts.reward_tokens;
+        require!(reward_tokens.tokens.len() < MAX_REWARD_TOKENS, ErrorCode::TooManyRewardTokens);
+        require!(
+            !reward_tokens.tokens.iter().any(|token| token.mint == mint),
+            ErrorCode::RewardTokenAlreadyRegistered
+        );
+        reward_tokens.tokens.push(RewardToken {
+            mint,
+            vault: ctx.accounts.reward_token_vault.key(),
+            tokens_per_second,
+            reward_per_token_stored: 0,
+            last_update_time: Clock::get()?.unix_timestamp,
+            active: true,
+            reward_dust: 0,
+        });
+        msg!(
+            "Reward token {} registered in slot {} at {} tokens/s",
+            mint,
+            reward_tokens.tokens.len() - 1,
+            tokens_per_second
+        );
+        Ok(())
+    }
+
+    /// Change a reward token's emission rate or pause it (config authority only). Accrual is
+    /// checkpointed under the old settings first.
+    pub fn update_reward_token(
+        ctx: Context<UpdateRewardToken>,
+        index: u8,
+        tokens_per_second: u64,
+        active: bool,
+    ) -> Result<()> {
+        let total_staked = ctx.accounts.protocol_stats.total_staked;
+        let token = ctx.accounts.reward_tokens.tokens
+            .get_mut(index as usize)
+            .ok_or(ErrorCode::InvalidRewardToken)?;
+        token.update_reward_per_token(total_staked, Clock::get()?.unix_timestamp)?;
+        token.tokens_per_second = tokens_per_second;
+        token.active = active;
+        msg!("Reward token {} now emits {} tokens/s (active: {})", token.mint, tokens_per_second, active);
+        Ok(())
+    }
+
+    /// View: returns (via return data) the position's claimable rewards as of now, including the
+    /// LP boost `claim_rewards` would apply and any partner reward tokens.
+    pub fn get_pending_rewards(ctx: Context<GetPendingRewards>) -> Result<()> {
+        let mut protocol_stats = (*ctx.accounts.protocol_stats).clone();
+        let mut reward_tokens = (*ctx.accounts.reward_tokens).clone();
+        let mut stake_info = (*ctx.accounts.stake_info).clone();
+        settle_rewards(
+            &mut protocol_stats,
+            &ctx.accounts.emission_schedule,
+            &mut reward_tokens,
+            &ctx.accounts.config,
+            &mut stake_info,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let (base_reward, lp_boost, total_reward) =
+            calculate_claim_reward(stake_info.accrued_rewards, stake_info.take_lp_boost(Clock::get()?.unix_timestamp)?)?;
+        let partner_rewards = reward_tokens.tokens
+            .iter()
+            .enumerate()
+            .map(|(slot, token)| PendingTokenReward { mint: token.mint, amount: stake_info.reward_token_accrued[slot] })
+            .collect();
+        let pending = PendingRewards {
+            staker: stake_info.staker,
+            base_reward,
+            lp_boost,
+            total_reward,
+            partner_rewards,
+        };
+        msg!("Pending rewards for {}: {} (Base: {}, LP Boost: {})", pending.staker, total_reward, base_reward, lp_boost);
+        set_return_data(&pending.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Create the caller's reward vesting escrow, required to claim while reward vesting is enabled.
+    pub fn open_reward_vesting(ctx: Context<OpenRewardVesting>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let reward_vesting = &mut ctx.accounts.reward_vesting;
+        let now = Clock::get()?.unix_timestamp;
+        reward_vesting.staker = ctx.accounts.staker.key();
+        reward_vesting.vested = 0;
+        reward_vesting.vesting = 0;
+        reward_vesting.start = now;
+        reward_vesting.end = now;
+        reward_vesting.tranches = [VestingTranche::default(); REWARD_VESTING_TRANCHES];
+        msg!("Reward vesting escrow opened for {}", reward_vesting.staker);
+        Ok(())
+    }
+
+    /// Withdraw vested rewards from the escrow. With `early_exit`, the still-vesting balance is
+    /// released too, minus the configured haircut, which returns to the reward budget.
+    pub fn claim_vested(ctx: Context<ClaimVested>, early_exit: bool) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let reward_vesting = &mut ctx.accounts.reward_vesting;
+        reward_vesting.apply_incident_offset(&ctx.accounts.config);
+        reward_vesting.checkpoint(Clock::get()?.unix_timestamp)?;
+        let mut payout = reward_vesting.vested;
+        let mut haircut = 0;
+        if early_exit && reward_vesting.vesting > 0 {
+            haircut = mul_div_ceil(
+                reward_vesting.vesting as u128,
+                ctx.accounts.config.reward_vesting_haircut_bps as u128,
+                BPS_DENOMINATOR as u128,
+            )?;
+            payout = payout
+                .checked_add(reward_vesting.vesting.checked_sub(haircut).ok_or(ErrorCode::Underflow)?)
+                .ok_or(ErrorCode::Overflow)?;
+            reward_vesting.vesting = 0;
+            reward_vesting.tranches = [VestingTranche::default(); REWARD_VESTING_TRANCHES];
+        }
+        require!(payout > 0, ErrorCode::NoVestedRewards);
+        reward_vesting.vested = 0;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), payout)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.rewards_outstanding = protocol_stats.rewards_outstanding
+            .checked_add(haircut)
+            .ok_or(ErrorCode::Overflow)?;
+        msg!("Vested rewards claimed: {} tokens ({} forfeited to early exit)", payout, haircut);
+        Ok(())
+    }
+
+    /// Migrate a reward vesting escrow from before vesting tranches: its single vesting window
+    /// becomes the first tranche. Permissionless; the payer covers the extra rent.
+    pub fn migrate_reward_vesting(ctx: Context<MigrateRewardVesting>) -> Result<()> {
+        let info = ctx.accounts.reward_vesting.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::Unauthorized);
+        require!(info.data_len() < 8 + RewardVesting::LEN, ErrorCode::AlreadyMigrated);
+        grow_account(&info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + RewardVesting::LEN)?;
+
+        // Deserializing also verifies the account discriminator.
+        let mut reward_vesting = RewardVesting::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        if reward_vesting.vesting > 0 {
+            reward_vesting.tranches[0] = VestingTranche {
+                amount: reward_vesting.vesting,
+                start: reward_vesting.start,
+                end: reward_vesting.end,
+            };
+        }
+        reward_vesting.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        msg!("Reward vesting escrow of {} migrated to tranches", reward_vesting.staker);
+        Ok(())
+    }
+
+    /// Vault rotation, step 1 (config authority only): stages the next-generation vault authority
+    /// PDA `[b"vault", generation]`. Token accounts owned by it are created off-chain.
+    pub fn begin_vault_rotation(ctx: Context<BeginVaultRotation>) -> Result<()> {
+        let generation = ctx.accounts.config.vault_generation.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        let (new_vault_authority, new_vault_bump) =
+            Pubkey::find_program_address(&[b"vault".as_ref(), &[generation]], ctx.program_id);
+        let vault_rotation = &mut ctx.accounts.vault_rotation;
+        vault_rotation.generation = generation;
+        vault_rotation.new_vault_authority = new_vault_authority;
+        vault_rotation.new_vault_bump = new_vault_bump;
+        vault_rotation.accounts_migrated = 0;
+        vault_rotation.started_at = Clock::get()?.unix_timestamp;
+        msg!("Vault rotation to generation {} started: new authority {}", generation, new_vault_authority);
+        Ok(())
+    }
+
+    /// Vault rotation, step 2 (config authority only): moves the full balance of up to
+    /// `MAX_VAULT_MIGRATION_BATCH` vault token accounts, passed as (old, new) pairs in
+    /// `remaining_accounts`, from the active vault authority to the staged one. Vaults pinned in the
+    /// config are repointed to their replacements.
+    pub fn migrate_vault_batch(ctx: Context<MigrateVaultBatch>) -> Result<()> {
+        let pair_count = ctx.remaining_accounts.len() / 2;
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0 && pair_count > 0 && pair_count <= MAX_VAULT_MIGRATION_BATCH,
+            ErrorCode::InvalidVaultMigrationAccounts
+        );
+        let config = &mut ctx.accounts.config;
+        let vault_rotation = &mut ctx.accounts.vault_rotation;
+        let vault_bump = config.vault_bump;
+        let vault_generation = config.vault_generation;
+        let active_authority = config.vault_authority;
+        let seeds: Vec<&[u8]> = if vault_generation == 0 {
+            vec![b"vault".as_ref(), std::slice::from_ref(&vault_bump)]
+        } else {
+            vec![b"vault".as_ref(), std::slice::from_ref(&vault_generation), std::slice::from_ref(&vault_bump)]
+        };
+        let signer = &[&seeds[..]];
+        for pair in ctx.remaining_accounts.chunks(2) {
+            require!(
+                *pair[0].owner == token::ID && *pair[1].owner == token::ID,
+                ErrorCode::InvalidVaultMigrationAccounts
+            );
+            let old_vault = TokenAccount::try_deserialize(&mut &pair[0].try_borrow_data()?[..])?;
+            let new_vault = TokenAccount::try_deserialize(&mut &pair[1].try_borrow_data()?[..])?;
+            require!(
+                old_vault.owner == active_authority
+                    && new_vault.owner == vault_rotation.new_vault_authority
+                    && old_vault.mint == new_vault.mint,
+                ErrorCode::InvalidVaultMigrationAccounts
+            );
+            if old_vault.amount > 0 {
+                let cpi_accounts = Transfer {
+                    from: pair[0].clone(),
+                    to: pair[1].clone(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), old_vault.amount)?;
+            }
+            config.remap_vault(pair[0].key(), pair[1].key());
+            vault_rotation.accounts_migrated = vault_rotation.accounts_migrated
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?;
+            msg!("Vault {} migrated to {}: {} tokens", pair[0].key(), pair[1].key(), old_vault.amount);
+        }
+        Ok(())
+    }
+
+    /// Vault rotation, step 3 (config authority only): flips the config to the new vault authority,
+    /// which every vault payout signs with from now on. Receipt NFTs stay under the original one.
+    pub fn complete_vault_rotation(ctx: Context<CompleteVaultRotation>) -> Result<()> {
+        let vault_rotation = &ctx.accounts.vault_rotation;
+        let config = &mut ctx.accounts.config;
+        // remaining_accounts: every vault pinned in the config, in `pinned_vaults` order; each must
+        // already be held by the new vault authority so no funds are stranded under the old one.
+        let pinned = config.pinned_vaults();
+        require!(ctx.remaining_accounts.len() == pinned.len(), ErrorCode::VaultsNotMigrated);
+        for (address, info) in pinned.iter().zip(ctx.remaining_accounts.iter()) {
+            require!(info.key() == *address && *info.owner == token::ID, ErrorCode::VaultsNotMigrated);
+            let vault = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(vault.owner, vault_rotation.new_vault_authority, ErrorCode::VaultsNotMigrated);
+        }
+        config.vault_authority = vault_rotation.new_vault_authority;
+        config.vault_generation = vault_rotation.generation;
+        config.vault_bump = vault_rotation.new_vault_bump;
+        msg!(
+            "Vault rotation complete: generation {} ({} accounts migrated), authority {}",
+            config.vault_generation,
+            vault_rotation.accounts_migrated,
+            config.vault_authority
+        );
+        Ok(())
+    }
+
+    /// View: pre-flight check of a would-be stake or unstake account set. Returns (via return data)
+    /// one `AccountCheck` per account in context order, carrying the error code the real instruction
+    /// would fail with (0 = valid), so integrations can tell users exactly which account to fix.
+    pub fn validate_accounts(
+        ctx: Context<ValidateAccounts>,
+        operation: StakeOperation,
+        staker: Pubkey,
+        mint: Pubkey,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let config = if *accounts.config.owner == crate::ID {
+            ProtocolConfig::try_deserialize(&mut &accounts.config.try_borrow_data()?[..]).ok()
+        } else {
+            None
+        };
+        let vault_authority = config
+            .as_ref()
+            .map(|config| config.vault_authority)
+            .unwrap_or_else(|| Pubkey::find_program_address(&[b"vault".as_ref()], ctx.program_id).0);
+
+        let (expected_stake_info, _) =
+            Pubkey::find_program_address(&[b"stake".as_ref(), staker.as_ref()], ctx.program_id);
+        let stake_info_code = if accounts.stake_info.key() != expected_stake_info {
+            u32::from(ErrorCode::InvalidStakeAccount)
+        } else if accounts.stake_info.data_is_empty() {
+            // Staking creates the position; unstaking needs an existing one.
+            match operation {
+                StakeOperation::Stake => 0,
+                StakeOperation::Unstake => u32::from(ErrorCode::InvalidStakeAccount),
+            }
+        } else if *accounts.stake_info.owner != crate::ID
+            || StakeInfo::try_deserialize(&mut &accounts.stake_info.try_borrow_data()?[..]).is_err()
+        {
+            u32::from(ErrorCode::InvalidStakeAccount)
+        } else {
+            0
+        };
+        let config_code = if config.is_some() {
+            check_protocol_account(&accounts.config, b"config")
+        } else {
+            u32::from(ErrorCode::InvalidProtocolAccount)
+        };
+        let vault_authority_code = if accounts.vault_authority.key() == vault_authority {
+            0
+        } else {
+            u32::from(ErrorCode::InvalidVaultAuthority)
+        };
+
+        let checks = vec![
+            AccountCheck { address: accounts.stake_info.key(), error_code: stake_info_code },
+            AccountCheck {
+                address: accounts.staker_token_account.key(),
+                error_code: check_token_account(
+                    &accounts.staker_token_account,
+                    mint,
+                    staker,
+                    operation == StakeOperation::Unstake,
+                )?,
+            },
+            AccountCheck {
+                address: accounts.vault_token_account.key(),
+                error_code: check_token_account(&accounts.vault_token_account, mint, vault_authority, false)?,
+            },
+            AccountCheck { address: accounts.config.key(), error_code: config_code },
+            AccountCheck {
+                address: accounts.protocol_stats.key(),
+                error_code: check_protocol_account(&accounts.protocol_stats, b"protocol_stats"),
+            },
+            AccountCheck {
+                address: accounts.emission_schedule.key(),
+                error_code: check_protocol_account(&accounts.emission_schedule, b"emission_schedule"),
+            },
+            AccountCheck {
+                address: accounts.reward_tokens.key(),
+                error_code: check_protocol_account(&accounts.reward_tokens, b"reward_tokens"),
+            },
+            AccountCheck { address: accounts.vault_authority.key(), error_code: vault_authority_code },
+        ];
+        for check in checks.iter().filter(|check| check.error_code != 0) {
+            msg!("Account {} fails with error {}", check.address, check.error_code);
+        }
+        set_return_data(&checks.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Register a 16-byte integration code (frontend or partner) that deposits can be attributed
+    /// to. Permissionless; the registrant is recorded so the team can reward it.
+    pub fn register_integration_code(ctx: Context<RegisterIntegrationCode>, code: [u8; 16]) -> Result<()> {
+        let integration_stats = &mut ctx.accounts.integration_stats;
+        integration_stats.code = code;
+        integration_stats.registrant = ctx.accounts.registrant.key();
+        integration_stats.deposit_count = 0;
+        integration_stats.total_deposited = 0;
+        integration_stats.last_deposit_at = 0;
+        msg!("Integration code registered by {}", integration_stats.registrant);
+        Ok(())
+    }
+
+    /// Create and fully fund a time-boxed boost campaign (any partner). The campaign emits
+    /// `tokens_per_second` of `funding_mint` between `start_time` and `end_time`, shared pro rata
+    /// among positions that pass its eligibility filter.
+    pub fn create_boost_campaign(
+        ctx: Context<CreateBoostCampaign>,
+        campaign_id: u64,
+        tokens_per_second: u64,
+        start_time: i64,
+        end_time: i64,
+        eligibility: BoostEligibility,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            tokens_per_second > 0 && start_time < end_time && end_time > now,
+            ErrorCode::InvalidBoostCampaign
+        );
+        let budget = (tokens_per_second as u128)
+            .checked_mul((end_time - start_time) as u128)
+            .ok_or(ErrorCode::Overflow)?;
+        let budget = u64::try_from(budget).map_err(|_| error!(ErrorCode::Overflow))?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.partner_token_account.to_account_info(),
+            to: ctx.accounts.boost_vault.to_account_info(),
+            authority: ctx.accounts.partner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), budget)?;
+
+        let campaign = &mut ctx.accounts.boost_campaign;
+        campaign.partner = ctx.accounts.partner.key();
+        campaign.campaign_id = campaign_id;
+        campaign.funding_mint = ctx.accounts.funding_mint.key();
+        campaign.vault = ctx.accounts.boost_vault.key();
+        campaign.tokens_per_second = tokens_per_second;
+        campaign.start_time = start_time;
+        campaign.end_time = end_time;
+        campaign.eligibility = eligibility;
+        campaign.total_weight = 0;
+        campaign.reward_per_token_stored = 0;
+        campaign.last_update_time = start_time;
+        campaign.reward_dust = 0;
+        campaign.bump = ctx.bumps.boost_campaign;
+        msg!("Boost campaign {} funded with {} tokens from {} to {}", campaign_id, budget, start_time, end_time);
+        Ok(())
+    }
+
+    /// Crank: settles a position's boost rewards and refreshes its campaign weight from the current
+    /// stake (zero if ineligible). Permissionless, so keepers can keep weights current; only the
+    /// owner or operator can enroll a position that holds no weight in the campaign yet.
+    pub fn sync_boost_position(ctx: Context<SyncBoostPosition>) -> Result<()> {
+        let campaign_key = ctx.accounts.boost_campaign.key();
+        let stake_info = &mut ctx.accounts.stake_info;
+        let boost_position = &mut ctx.accounts.boost_position;
+        let previous_weight = boost_position.weight;
+        boost_position.campaign = campaign_key;
+        boost_position.staker = stake_info.staker;
+        settle_boost_position(
+            &mut ctx.accounts.boost_campaign,
+            boost_position,
+            stake_info,
+            Clock::get()?.unix_timestamp,
+        )?;
+        if previous_weight == 0 && boost_position.weight > 0 {
+            require!(stake_info.is_owner_or_operator(ctx.accounts.payer.key()), ErrorCode::Unauthorized);
+        }
+        stake_info.set_boost_enrollment(campaign_key, boost_position.weight)?;
+        msg!("Boost position synced: weight {}, accrued {}", boost_position.weight, boost_position.accrued);
+        Ok(())
+    }
+
+    /// Claim a position's accrued boost rewards from the campaign vault.
+    pub fn claim_boost_rewards(ctx: Context<ClaimBoostRewards>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let campaign = &mut ctx.accounts.boost_campaign;
+        let boost_position = &mut ctx.accounts.boost_position;
+        let stake_info = &mut ctx.accounts.stake_info;
+        settle_boost_position(campaign, boost_position, stake_info, Clock::get()?.unix_timestamp)?;
+        stake_info.set_boost_enrollment(campaign.key(), boost_position.weight)?;
+        let amount = boost_position.accrued;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        boost_position.accrued = 0;
+
+        let campaign_id = campaign.campaign_id.to_le_bytes();
+        let seeds = &[b"boost_campaign".as_ref(), campaign.partner.as_ref(), campaign_id.as_ref(), &[campaign.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.boost_vault.to_account_info(),
+            to: ctx.accounts.staker_token_account.to_account_info(),
+            authority: campaign.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        msg!("Boost rewards claimed: {} tokens from campaign {}", amount, campaign.campaign_id);
+        Ok(())
+    }
+
+    /// Return the part of an ended boost campaign's budget that was never distributed (partner
+    /// only), i.e. emission from stretches without any eligible weight plus rounding remainders.
+    pub fn reclaim_boost_remainder(ctx: Context<ReclaimBoostRemainder>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let campaign = &mut ctx.accounts.boost_campaign;
+        require!(now >= campaign.end_time, ErrorCode::BoostCampaignActive);
+        campaign.update_reward_per_token(now)?;
+        let amount = u64::try_from(campaign.reward_dust / math::REWARD_SCALE).map_err(|_| error!(ErrorCode::Overflow))?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        campaign.reward_dust = campaign.reward_dust
+            .checked_sub(math::to_reward_scale(amount as u128)?)
+            .ok_or(ErrorCode::Underflow)?;
+
+        let campaign_id = campaign.campaign_id.to_le_bytes();
+        let seeds = &[b"boost_campaign".as_ref(), campaign.partner.as_ref(), campaign_id.as_ref(), &[campaign.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.boost_vault.to_account_info(),
+            to: ctx.accounts.partner_token_account.to_account_info(),
+            authority: campaign.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        msg!("Boost campaign {} remainder reclaimed: {} tokens", campaign.campaign_id, amount);
+        Ok(())
+    }
+
+    /// Claim rewards for several positions in one transaction. `remaining_accounts` holds
+    /// (stake_info, destination, referral_account) triples; the signer must own or operate each
+    /// position. `referral_account` is the referrer's referral PDA, or the program id for positions
+    /// without a referrer. Positions with auto-restake enabled compound into the stake vault, the
+    /// rest are paid to their destination. Partner reward tokens stay accrued on each position, and boost campaign weights
+    /// of compounded positions catch up on their next sync.
+    pub fn claim_all(ctx: Context<ClaimAll>) -> Result<()> {
+        let triples = ctx.remaining_accounts;
+        require!(
+            !triples.is_empty() && triples.len() % 3 == 0 && triples.len() / 3 <= MAX_CLAIM_ALL_POSITIONS,
+            ErrorCode::InvalidClaimAllAccounts
+        );
+        ensure_no_incident(&ctx.accounts.config)?;
+        let authority = ctx.accounts.authority.key();
+        let now = Clock::get()?.unix_timestamp;
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let mut total_paid: u64 = 0;
+        let mut total_compounded: u64 = 0;
+        let mut total_referred: u64 = 0;
+
+        for (index, triple) in triples.chunks(3).enumerate() {
+            let (info, destination_info, referral_info) = (&triple[0], &triple[1], &triple[2]);
+            require!(info.is_writable, ErrorCode::InvalidClaimAllAccounts);
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidClaimAllAccounts);
+            require!(
+                !triples[..index * 3].chunks(3).any(|earlier| earlier[0].key() == info.key()),
+                ErrorCode::InvalidClaimAllAccounts
+            );
+            let mut data = info.try_borrow_mut_data()?;
+            let mut stake_info = StakeInfo::try_deserialize(&mut &data[..])?;
+            let (expected, _) = Pubkey::find_program_address(&[b"stake", stake_info.staker.as_ref()], &crate::ID);
+            require_keys_eq!(info.key(), expected, ErrorCode::InvalidClaimAllAccounts);
+            require!(stake_info.is_owner_or_operator(authority), ErrorCode::Unauthorized);
+            require!(!stake_info.locked, ErrorCode::ReentrancyDetected);
+
+            settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, &mut stake_info, now)?;
+            if stake_info.accrued_rewards == 0 {
+                // Nothing accrued: no claim, so no LP boost either.
+                stake_info.try_serialize(&mut &mut data[..])?;
+                continue;
+            }
+            let (requested_base, requested_boost, _) = calculate_claim_reward(stake_info.accrued_rewards, stake_info.take_lp_boost(now)?)?;
+            let vault_remaining = ctx.accounts.reward_vault.amount
+                .saturating_sub(total_paid)
+                .saturating_sub(total_compounded)
+                .saturating_sub(total_referred);
+            let (base_reward, lp_boost, total_reward) = cap_claim(
+                &mut ctx.accounts.protocol_stats,
+                &ctx.accounts.config,
+                &mut stake_info,
+                requested_base,
+                requested_boost,
+                vault_remaining,
+                now,
+            )?;
+            emit!(RewardsClaimed {
+                staker: stake_info.staker,
+                base_reward,
+                lp_boost,
+                total_reward,
+                compounded: stake_info.auto_restake,
+            });
+            stake_info.lifetime_rewards = stake_info.lifetime_rewards.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+
+            if stake_info.auto_restake {
+                stake_info.checkpoint_voting_power(now);
+                stake_info.amount = stake_info.amount.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+                total_compounded = total_compounded.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+            } else if total_reward > 0 {
+                require!(!ctx.accounts.config.reward_vesting_enabled, ErrorCode::RewardVestingRequired);
+                require!(stake_info.claim_as_mint == Pubkey::default(), ErrorCode::ClaimAsModeActive);
+                require!(
+                    stake_info.reward_destination_allowed(destination_info.key()),
+                    ErrorCode::InvalidRewardBeneficiary
+                );
+                let destination = TokenAccount::try_deserialize(&mut &destination_info.try_borrow_data()?[..])?;
+                if authority != stake_info.staker
+                    && stake_info.reward_beneficiary == Pubkey::default()
+                    && stake_info.withdrawal_address == Pubkey::default()
+                {
+                    require!(destination.owner == stake_info.staker, ErrorCode::InvalidTokenAccountOwner);
+                }
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: destination_info.clone(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_reward)?;
+                total_paid = total_paid.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+            }
+
+            let mut referral_account = None;
+            if stake_info.referrer != Pubkey::default() && referral_info.key() != crate::ID {
+                require!(referral_info.is_writable, ErrorCode::InvalidClaimAllAccounts);
+                require_keys_eq!(*referral_info.owner, crate::ID, ErrorCode::InvalidClaimAllAccounts);
+                let (expected, _) = Pubkey::find_program_address(&[b"referral", stake_info.referrer.as_ref()], &crate::ID);
+                require_keys_eq!(referral_info.key(), expected, ErrorCode::InvalidClaimAllAccounts);
+                referral_account = Some(ReferralAccount::try_deserialize(&mut &referral_info.try_borrow_data()?[..])?);
+            }
+            let referred = credit_referral(
+                &ctx.accounts.config,
+                &stake_info,
+                referral_account.as_mut(),
+                ctx.accounts.referral_vault.as_ref().map(|vault| vault.to_account_info()),
+                ctx.accounts.reward_vault.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                total_reward,
+                vault_remaining.saturating_sub(total_reward),
+            )?;
+            if let Some(referral_account) = referral_account {
+                referral_account.try_serialize(&mut &mut referral_info.try_borrow_mut_data()?[..])?;
+            }
+            total_referred = total_referred.checked_add(referred).ok_or(ErrorCode::Overflow)?;
+            stake_info.try_serialize(&mut &mut data[..])?;
+        }
+
+        if total_compounded > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), total_compounded)?;
+        }
+
+        let total_reward = total_paid.checked_add(total_compounded).ok_or(ErrorCode::Overflow)?;
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_distributed = protocol_stats.total_rewards_distributed
+            .checked_add(total_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        protocol_stats.total_staked = protocol_stats.total_staked.checked_add(total_compounded).ok_or(ErrorCode::Overflow)?;
+        msg!(
+            "Claimed across {} positions: {} tokens paid out, {} tokens compounded",
+            triples.len() / 3,
+            total_paid,
+            total_compounded
+        );
+        Ok(())
+    }
+
+    /// Declare (`active = true`) or resolve a verified incident (config authority only). While an
+    /// incident is active deposits, withdrawals and claims are paused; on resolution the pause
+    /// length is added to `incident_offset` so lock, duration and reward-vesting clocks resume
+    /// where they stopped.
+    pub fn set_incident_mode(ctx: Context<SetIncidentMode>, active: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let now = Clock::get()?.unix_timestamp;
+        if active {
+            require!(config.incident_started_at == 0, ErrorCode::IncidentActive);
+            config.incident_started_at = now;
+            config.last_incident_started_at = now;
+        } else {
+            require!(config.incident_started_at != 0, ErrorCode::NoActiveIncident);
+            let paused_for = now.checked_sub(config.incident_started_at).ok_or(ErrorCode::Underflow)?;
+            config.incident_offset = config.incident_offset.checked_add(paused_for).ok_or(ErrorCode::Overflow)?;
+            config.incident_started_at = 0;
+        }
+        emit!(IncidentModeChanged {
+            active,
+            timestamp: now,
+            incident_offset: config.incident_offset,
+        });
+        msg!("Incident mode set to {} (total offset {}s)", active, config.incident_offset);
+        Ok(())
+    }
+
+    /// Nominate a new config authority (config authority only). The transfer only completes once the
+    /// nominee signs `accept_config_authority`; `Pubkey::default()` clears the nomination.
+    pub fn propose_new_config_authority(ctx: Context<ProposeConfigAuthority>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.config.pending_authority = new_authority;
+        msg!("Config authority transfer proposed to {}", new_authority);
+        Ok(())
+    }
+
+    /// Complete a config authority transfer (nominated authority only).
+    pub fn accept_config_authority(ctx: Context<AcceptConfigAuthority>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous = config.authority;
+        config.authority = config.pending_authority;
+        config.pending_authority = Pubkey::default();
+        emit!(AuthorityTransferred {
+            config: config.key(),
+            previous_authority: previous,
+            new_authority: config.authority,
+        });
+        msg!("Config authority transferred to {}", config.authority);
+        Ok(())
+    }
+
+    /// Pause the protocol (guardian only). The guardian cannot unpause; only governance can. While
+    /// paused every user instruction that moves tokens or changes a position fails, except loan
+    /// repayment and liquidation; governance and authority instructions stay available so the pause
+    /// can be resolved.
+    pub fn pause_protocol(ctx: Context<PauseProtocol>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.paused, ErrorCode::ProtocolPaused);
+        config.paused = true;
+        emit!(ProtocolPauseChanged {
+            paused: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Protocol paused by the guardian");
+        Ok(())
+    }
+
+    /// Lift a guardian pause. Only callable by the governance signer, i.e. through `execute_proposal`.
+    pub fn unpause_protocol(ctx: Context<SetGuardian>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.paused, ErrorCode::ProtocolNotPaused);
+        config.paused = false;
+        emit!(ProtocolPauseChanged {
+            paused: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Protocol unpaused by governance");
+        Ok(())
+    }
+
+    /// Replace the pause guardian (`Pubkey::default()` removes it). Only callable by the governance
+    /// signer, i.e. through `execute_proposal`.
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.config.guardian = guardian;
+        msg!("Pause guardian set to {}", guardian);
+        Ok(())
+    }
+
+    /// Create the swap-on-claim payout route table (config authority only).
+    pub fn initialize_payout_routes(ctx: Context<InitializePayoutRoutes>) -> Result<()> {
+        ctx.accounts.payout_routes.routes = Vec::new();
+        msg!("Payout route table initialized");
+        Ok(())
+    }
+
+    /// Add or replace the AMM route that converts `reward_mint` into `payout_mint` on claim
+    /// (config authority only). Setting `active = false` disables the payout option.
+    pub fn set_payout_route(ctx: Context<SetPayoutRoute>, route: PayoutRoute) -> Result<()> {
+        require!(route.reward_mint != route.payout_mint, ErrorCode::InvalidPayoutRoute);
+        require!(
+            route.amm_program != crate::ID && route.amm_program != token::ID,
+            ErrorCode::InvalidPayoutRoute
+        );
+        let payout_routes = &mut ctx.accounts.payout_routes;
+        match payout_routes
+            .routes
+            .iter_mut()
+            .find(|existing| existing.reward_mint == route.reward_mint && existing.payout_mint == route.payout_mint)
+        {
+            Some(existing) => *existing = route,
+            None => {
+                require!(payout_routes.routes.len() < MAX_PAYOUT_ROUTES, ErrorCode::TooManyPayoutRoutes);
+                payout_routes.routes.push(route);
+            }
+        }
+        msg!(
+            "Payout route {} -> {} via {} (active: {})",
+            route.reward_mint,
+            route.payout_mint,
+            route.amm_program,
+            route.active
+        );
+        Ok(())
+    }
+
+    /// Claim SST rewards paid out in another mint. The reward is moved into the route escrow and
+    /// swapped from there through the governance-approved AMM route for (reward mint,
+    /// `payout_mint`); the route's accounts are passed via `remaining_accounts`. Fails unless the
+    /// escrow spends exactly the reward and the destination receives at least `min_amount_out`.
+    pub fn claim_rewards_routed(
+        ctx: Context<ClaimRewardsRouted>,
+        payout_mint: Pubkey,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(!ctx.accounts.config.reward_vesting_enabled, ErrorCode::RewardVestingRequired);
+        let route = *ctx.accounts.payout_routes
+            .find(ctx.accounts.reward_vault.mint, payout_mint)
+            .ok_or(ErrorCode::InvalidPayoutRoute)?;
+        require_keys_eq!(ctx.accounts.amm_program.key(), route.amm_program, ErrorCode::InvalidPayoutRoute);
+        require!(
+            ctx.remaining_accounts.len() == route.route_account_count as usize,
+            ErrorCode::InvalidPayoutRoute
+        );
+        require_keys_eq!(ctx.accounts.payout_token_account.mint, payout_mint, ErrorCode::InvalidPayoutRoute);
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.claim_as_mint == Pubkey::default() || stake_info.claim_as_mint == payout_mint,
+            ErrorCode::InvalidPayoutRoute
+        );
+        let clock = Clock::get()?;
+        if ctx.accounts.authority.key() != stake_info.staker
+            && stake_info.reward_beneficiary == Pubkey::default()
+            && stake_info.withdrawal_address == Pubkey::default()
+        {
+            require!(
+                ctx.accounts.payout_token_account.owner == stake_info.staker,
+                ErrorCode::InvalidTokenAccountOwner
+            );
+        }
+        settle_rewards(&mut ctx.accounts.protocol_stats, &ctx.accounts.emission_schedule, &mut ctx.accounts.reward_tokens, &ctx.accounts.config, stake_info, clock.unix_timestamp)?;
+        let (requested_base, requested_boost, _) = calculate_claim_reward(stake_info.accrued_rewards, stake_info.take_lp_boost(clock.unix_timestamp)?)?;
+        let (base_reward, lp_boost, total_reward) = cap_claim(
+            &mut ctx.accounts.protocol_stats,
+            &ctx.accounts.config,
+            stake_info,
+            requested_base,
+            requested_boost,
+            ctx.accounts.reward_vault.amount,
+            clock.unix_timestamp,
+        )?;
+        require!(total_reward > 0, ErrorCode::InvalidAmount);
+        stake_info.lifetime_rewards = stake_info.lifetime_rewards.checked_add(total_reward).ok_or(ErrorCode::Overflow)?;
+        emit!(RewardsClaimed {
+            staker: stake_info.staker,
+            base_reward,
+            lp_boost,
+            total_reward,
+            compounded: false,
+        });
+
+        // Move only the claimed amount into the route escrow, which alone signs the swap, so the
+        // route program never gets the vault authority's signature.
+        let vault_seeds = ctx.accounts.config.vault_signer_seeds();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.route_escrow_vault.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, &[&vault_seeds[..]]), total_reward)?;
+
+        let escrow_before = ctx.accounts.route_escrow_vault.amount;
+        let payout_before = ctx.accounts.payout_token_account.amount;
+        let escrow_authority = ctx.accounts.route_escrow_authority.key();
+        let mut data = route.swap_discriminator.to_vec();
+        data.extend_from_slice(&total_reward.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+        let swap = Instruction {
+            program_id: route.amm_program,
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|info| AccountMeta {
+                    pubkey: info.key(),
+                    is_signer: info.key() == escrow_authority,
+                    is_writable: info.is_writable,
+                })
+                .collect(),
+            data,
+        };
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(ctx.accounts.amm_program.to_account_info());
+        let escrow_seeds = &[b"route_escrow".as_ref(), &[ctx.bumps.route_escrow_authority]];
+        invoke_signed(&swap, &infos, &[&escrow_seeds[..]])?;
+
+        ctx.accounts.route_escrow_vault.reload()?;
+        ctx.accounts.payout_token_account.reload()?;
+        require!(
+            escrow_before.checked_sub(ctx.accounts.route_escrow_vault.amount) == Some(total_reward),
+            ErrorCode::PayoutSwapMismatch
+        );
+        let amount_out = ctx.accounts.payout_token_account.amount.saturating_sub(payout_before);
+        require!(amount_out >= min_amount_out, ErrorCode::PayoutSwapMismatch);
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_rewards_distributed = protocol_stats.total_rewards_distributed
+            .checked_add(total_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        credit_referral(
+            &ctx.accounts.config,
+            &ctx.accounts.stake_info,
+            ctx.accounts.referral_account.as_deref_mut(),
+            ctx.accounts.referral_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            total_reward,
+            ctx.accounts.reward_vault.amount.saturating_sub(total_reward),
+        )?;
+        msg!("Rewards claimed: {} SST swapped into {} of mint {}", total_reward, amount_out, payout_mint);
+        Ok(())
+    }
+
+    /// Crank: flags an empty position with no staker activity for `DORMANCY_PERIOD`. It can be
+    /// closed after `DORMANT_CLOSURE_NOTICE` unless the staker acts first; a deposit in the
+    /// meantime earns the re-engagement bonus while the budget lasts.
+    pub fn flag_dormant_position(ctx: Context<FlagDormantPosition>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        require!(stake_info.is_empty(), ErrorCode::PositionNotDormant);
+        require!(stake_info.dormant_flagged_at == 0, ErrorCode::PositionNotDormant);
+        let last_activity_at = stake_info.last_activity_at.max(stake_info.last_staked_time);
+        require!(now.saturating_sub(last_activity_at) >= DORMANCY_PERIOD, ErrorCode::PositionNotDormant);
+        stake_info.dormant_flagged_at = now;
+        let closable_at = now.checked_add(DORMANT_CLOSURE_NOTICE).ok_or(ErrorCode::Overflow)?;
+        emit!(DormantPositionFlagged {
+            staker: stake_info.staker,
+            last_activity_at,
+            closable_at,
+        });
+        msg!("Position of {} flagged as dormant; closable at {}", stake_info.staker, closable_at);
+        Ok(())
+    }
+
+    /// Crank: closes a flagged dormant position once the notice period has passed, returning
+    /// its rent to the staker.
+    pub fn close_dormant_position(ctx: Context<CloseDormantPosition>) -> Result<()> {
+        let stake_info = &ctx.accounts.stake_info;
+        let now = Clock::get()?.unix_timestamp;
+        require!(stake_info.dormant_flagged_at != 0 && stake_info.is_empty(), ErrorCode::PositionNotDormant);
+        require!(
+            now.saturating_sub(stake_info.dormant_flagged_at) >= DORMANT_CLOSURE_NOTICE,
+            ErrorCode::DormantNoticePending
+        );
+        emit!(DormantPositionClosed {
+            staker: stake_info.staker,
+        });
+        msg!("Dormant position of {} closed", stake_info.staker);
+        Ok(())
+    }
+
+    /// Deposit protocol revenue (e.g. trading fees in SST or USDC) for stakers. The mint must be
+    /// registered in the reward-token registry; the deposit goes to that slot's vault and is
+    /// credited pro rata to current stake through the slot's accumulator, so it is claimed
+    /// alongside partner rewards in `claim_rewards`.
+    pub fn deposit_revenue(ctx: Context<DepositRevenue>, amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let mint = ctx.accounts.depositor_token_account.mint;
+        let total_staked = ctx.accounts.protocol_stats.total_staked;
+        require!(total_staked > 0, ErrorCode::NoStakeToCredit);
+        let now = Clock::get()?.unix_timestamp;
+        let reward_tokens = &mut ctx.accounts.reward_tokens;
+        reward_tokens.update(total_staked, now)?;
+        let slot = reward_tokens.tokens
+            .iter()
+            .position(|token| token.mint == mint)
+            .ok_or(ErrorCode::InvalidRewardToken)?;
+        require_keys_eq!(ctx.accounts.revenue_vault.key(), reward_tokens.tokens[slot].vault, ErrorCode::InvalidRewardTokenAccounts);
+        reward_tokens.tokens[slot].distribute(amount, total_staked)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.depositor_token_account.to_account_info(),
+            to: ctx.accounts.revenue_vault.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let reward_per_token = ctx.accounts.reward_tokens.tokens[slot].reward_per_token_stored;
+        emit!(RevenueDeposited {
+            depositor: ctx.accounts.depositor.key(),
+            mint,
+            amount,
+            reward_per_token,
+        });
+        msg!("Revenue of {} tokens of mint {} credited to stakers (slot {})", amount, mint, slot);
+        Ok(())
+    }
+
+    /// Registers the caller as a referrer so new positions can name them at stake time.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referral_account = &mut ctx.accounts.referral_account;
+        referral_account.referrer = ctx.accounts.referrer.key();
+        referral_account.referee_count = 0;
+        referral_account.pending = 0;
+        referral_account.total_earned = 0;
+        msg!("Referrer registered: {}", referral_account.referrer);
+        Ok(())
+    }
+
+    /// Pays out the referral share credited to the caller from the referral vault.
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let amount = ctx.accounts.referral_account.pending;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.referral_vault.to_account_info(),
+            to: ctx.accounts.referrer_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        let referral_account = &mut ctx.accounts.referral_account;
+        referral_account.pending = 0;
+        emit!(ReferralRewardsClaimed {
+            referrer: referral_account.referrer,
+            amount,
+        });
+        msg!("Referral rewards claimed: {} tokens", amount);
+        Ok(())
+    }
+
+    /// Opens the caller's loyalty account; points accrue from then on in `stake`, `execute_trade`
+    /// and `claim_rewards` whenever the account is passed.
+    pub fn initialize_loyalty_account(ctx: Context<InitializeLoyaltyAccount>) -> Result<()> {
+        let loyalty_account = &mut ctx.accounts.loyalty_account;
+        loyalty_account.staker = ctx.accounts.staker.key();
+        loyalty_account.points = 0;
+        loyalty_account.last_accrued_at = Clock::get()?.unix_timestamp;
+        msg!("Loyalty account opened for {}", loyalty_account.staker);
+        Ok(())
+    }
+
+    /// Creates a staking competition running from `start_time` to `end_time`, with a dedicated SST
+    /// prize vault owned by the season. Once finalized, the top participants claim their prizes,
+    /// split by `prize_shares_bps` in rank order.
+    pub fn create_season(
+        ctx: Context<CreateSeason>,
+        season_id: u64,
+        start_time: i64,
+        end_time: i64,
+        prize_shares_bps: [u64; SEASON_WINNERS],
+    ) -> Result<()> {
+        let total_share_bps = prize_shares_bps.iter().try_fold(0u64, |sum, bps| sum.checked_add(*bps));
+        require!(
+            start_time < end_time
+                && end_time > Clock::get()?.unix_timestamp
+                && total_share_bps.is_some_and(|total| total <= BPS_DENOMINATOR),
+            ErrorCode::InvalidSeason
+        );
+        let season = &mut ctx.accounts.season;
+        season.id = season_id;
+        season.start_time = start_time;
+        season.end_time = end_time;
+        season.prize_vault = ctx.accounts.prize_vault.key();
+        season.prize_shares_bps = prize_shares_bps;
+        season.leaderboard = [SeasonEntry::default(); SEASON_WINNERS];
+        season.finalized = false;
+        season.prize_pool = 0;
+        msg!("Season {} created: {} to {}", season_id, start_time, end_time);
+        Ok(())
+    }
+
+    /// Registers the caller's position for a season. It then scores on its stake held over time,
+    /// re-synced by every instruction that changes the stake; joining replaces an earlier season.
+    pub fn join_season(ctx: Context<JoinSeason>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let season = &ctx.accounts.season;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!season.finalized && now < season.end_time, ErrorCode::SeasonEnded);
+        let stake_info = &mut ctx.accounts.stake_info;
+        let season_score = &mut ctx.accounts.season_score;
+        season_score.season = season.key();
+        season_score.participant = ctx.accounts.participant.key();
+        season_score.score = 0;
+        season_score.stake = stake_info.amount;
+        season_score.updated_at = now;
+        season_score.prize_claimed = false;
+        stake_info.season_score = season_score.key();
+        msg!("{} joined season {}", season_score.participant, season.id);
+        Ok(())
+    }
+
+    /// Brings a position's season score up to date, e.g. before the season is finalized.
+    /// Permissionless.
+    pub fn sync_season_score(ctx: Context<SyncSeasonScore>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let amount = stake_info.amount;
+        record_season_score(
+            Some(&mut ctx.accounts.season),
+            Some(&mut ctx.accounts.season_score),
+            stake_info,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        msg!("Season score synced: {}", ctx.accounts.season_score.score);
+        Ok(())
+    }
+
+    /// Permissionless once the season has ended and `SEASON_FINALIZE_DELAY` has passed for late
+    /// score syncs: freezes the leaderboard and the prize pool the winners claim from.
+    pub fn finalize_season(ctx: Context<FinalizeSeason>) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        require!(!season.finalized, ErrorCode::SeasonFinalized);
+        require!(
+            Clock::get()?.unix_timestamp >= season.end_time.saturating_add(SEASON_FINALIZE_DELAY),
+            ErrorCode::SeasonNotEnded
+        );
+        season.prize_pool = ctx.accounts.prize_vault.amount;
+        season.finalized = true;
+        let winners = season.leaderboard.iter().filter(|entry| entry.participant != Pubkey::default()).count();
+        msg!("Season {} finalized with {} winners", season.id, winners);
+        Ok(())
+    }
+
+    /// Claim the caller's prize from a finalized season: their rank's share of the prize pool.
+    pub fn claim_season_prize(ctx: Context<ClaimSeasonPrize>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let season = &ctx.accounts.season;
+        require!(season.finalized, ErrorCode::SeasonNotEnded);
+        let season_score = &mut ctx.accounts.season_score;
+        require!(!season_score.prize_claimed, ErrorCode::SeasonPrizeClaimed);
+        let participant = ctx.accounts.participant.key();
+        let (rank, entry) = season
+            .leaderboard
+            .iter()
+            .enumerate()
+            .find(|(_, entry)| entry.participant == participant)
+            .ok_or(ErrorCode::NoSeasonPrize)?;
+        let prize = mul_div_floor(
+            season.prize_pool as u128,
+            season.prize_shares_bps[rank] as u128,
+            BPS_DENOMINATOR as u128,
+        )?;
+        require!(prize > 0, ErrorCode::NoSeasonPrize);
+        season_score.prize_claimed = true;
+
+        let season_id = season.id.to_le_bytes();
+        let seeds = &[b"season".as_ref(), season_id.as_ref(), &[ctx.bumps.season]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.prize_vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.season.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, &[&seeds[..]]), prize)?;
+        emit!(SeasonPrizePaid {
+            season_id: season.id,
+            participant,
+            rank: rank as u8,
+            score: entry.score,
+            prize,
+        });
+        Ok(())
+    }
+
+    /// Claim-as mode: `Some(mint)` makes the position take its rewards in `mint` (e.g. USDC)
+    /// through the approved payout route, so SST payouts are refused and claims must go through
+    /// `claim_rewards_routed`; `None` switches back to SST. Compounding is unaffected.
+    pub fn set_claim_as(ctx: Context<SetClaimAs>, payout_mint: Option<Pubkey>) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        let payout_mint = payout_mint.unwrap_or_default();
+        if payout_mint != Pubkey::default() {
+            ctx.accounts
+                .payout_routes
+                .find(ctx.accounts.reward_vault.mint, payout_mint)
+                .ok_or(ErrorCode::InvalidPayoutRoute)?;
+        }
+        stake_info.claim_as_mint = payout_mint;
+        emit!(ClaimAsModeSet {
+            staker: stake_info.staker,
+            payout_mint,
+        });
+        msg!("Claim-as mint set to: {}", payout_mint);
+        Ok(())
+    }
+
+    /// Withdraw deposited LP tokens back to the staker once the configured LP lockup since the
+    /// last LP deposit has passed.
+    pub fn withdraw_lp(ctx: Context<WithdrawLP>, lp_amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(lp_amount <= stake_info.lp_deposit, ErrorCode::InsufficientStakedAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let unlocks_at = stake_info.lp_deposited_at.saturating_add(ctx.accounts.config.lp_lockup_period);
+        require!(now >= unlocks_at, ErrorCode::LpLocked);
+        stake_info.accrue_lp_boost(now)?;
+        // Registry deposits made before the vault was recorded fall back to their mint's vault;
+        // pre-registry deposits need `assign_lp_vault` first.
+        let lp_vault = if stake_info.lp_vault != Pubkey::default() {
+            stake_info.lp_vault
+        } else {
+            ctx.accounts.lp_mints.mints
+                .iter()
+                .find(|entry| stake_info.lp_mint != Pubkey::default() && entry.mint == stake_info.lp_mint)
+                .map(|entry| entry.vault)
+                .ok_or(ErrorCode::LpVaultUnknown)?
+        };
+        require_keys_eq!(ctx.accounts.vault_lp_token_account.key(), lp_vault, ErrorCode::InvalidLpPoolAccounts);
+        let entry = ctx.accounts.lp_mints.mints
+            .iter_mut()
+            .find(|entry| entry.vault == lp_vault)
+            .ok_or(ErrorCode::LpMintNotApproved)?;
+        entry.total_deposited = entry.total_deposited.checked_sub(lp_amount).ok_or(ErrorCode::Underflow)?;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_lp_token_account.to_account_info(),
+            to: ctx.accounts.staker_lp_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), lp_amount)?;
+        stake_info.lp_deposit = stake_info.lp_deposit.checked_sub(lp_amount).ok_or(ErrorCode::Underflow)?;
+        stake_info.clear_lp_mint_if_empty();
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_lp_deposited = protocol_stats.total_lp_deposited.checked_sub(lp_amount).ok_or(ErrorCode::Underflow)?;
+        msg!("LP withdrawn: {} tokens ({} still deposited)", lp_amount, stake_info.lp_deposit);
+        Ok(())
+    }
+
+    /// Create an LP farm for `lp_mint` (config authority only). Deposited LP tokens earn the
+    /// farm's own reward emission, independent of SST staking rewards and the LP boost.
+    pub fn create_lp_pool(ctx: Context<CreateLpPool>, tokens_per_second: u64) -> Result<()> {
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        lp_pool.lp_mint = ctx.accounts.lp_mint.key();
+        lp_pool.lp_vault = ctx.accounts.lp_vault.key();
+        lp_pool.total_deposited = 0;
+        lp_pool.rewards = RewardToken {
+            mint: ctx.accounts.reward_vault.mint,
+            vault: ctx.accounts.reward_vault.key(),
+            tokens_per_second,
+            reward_per_token_stored: 0,
+            last_update_time: Clock::get()?.unix_timestamp,
+            active: true,
+            reward_dust: 0,
+        };
+        msg!(
+            "LP pool for {} created: {} tokens/s of {}",
+            lp_pool.lp_mint,
+            tokens_per_second,
+            lp_pool.rewards.mint
+        );
+        Ok(())
+    }
+
+    /// Change an LP farm's emission rate or pause it (config authority only). Accrual is
+    /// checkpointed under the old settings first.
+    pub fn update_lp_pool(ctx: Context<UpdateLpPool>, tokens_per_second: u64, active: bool) -> Result<()> {
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        lp_pool.rewards.update_reward_per_token(lp_pool.total_deposited, Clock::get()?.unix_timestamp)?;
+        lp_pool.rewards.tokens_per_second = tokens_per_second;
+        lp_pool.rewards.active = active;
+        msg!("LP pool {} now emits {} tokens/s (active: {})", lp_pool.lp_mint, tokens_per_second, active);
+        Ok(())
+    }
+
+    /// Deposit LP tokens into an LP farm.
+    pub fn deposit_lp_farm(ctx: Context<DepositLpFarm>, lp_amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let position = &mut ctx.accounts.lp_farm_position;
+        position.lp_pool = lp_pool.key();
+        position.staker = ctx.accounts.staker.key();
+        settle_lp_farm(lp_pool, position, now)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.staker_lp_token_account.to_account_info(),
+            to: ctx.accounts.lp_vault.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), lp_amount)?;
+
+        position.amount = position.amount.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        position.deposited_at = now;
+        lp_pool.total_deposited = lp_pool.total_deposited.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        msg!("LP farm deposit: {} tokens of {}", lp_amount, lp_pool.lp_mint);
+        Ok(())
+    }
+
+    /// Withdraw LP tokens from an LP farm, subject to the configured LP lockup. Accrued farm
+    /// rewards stay claimable.
+    pub fn withdraw_lp_farm(ctx: Context<WithdrawLpFarm>, lp_amount: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let position = &mut ctx.accounts.lp_farm_position;
+        require!(lp_amount <= position.amount, ErrorCode::InsufficientStakedAmount);
+        require!(
+            now >= position.deposited_at.saturating_add(ctx.accounts.config.lp_lockup_period),
+            ErrorCode::LpLocked
+        );
+        settle_lp_farm(lp_pool, position, now)?;
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.lp_vault.to_account_info(),
+            to: ctx.accounts.staker_lp_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), lp_amount)?;
+
+        position.amount = position.amount.checked_sub(lp_amount).ok_or(ErrorCode::Underflow)?;
+        lp_pool.total_deposited = lp_pool.total_deposited.checked_sub(lp_amount).ok_or(ErrorCode::Underflow)?;
+        msg!("LP farm withdrawal: {} tokens of {}", lp_amount, lp_pool.lp_mint);
+        Ok(())
+    }
+
+    /// Claim the farm rewards accrued by an LP farm position.
+    pub fn claim_lp_farm_rewards(ctx: Context<ClaimLpFarmRewards>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let now = Clock::get()?.unix_timestamp;
+        let lp_pool = &mut ctx.accounts.lp_pool;
+        let position = &mut ctx.accounts.lp_farm_position;
+        settle_lp_farm(lp_pool, position, now)?;
+        let amount = position.accrued_rewards;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.staker_reward_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+        position.accrued_rewards = 0;
+        emit!(LpFarmRewardsClaimed {
+            lp_pool: lp_pool.key(),
+            staker: position.staker,
+            mint: lp_pool.rewards.mint,
+            amount,
+        });
+        msg!("LP farm rewards claimed: {} tokens of {}", amount, lp_pool.rewards.mint);
+        Ok(())
+    }
+
+    /// Create the registry of approved LP mints (config authority only).
+    pub fn initialize_lp_mint_registry(ctx: Context<InitializeLpMintRegistry>) -> Result<()> {
+        ctx.accounts.lp_mints.mints = Vec::new();
+        msg!("LP mint registry initialized");
+        Ok(())
+    }
+
+    /// Record the vault of LP deposited before the LP registry (config authority only), so
+    /// `withdraw_lp` releases it from that vault alone. The deposit then counts toward the mint's
+    /// registry total.
+    pub fn assign_lp_vault(ctx: Context<AssignLpVault>, lp_vault: Pubkey) -> Result<()> {
+        let stake_info = &mut ctx.accounts.stake_info;
+        require!(
+            stake_info.lp_deposit > 0 && stake_info.lp_mint == Pubkey::default() && stake_info.lp_vault == Pubkey::default(),
+            ErrorCode::LpVaultAlreadyKnown
+        );
+        let entry = ctx.accounts.lp_mints.mints
+            .iter_mut()
+            .find(|entry| entry.vault == lp_vault)
+            .ok_or(ErrorCode::LpMintNotApproved)?;
+        entry.total_deposited = entry.total_deposited.checked_add(stake_info.lp_deposit).ok_or(ErrorCode::Overflow)?;
+        stake_info.lp_mint = entry.mint;
+        stake_info.lp_vault = entry.vault;
+        stake_info.lp_boost_weight_bps = entry.boost_weight_bps;
+        msg!("LP vault {} assigned to {} ({} LP)", lp_vault, stake_info.staker, stake_info.lp_deposit);
+        Ok(())
+    }
+
+    /// Approve an LP mint for `deposit_lp`, or update its deposit cap (0 = unlimited), boost
+    /// weight and active flag (config authority only). The mint is taken from `lp_vault`.
+    pub fn set_lp_mint(ctx: Context<SetLpMint>, cap: u64, boost_weight_bps: u64, active: bool) -> Result<()> {
+        require!(
+            boost_weight_bps > 0 && boost_weight_bps <= MAX_LP_BOOST_WEIGHT_BPS,
+            ErrorCode::InvalidRewardMultiplier
+        );
+        let mint = ctx.accounts.lp_vault.mint;
+        let vault = ctx.accounts.lp_vault.key();
+        let lp_mints = &mut ctx.accounts.lp_mints;
+        match lp_mints.mints.iter_mut().find(|entry| entry.mint == mint) {
+            Some(entry) => {
+                require_keys_eq!(entry.vault, vault, ErrorCode::InvalidLpPoolAccounts);
+                entry.cap = cap;
+                entry.boost_weight_bps = boost_weight_bps;
+                entry.active = active;
+            }
+            None => {
+                require!(lp_mints.mints.len() < MAX_LP_MINTS, ErrorCode::TooManyLpMints);
+                lp_mints.mints.push(LpMintEntry {
+                    mint,
+                    vault,
+                    cap,
+                    boost_weight_bps,
+                    total_deposited: 0,
+                    active,
+                });
+            }
+        }
+        msg!("LP mint {}: cap {}, boost weight {} bps (active: {})", mint, cap, boost_weight_bps, active);
+        Ok(())
+    }
+
+    /// Deposit LP tokens under a 30, 90 or 180-day lock. Locked LP counts toward the LP boost
+    /// with the tier's LP multiplier and is withdrawn through `withdraw_locked_lp` once matured.
+    pub fn deposit_lp_locked(ctx: Context<DepositLpLocked>, lock_id: u64, lp_amount: u64, lock_period: u64) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+        require!(ALLOWED_LOCK_PERIODS.contains(&lock_period), ErrorCode::InvalidLockPeriod);
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.accrue_lp_boost(Clock::get()?.unix_timestamp)?;
+        let lp_mint = ctx.accounts.staker_lp_token_account.mint;
+        record_lp_deposit(
+            &mut ctx.accounts.lp_mints,
+            stake_info,
+            lp_mint,
+            ctx.accounts.vault_lp_token_account.key(),
+            lp_amount,
+        )?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.staker_lp_token_account.to_account_info(),
+            to: ctx.accounts.vault_lp_token_account.to_account_info(),
+            authority: ctx.accounts.staker.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), lp_amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let multiplier_bps = ctx.accounts.config.lp_lock_multiplier_bps(lock_period);
+        let weight = mul_div_floor(lp_amount as u128, multiplier_bps as u128, BPS_DENOMINATOR as u128)?;
+        let lp_lock = &mut ctx.accounts.lp_lock;
+        lp_lock.staker = stake_info.staker;
+        lp_lock.lock_id = lock_id;
+        lp_lock.lp_mint = lp_mint;
+        lp_lock.amount = lp_amount;
+        lp_lock.weight = weight;
+        lp_lock.lock_period = lock_period;
+        lp_lock.locked_until = now.checked_add(lock_period as i64).ok_or(ErrorCode::Overflow)?;
+        stake_info.locked_lp_deposit = stake_info.locked_lp_deposit.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        stake_info.locked_lp_weight = stake_info.locked_lp_weight.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_lp_deposited = protocol_stats.total_lp_deposited.checked_add(lp_amount).ok_or(ErrorCode::Overflow)?;
+        msg!(
+            "Locked LP deposit {}: {} tokens until {} (weight {})",
+            lock_id,
+            lp_amount,
+            lp_lock.locked_until,
+            weight
+        );
+        Ok(())
+    }
+
+    /// Withdraw a matured locked LP deposit and close its record.
+    pub fn withdraw_locked_lp(ctx: Context<WithdrawLockedLp>) -> Result<()> {
+        ensure_no_incident(&ctx.accounts.config)?;
+        let lp_lock = &ctx.accounts.lp_lock;
+        require!(Clock::get()?.unix_timestamp >= lp_lock.locked_until, ErrorCode::LpLocked);
+        let entry = ctx.accounts.lp_mints.mints
+            .iter_mut()
+            .find(|entry| entry.mint == lp_lock.lp_mint)
+            .ok_or(ErrorCode::LpMintNotApproved)?;
+        require_keys_eq!(ctx.accounts.vault_lp_token_account.key(), entry.vault, ErrorCode::InvalidLpPoolAccounts);
+        entry.total_deposited = entry.total_deposited.saturating_sub(lp_lock.amount);
+
+        let seeds = ctx.accounts.config.vault_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_lp_token_account.to_account_info(),
+            to: ctx.accounts.staker_lp_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), lp_lock.amount)?;
+
+        let stake_info = &mut ctx.accounts.stake_info;
+        stake_info.accrue_lp_boost(Clock::get()?.unix_timestamp)?;
+        stake_info.locked_lp_deposit = stake_info.locked_lp_deposit.checked_sub(lp_lock.amount).ok_or(ErrorCode::Underflow)?;
+        stake_info.locked_lp_weight = stake_info.locked_lp_weight.checked_sub(lp_lock.weight).ok_or(ErrorCode::Underflow)?;
+        stake_info.clear_lp_mint_if_empty();
+
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.total_lp_deposited = protocol_stats.total_lp_deposited.checked_sub(lp_lock.amount).ok_or(ErrorCode::Underflow)?;
+        msg!("Locked LP deposit {} withdrawn: {} tokens", lp_lock.lock_id, lp_lock.amount);
+        Ok(())
+    }
+}
+
+/// Helper: rejects position changes while the guardian has paused the protocol or an incident
+/// pause is active.
+fn ensure_no_incident(config: &ProtocolConfig) -> Result<()> {
+    require!(!config.paused, ErrorCode::ProtocolPaused);
+    require!(config.incident_started_at == 0, ErrorCode::IncidentActive);
+    Ok(())
+}
+
+/// Helper: rejects debt settlement only during an incident pause. A guardian pause leaves
+/// repayment and liquidation open, since borrow interest keeps accruing while it lasts.
+fn ensure_debt_settleable(config: &ProtocolConfig) -> Result<()> {
+    require!(config.incident_started_at == 0, ErrorCode::IncidentActive);
+    Ok(())
+}
+
+/// Helper: interest owed on `principal` for the growth of a borrow index from `index_paid` to
+/// `borrow_index` (nothing before the position first accrued).
+fn index_interest(principal: u64, borrow_index: u128, index_paid: u128) -> Result<u64> {
+    if principal == 0 || index_paid == 0 || borrow_index <= index_paid {
+        return Ok(0);
+    }
+    mul_div_ceil(principal as u128, borrow_index - index_paid, index_paid)
+}
+
+/// Helper: SST price in USDC (scaled by `PRICE_SCALE`) from the configured pull feed. Rejects
+/// other accounts, stale or under-sampled values, and values whose spread exceeds the confidence bound.
+fn read_sst_price(price_feed: &AccountInfo, oracle: &PriceOracle) -> Result<u128> {
+    require!(
+        oracle.feed != Pubkey::default() && price_feed.key() == oracle.feed,
+        ErrorCode::InvalidPriceFeed
+    );
+    let feed = PullFeedAccountData::parse(price_feed.data.borrow()).map_err(|_| ErrorCode::InvalidPriceFeed)?;
+    let value = feed
+        .get_value(&Clock::get()?, oracle.max_staleness_slots, oracle.min_samples.max(1), true)
+        .map_err(|_| ErrorCode::StalePrice)?;
+    let price = scale_price(value.mantissa(), value.scale())?;
+    let std_dev = feed.result.std_dev().ok_or(ErrorCode::InvalidPriceFeed)?;
+    let spread = scale_price(std_dev.mantissa(), std_dev.scale())?;
+    require!(price > 0, ErrorCode::InvalidPriceFeed);
+    require!(
+        spread * (BPS_DENOMINATOR as u128) <= price * oracle.max_confidence_bps as u128,
+        ErrorCode::PriceConfidenceTooWide
+    );
+    Ok(price)
+}
+
+/// Helper: a decimal `mantissa * 10^-scale` as a `PRICE_SCALE` fixed-point value.
+fn scale_price(mantissa: i128, scale: u32) -> Result<u128> {
+    let mantissa = u128::try_from(mantissa).map_err(|_| ErrorCode::InvalidPriceFeed)?;
+    let divisor = 10u128.checked_pow(scale).ok_or(ErrorCode::InvalidPriceFeed)?;
+    Ok(mantissa.checked_mul(PRICE_SCALE).ok_or(ErrorCode::Overflow)? / divisor)
+}
+
+/// Helper: whether `amount` staked SST reaches the VIP threshold. With a USD threshold set, the
+/// stake is valued at the oracle price; callers that pass no price feed are not VIP.
+fn is_vip(config: &ProtocolConfig, amount: u64, price_feed: Option<&AccountInfo>) -> Result<bool> {
+    if config.vip_threshold_usd == 0 {
+        return Ok(amount >= config.vip_threshold);
+    }
+    let price_feed = match price_feed {
+        Some(price_feed) => price_feed,
+        None => return Ok(false),
+    };
+    let price = read_sst_price(price_feed, &config.price_oracle)?;
+    let value = amount as u128 * price / PRICE_SCALE;
+    Ok(value >= config.vip_threshold_usd as u128)
+}
+
+/// Helper: the SST price and USDC market terms a position's USDC loan is valued at, or `None`
+/// when it owes no USDC. With a USDC loan open, the market and a live price feed are required.
+fn usdc_loan_terms<'a>(
+    stake_info: &StakeInfo,
+    config: &ProtocolConfig,
+    usdc_market: Option<&'a UsdcMarket>,
+    price_feed: Option<&AccountInfo>,
+) -> Result<Option<(u128, &'a UsdcMarketParams)>> {
+    if stake_info.usdc_debt()? == 0 {
+        return Ok(None);
+    }
+    let usdc_market = usdc_market.ok_or(ErrorCode::InvalidPriceFeed)?;
+    let price = read_sst_price(price_feed.ok_or(ErrorCode::InvalidPriceFeed)?, &config.price_oracle)?;
+    Ok(Some((price, &usdc_market.params)))
+}
+
+/// Helper: staked SST needed to back `usdc_debt` at `price` (USDC per SST, scaled by
+/// `PRICE_SCALE`) and a loan-to-value of `ltv_bps`.
+fn usdc_collateral_required(usdc_debt: u64, price: u128, ltv_bps: u64) -> Result<u64> {
+    require!(price > 0 && ltv_bps > 0, ErrorCode::InvalidPriceFeed);
+    mul_div_ceil(usdc_debt as u128, PRICE_SCALE * BPS_DENOMINATOR as u128, price * ltv_bps as u128)
+}
+
+/// Helper: requires the loan to be a top-level instruction and an instruction after it in this
+/// transaction to be a `repay_flash_loan` of this program for `stake_info` into
+/// `vault_token_account`. While an allowlist is enforced, every instruction before the repayment
+/// must target this program or an allowlisted one (CPIs made by those programs are not inspected).
+fn require_flash_loan_repayment(
+    instructions: &AccountInfo,
+    allowlist: Option<&FlashLoanAllowlist>,
+    stake_info: Pubkey,
+    vault_token_account: Pubkey,
+) -> Result<()> {
+    // Called through CPI, the caller's own later CPIs would escape the checks below.
+    require!(get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT, ErrorCode::FlashLoanCpiNotAllowed);
+    let enforced = allowlist.filter(|allowlist| allowlist.enforced);
+    let current = load_current_index_checked(instructions)? as usize;
+    let mut index = current + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.starts_with(&instruction::RepayFlashLoan::DISCRIMINATOR)
+            && ix.accounts.get(1).map(|meta| meta.pubkey) == Some(stake_info)
+            && ix.accounts.get(2).map(|meta| meta.pubkey) == Some(vault_token_account)
+        {
+            return Ok(());
+        }
+        require!(
+            !enforced.is_some_and(|allowlist| {
+                ix.program_id != crate::ID && !allowlist.programs.contains(&ix.program_id)
+            }),
+            ErrorCode::FlashLoanProgramNotAllowed
+        );
+        index += 1;
+    }
+    err!(ErrorCode::FlashLoanNotRepaid)
+}
+
+/// Helper: bump of the original `[b"vault"]` PDA, which keeps authority over receipt NFTs across
+/// vault rotations.
+fn receipt_authority_bump(program_id: &Pubkey) -> u8 {
+    Pubkey::find_program_address(&[b"vault".as_ref()], program_id).1
+}
+
+/// Helper: the (mint, token account, original vault authority) of a position's receipt NFT,
+/// required whenever the position has one.
+fn receipt_accounts<'info>(
+    stake_info: &StakeInfo,
+    receipt_mint: Option<AccountInfo<'info>>,
+    receipt_token_account: Option<AccountInfo<'info>>,
+    receipt_authority: Option<AccountInfo<'info>>,
+) -> Result<(AccountInfo<'info>, AccountInfo<'info>, AccountInfo<'info>)> {
+    match (receipt_mint, receipt_token_account, receipt_authority) {
+        (Some(receipt_mint), Some(receipt_token_account), Some(receipt_authority)) => {
+            require_keys_eq!(receipt_mint.key(), stake_info.receipt_mint, ErrorCode::ReceiptRequired);
+            Ok((receipt_mint, receipt_token_account, receipt_authority))
+        }
+        _ => err!(ErrorCode::ReceiptRequired),
+    }
+}
+
+/// Helper: burns the position receipt NFT on a full exit and clears `stake_info.receipt_mint`; a
+/// no-op for positions without one. `holder` signs the burn as the token account owner; without
+/// one, the original vault authority burns as the delegate approved by `schedule_withdrawal`.
+fn burn_position_receipt<'info>(
+    stake_info: &mut StakeInfo,
+    receipt_mint: Option<AccountInfo<'info>>,
+    receipt_token_account: Option<AccountInfo<'info>>,
+    receipt_authority: Option<AccountInfo<'info>>,
+    holder: Option<AccountInfo<'info>>,
+    token_program: AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    if stake_info.receipt_mint == Pubkey::default() {
+        return Ok(());
+    }
+    let (receipt_mint, receipt_token_account, receipt_authority) =
+        receipt_accounts(stake_info, receipt_mint, receipt_token_account, receipt_authority)?;
+    let bump = [receipt_authority_bump(program_id)];
+    let seeds = &[b"vault".as_ref(), &bump];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = ThawAccount {
+        account: receipt_token_account.clone(),
+        mint: receipt_mint.clone(),
+        authority: receipt_authority.clone(),
+    };
+    token::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+    let cpi_accounts = Burn {
+        mint: receipt_mint,
+        from: receipt_token_account,
+        authority: holder.unwrap_or(receipt_authority),
+    };
+    token::burn(CpiContext::new_with_signer(token_program, cpi_accounts, signer), 1)?;
+    stake_info.receipt_mint = Pubkey::default();
+    msg!("Position receipt burned");
+    Ok(())
+}
+
+/// Helper: approves the original vault authority to burn the position receipt NFT, so a keeper
+/// executing a scheduled withdrawal can burn it without the owner; a no-op for positions without one.
+fn approve_receipt_burn<'info>(
+    stake_info: &StakeInfo,
+    receipt_mint: Option<AccountInfo<'info>>,
+    receipt_token_account: Option<AccountInfo<'info>>,
+    receipt_authority: Option<AccountInfo<'info>>,
+    owner: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    if stake_info.receipt_mint == Pubkey::default() {
+        return Ok(());
+    }
+    let (receipt_mint, receipt_token_account, receipt_authority) =
+        receipt_accounts(stake_info, receipt_mint, receipt_token_account, receipt_authority)?;
+    let bump = [receipt_authority_bump(program_id)];
+    let seeds = &[b"vault".as_ref(), &bump];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = ThawAccount {
+        account: receipt_token_account.clone(),
+        mint: receipt_mint.clone(),
+        authority: receipt_authority.clone(),
+    };
+    token::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+    let cpi_accounts = Approve {
+        to: receipt_token_account.clone(),
+        delegate: receipt_authority.clone(),
+        authority: owner,
+    };
+    token::approve(CpiContext::new(token_program.clone(), cpi_accounts), 1)?;
+    let cpi_accounts = FreezeAccount {
+        account: receipt_token_account,
+        mint: receipt_mint,
+        authority: receipt_authority,
+    };
+    token::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+    Ok(())
+}
+
+/// Helper: validates a (delegator `StakeInfo`, delegator record PDA) pair passed by `delegate` and
+/// creates the record PDA under `seed`. Returns the delegator and their voting power at
+/// `snapshot_at`; the caller writes the record.
+#[allow(clippy::too_many_arguments)]
+fn open_delegator_record<'info>(
+    pair: &[AccountInfo<'info>],
+    seed: &[u8],
+    proposal: Pubkey,
+    delegate: Pubkey,
+    snapshot_at: i64,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<(Pubkey, u64)> {
+    require_keys_eq!(*pair[0].owner, crate::ID, ErrorCode::InvalidDelegatorAccounts);
+    let delegator = StakeInfo::try_deserialize(&mut &pair[0].try_borrow_data()?[..])?;
+    let (stake_address, _) = Pubkey::find_program_address(&[b"stake", delegator.staker.as_ref()], &crate::ID);
+    require!(
+        pair[0].key() == stake_address && delegator.delegate == delegate,
+        ErrorCode::InvalidDelegatorAccounts
+    );
+    let (record_address, bump) =
+        Pubkey::find_program_address(&[seed, proposal.as_ref(), delegator.staker.as_ref()], &crate::ID);
+    require_keys_eq!(pair[1].key(), record_address, ErrorCode::InvalidDelegatorAccounts);
+    // An existing record means the delegator's power was already cast on this proposal.
+    require!(pair[1].data_is_empty(), ErrorCode::DelegatorAlreadyVoted);
+
+    let delegated_power = calculate_voting_power(&delegator, snapshot_at)?;
+    let record_seeds: &[&[u8]] = &[seed, proposal.as_ref(), delegator.staker.as_ref(), &[bump]];
+    create_pda_account(&pair[1], payer, system_program, space, record_seeds)?;
+    Ok((delegator.staker, delegated_power))
+}
+
+/// Helper: whether a proposal calls `overturn_council_veto`, given all of its `instruction_count`
+/// instruction accounts in index order.
+fn overturns_council_veto(proposal: Pubkey, instruction_count: u8, instructions: &[AccountInfo]) -> Result<bool> {
+    require!(instructions.len() == instruction_count as usize, ErrorCode::InvalidProposalInstruction);
+    for (index, info) in instructions.iter().enumerate() {
+        let (address, _) = Pubkey::find_program_address(&[b"proposal_ix", proposal.as_ref(), &[index as u8]], &crate::ID);
+        require_keys_eq!(info.key(), address, ErrorCode::InvalidProposalInstruction);
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidProposalInstruction);
+        let proposal_instruction = ProposalInstruction::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        if proposal_instruction.program_id == crate::ID
+            && proposal_instruction.data.starts_with(&instruction::OverturnCouncilVeto::DISCRIMINATOR)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Helper: checks that `records` are exactly the delegator vote records cast along with
+/// `vote_record` and returns their total power. With a `choice` they are moved to it; without one
+/// they are closed, refunding their rent to `rent_destination`.
+fn update_delegator_votes<'info>(
+    records: &[AccountInfo<'info>],
+    proposal: Pubkey,
+    vote_record: &VoteRecord,
+    choice: Option<VoteChoice>,
+    rent_destination: &AccountInfo<'info>,
+) -> Result<u64> {
+    require!(records.len() == vote_record.delegator_count as usize, ErrorCode::InvalidDelegatorAccounts);
+    let mut keys: Vec<Pubkey> = records.iter().map(|record| record.key()).collect();
+    keys.sort();
+    keys.dedup();
+    require!(keys.len() == records.len(), ErrorCode::InvalidDelegatorAccounts);
+
+    let mut delegated_power: u64 = 0;
+    for info in records {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidDelegatorAccounts);
+        let mut record = VoteRecord::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let (record_address, _) =
+            Pubkey::find_program_address(&[b"vote", proposal.as_ref(), record.voter.as_ref()], &crate::ID);
+        require!(
+            info.key() == record_address && record.proposal == proposal && record.delegate == vote_record.voter,
+            ErrorCode::InvalidDelegatorAccounts
+        );
+        delegated_power = delegated_power.checked_add(record.voting_power).ok_or(ErrorCode::Overflow)?;
+        match choice {
+            Some(choice) => {
+                record.choice = choice;
+                record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+            None => {
+                let refund = info.lamports();
+                **rent_destination.try_borrow_mut_lamports()? =
+                    rent_destination.lamports().checked_add(refund).ok_or(ErrorCode::Overflow)?;
+                **info.try_borrow_mut_lamports()? = 0;
+                info.assign(&anchor_lang::system_program::ID);
+                info.realloc(0, false)?;
+            }
+        }
+    }
+    Ok(delegated_power)
+}
+
+/// Helper: creates a program-owned PDA of `space` bytes, funded by `payer`. Like Anchor's `init`, it
+/// tops up, allocates and assigns an address that already holds lamports instead of failing on it.
+fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let current = info.lamports();
+    if current == 0 {
+        return anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+                &[seeds],
+            ),
+            required,
+            space as u64,
+            &crate::ID,
+        );
+    }
+    let top_up = required.saturating_sub(current);
+    if top_up > 0 {
+        let cpi_accounts = anchor_lang::system_program::Transfer {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        anchor_lang::system_program::transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), top_up)?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Allocate { account_to_allocate: info.clone() },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Assign { account_to_assign: info.clone() },
+            &[seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Helper: grows a program-owned account to `new_len` bytes, zero-filling the tail, with `payer`
+/// topping up rent.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+    let required = Rent::get()?.minimum_balance(new_len);
+    let top_up = required.saturating_sub(info.lamports());
+    if top_up > 0 {
+        let cpi_accounts = anchor_lang::system_program::Transfer {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_program = system_program.to_account_info();
+        anchor_lang::system_program::transfer(CpiContext::new(cpi_program, cpi_accounts), top_up)?;
+    }
+    info.realloc(new_len, true)?;
+    Ok(())
+}
+
+/// Helper: rejects borrows that would exceed a debt market's global or per-wallet principal caps
+/// (0 = unlimited).
+fn enforce_borrow_caps(global_cap: u64, wallet_cap: u64, total_borrowed: u64, wallet_borrowed: u64) -> Result<()> {
+    if wallet_cap > 0 {
+        require!(wallet_borrowed <= wallet_cap, ErrorCode::WalletBorrowCapExceeded);
+    }
+    if global_cap > 0 {
+        require!(total_borrowed <= global_cap, ErrorCode::BorrowCapExceeded);
+    }
+    Ok(())
+}
+
+/// Helper: rejects deposits that would exceed the configured per-wallet or global stake caps.
+fn enforce_stake_caps(config: &ProtocolConfig, wallet_staked: u64, total_staked: u64) -> Result<()> {
+    if config.max_stake_per_wallet > 0 {
+        require!(wallet_staked <= config.max_stake_per_wallet, ErrorCode::WalletStakeCapExceeded);
+    }
+    if config.global_stake_cap > 0 {
+        require!(total_staked <= config.global_stake_cap, ErrorCode::GlobalStakeCapExceeded);
+    }
+    Ok(())
+}
+
+/// Helper: validates an unstake against the minimum withdrawal and sweeps any remainder
+/// that would fall below the minimum stake, returning the amount to actually withdraw.
+fn apply_dust_sweep(config: &ProtocolConfig, staked_amount: u64, requested: u64) -> Result<u64> {
+    let remaining = staked_amount.checked_sub(requested).ok_or(ErrorCode::InsufficientStakedAmount)?;
+    if remaining == 0 {
+        return Ok(requested);
+    }
+    require!(requested >= config.min_withdrawal_amount, ErrorCode::BelowMinimumWithdrawal);
+    if remaining < config.min_stake_amount {
+        msg!("Sweeping {} dust tokens below the minimum stake", remaining);
+        return Ok(staked_amount);
+    }
+    Ok(requested)
+}
+
+/// Helper: calculates dynamic fee discount.
+fn calculate_fee_discount(staked_amount: u64, staking_duration: i64) -> u64 {
+    let base_discount = staked_amount / 1000;
+    let duration_bonus = (staking_duration / (30 * 24 * 60 * 60)) as u64;
+    std::cmp::min(base_discount + duration_bonus, 50)
+}
+
+/// Helper: fee-discount curve v2 — 1% per 1,000 whole SST staked (up to 40%) plus 1% per month
+/// staked (up to 10%).
+fn calculate_fee_discount_v2(staked_amount: u64, staking_duration: i64) -> u64 {
+    let base_discount = std::cmp::min(staked_amount / (1_000 * 1_000_000), 40);
+    let duration_bonus = std::cmp::min((staking_duration / (30 * 24 * 60 * 60)).max(0) as u64, 10);
+    base_discount + duration_bonus
+}
+
+/// Helper: fee discount under the given curve version.
+fn fee_discount_for_version(version: u8, staked_amount: u64, staking_duration: i64) -> u64 {
+    match version {
+        2 => calculate_fee_discount_v2(staked_amount, staking_duration),
+        _ => calculate_fee_discount(staked_amount, staking_duration),
+    }
+}
+
+/// Helper: claimable reward — rewards accrued through the reward-per-token accumulator plus the
+/// LP boost taken from the position — returned as (base, LP boost, total).
+fn calculate_claim_reward(accrued_rewards: u64, lp_boost: u64) -> Result<(u64, u64, u64)> {
+    let base_reward = accrued_rewards;
+    let total_reward = base_reward.checked_add(lp_boost).ok_or(ErrorCode::Overflow)?;
+    Ok((base_reward, lp_boost, total_reward))
+}
+
+/// Helper: part of `reward`, earned evenly from `settled_at` to `now`, that accrued on or before
+/// `locked_until`. Positions never settled with a timestamp earn at par once the lock has expired.
+fn locked_share(reward: u64, settled_at: i64, locked_until: i64, now: i64) -> Result<u64> {
+    if locked_until >= now {
+        return Ok(reward);
+    }
+    if settled_at == 0 || locked_until <= settled_at {
+        return Ok(0);
+    }
+    mul_div_floor(reward as u128, (locked_until - settled_at) as u128, (now - settled_at) as u128)
+}
+
+/// Helper: settles a position's rewards against the global reward-per-token accumulator.
+/// Must run before the position's amount changes so past accrual uses the old balance.
+fn settle_rewards(
+    protocol_stats: &mut ProtocolStats,
+    schedule: &EmissionSchedule,
+    reward_tokens: &mut RewardTokenRegistry,
+    config: &ProtocolConfig,
+    stake_info: &mut StakeInfo,
+    now: i64,
+) -> Result<()> {
+    protocol_stats.update_reward_per_token(schedule, now)?;
+    let stored = protocol_stats.reward_per_token_stored;
+    let earning_amount = stake_info.amount.checked_sub(stake_info.pending_stake).ok_or(ErrorCode::Underflow)?;
+    let (mut earned, mut dust) = rewards_earned(earning_amount, stored, stake_info.reward_per_token_paid)?;
+    if stake_info.pending_stake > 0 && reward_epoch(now) > stake_info.pending_stake_epoch {
+        let activated_at = protocol_stats
+            .activation_reward_per_token(stake_info.pending_stake_epoch)
+            .max(stake_info.reward_per_token_paid);
+        let (pending_earned, pending_dust) = rewards_earned(stake_info.pending_stake, stored, activated_at)?;
+        earned = earned.checked_add(pending_earned).ok_or(ErrorCode::Overflow)?;
+        dust = dust.checked_add(pending_dust).ok_or(ErrorCode::Overflow)?;
+        stake_info.pending_stake = 0;
+    }
+    // Lock tiers weight the emission rather than add to it: the share earned while locked pays at
+    // the tier multiplier, the rest at par, both relative to the largest multiplier. What a position
+    // earns below that top weight goes back into the pool for every staker.
+    let base_reward = earned;
+    let lock_multiplier_bps = config.lock_reward_multiplier_bps(stake_info.lock_period);
+    let top_multiplier_bps = config.max_lock_reward_multiplier_bps();
+    let locked_reward = locked_share(base_reward, stake_info.rewards_settled_at, stake_info.locked_until, now)?;
+    let locked_earned = mul_div_floor(locked_reward as u128, lock_multiplier_bps as u128, top_multiplier_bps as u128)?;
+    let unlocked_earned = mul_div_floor(
+        (base_reward - locked_reward) as u128,
+        BPS_DENOMINATOR as u128,
+        top_multiplier_bps as u128,
+    )?;
+    let earned = locked_earned.checked_add(unlocked_earned).ok_or(ErrorCode::Overflow)?;
+    let redistributed = base_reward.checked_sub(earned).ok_or(ErrorCode::Underflow)?;
+    let lock_bonus = mul_div_floor(
+        locked_reward as u128,
+        lock_multiplier_bps.saturating_sub(BPS_DENOMINATOR) as u128,
+        top_multiplier_bps as u128,
+    )?;
+    stake_info.accrued_rewards = stake_info.accrued_rewards.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+    stake_info.reward_per_token_paid = protocol_stats.reward_per_token_stored;
+    stake_info.rewards_settled_at = now;
+    let dust = dust.checked_add(math::to_reward_scale(redistributed as u128)?).ok_or(ErrorCode::Overflow)?;
+    protocol_stats.reward_dust = protocol_stats.reward_dust.checked_add(dust).ok_or(ErrorCode::Overflow)?;
+
+    reward_tokens.update(protocol_stats.total_staked, now)?;
+    let mut partner_rewards = [0u64; MAX_REWARD_TOKENS];
+    for (slot, token) in reward_tokens.tokens.iter_mut().enumerate() {
+        let (earned, dust) = rewards_earned(stake_info.amount, token.reward_per_token_stored, stake_info.reward_token_paid[slot])?;
+        stake_info.reward_token_accrued[slot] = stake_info.reward_token_accrued[slot]
+            .checked_add(earned)
+            .ok_or(ErrorCode::Overflow)?;
+        stake_info.reward_token_paid[slot] = token.reward_per_token_stored;
+        token.reward_dust = token.reward_dust.checked_add(dust).ok_or(ErrorCode::Overflow)?;
+        partner_rewards[slot] = earned;
+    }
+
+    emit!(RewardsSettled {
+        staker: stake_info.staker,
+        base_reward,
+        lock_multiplier_bps,
+        lock_bonus,
+        redistributed,
+        partner_rewards,
+        accrued_rewards: stake_info.accrued_rewards,
+        reward_per_token: stored,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Helper: pushes the bonus pool's undistributed penalties into the reward accumulator, pro rata
+/// over the current earning stake. Carried over when nobody is earning. The accumulator must
+/// already be up to date.
+fn redistribute_bonus_pool(bonus_pool: &mut BonusPool, protocol_stats: &mut ProtocolStats) -> Result<()> {
+    let amount = bonus_pool.undistributed;
+    let earning_stake = protocol_stats.total_staked
+        .checked_sub(protocol_stats.pending_stake_total)
+        .ok_or(ErrorCode::Underflow)?;
+    if amount == 0 || earning_stake == 0 {
+        return Ok(());
+    }
+    let scaled = math::to_reward_scale(amount as u128)?;
+    let (increment, dust) = sweep_reward_dust(scaled, protocol_stats.reward_dust, earning_stake)?;
+    protocol_stats.reward_per_token_stored = protocol_stats.reward_per_token_stored
+        .checked_add(increment)
+        .ok_or(ErrorCode::Overflow)?;
+    protocol_stats.reward_dust = dust;
+    protocol_stats.rewards_outstanding = protocol_stats.rewards_outstanding
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    bonus_pool.total_distributed = bonus_pool.total_distributed.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    bonus_pool.undistributed = 0;
+    emit!(PenaltiesRedistributed {
+        amount,
+        earning_stake,
+        reward_per_token: protocol_stats.reward_per_token_stored,
+    });
+    Ok(())
+}
+
+/// Helper: records staker activity, cancelling any pending dormant closure.
+fn record_activity(stake_info: &mut StakeInfo, now: i64) {
+    stake_info.dormant_flagged_at = 0;
+    stake_info.last_activity_at = now;
+}
+
+/// Helper: credits a position flagged as dormant with the re-engagement bonus, paid from the
+/// funded re-engagement budget for as long as it lasts. Must run before `record_activity` clears
+/// the flag.
+fn grant_reengagement_bonus(
+    config: &mut ProtocolConfig,
+    protocol_stats: &mut ProtocolStats,
+    stake_info: &mut StakeInfo,
+) -> Result<()> {
+    let bonus = config.reengagement_bonus.min(config.reengagement_budget);
+    if stake_info.dormant_flagged_at == 0 || bonus == 0 {
+        return Ok(());
+    }
+    config.reengagement_budget -= bonus;
+    stake_info.accrued_rewards = stake_info.accrued_rewards.checked_add(bonus).ok_or(ErrorCode::Overflow)?;
+    protocol_stats.rewards_outstanding = protocol_stats.rewards_outstanding
+        .checked_add(bonus)
+        .ok_or(ErrorCode::Overflow)?;
+    emit!(ReengagementBonusGranted {
+        staker: stake_info.staker,
+        bonus,
+    });
+    Ok(())
+}
+
+/// Helper: rejects an instruction that moved a position from `from` into a state the lifecycle
+/// does not allow, and emits the change otherwise.
+fn finish_transition(stake_info: &StakeInfo, from: PositionState, now: i64) -> Result<()> {
+    let to = stake_info.state(now);
+    if !from.can_transition_to(to) {
+        msg!("Invalid position transition: {:?} -> {:?}", from, to);
+        return err!(ErrorCode::InvalidStateTransition);
+    }
+    if from != to {
+        emit!(PositionStateChanged {
+            staker: stake_info.staker,
+            from,
+            to,
+        });
+    }
+    Ok(())
+}
+
+/// Helper: limits a claim of `base_reward` plus `lp_boost` to the epoch reward cap, to what the
+/// reward vault holds and to the funded rewards outstanding, and records the grant as paid out.
+/// The grant covers the base reward first; only the unpaid base reward stays accrued on the
+/// position for a later claim, the unpaid boost lapses. Returns the (base, boost, total) paid.
+fn cap_claim(
+    protocol_stats: &mut ProtocolStats,
+    config: &ProtocolConfig,
+    stake_info: &mut StakeInfo,
+    base_reward: u64,
+    lp_boost: u64,
+    vault_balance: u64,
+    now: i64,
+) -> Result<(u64, u64, u64)> {
+    let requested = base_reward.checked_add(lp_boost).ok_or(ErrorCode::Overflow)?;
+    let available = vault_balance.min(protocol_stats.rewards_outstanding);
+    let granted = protocol_stats.take_epoch_allowance(config.epoch_reward_cap, requested.min(available), now)?;
+    protocol_stats.pay_out_rewards(granted)?;
+    let base_paid = granted.min(base_reward);
+    let carried = base_reward - base_paid;
+    stake_info.accrued_rewards = carried;
+    if carried > 0 {
+        emit!(RewardsCarriedOver {
+            staker: stake_info.staker,
+            paid: granted,
+            carried,
+        });
+        msg!("Reward payout limited: {} paid, {} carried over", granted, carried);
+    }
+    Ok((base_paid, granted - base_paid, granted))
+}
+
+/// Helper: reward epoch containing `timestamp`.
+fn reward_epoch(timestamp: i64) -> u64 {
+    (timestamp.max(0) / REWARD_EPOCH_DURATION) as u64
+}
+
+/// Helper: marks a fresh deposit as pending until the next reward epoch. Must run after
+/// `settle_rewards`, which activates deposits from earlier epochs.
+fn record_pending_stake(protocol_stats: &mut ProtocolStats, stake_info: &mut StakeInfo, amount: u64, now: i64) -> Result<()> {
+    stake_info.pending_stake = stake_info.pending_stake.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    stake_info.pending_stake_epoch = reward_epoch(now);
+    protocol_stats.pending_stake_total = protocol_stats.pending_stake_total
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Helper: after a withdrawal, treats the withdrawn tokens as coming from pending stake first, so
+/// pending stake never exceeds the `remaining` position.
+fn clamp_pending_stake(protocol_stats: &mut ProtocolStats, stake_info: &mut StakeInfo, remaining: u64) -> Result<()> {
+    if stake_info.pending_stake > remaining {
+        let released = stake_info.pending_stake - remaining;
+        stake_info.pending_stake = remaining;
+        protocol_stats.pending_stake_total = protocol_stats.pending_stake_total
+            .checked_sub(released)
+            .ok_or(ErrorCode::Underflow)?;
+    }
+    Ok(())
+}
+
+// Rounding policy: every division rounds in the protocol's favour. Amounts paid to users (rewards,
+// refunds, redemptions, minted shares, vested amounts) round down; amounts charged to or withheld
+// from users (penalties, slashes, still-vesting amounts) round up. Reward remainders are not lost:
+// they accrue as dust on the accumulator and are swept into the next distribution.
+
+/// Helper: splits a scaled emission plus carried dust into a per-token accumulator increment
+/// (rounded down) and the new dust remainder.
+fn sweep_reward_dust(scaled_emission: u128, dust: u128, total_staked: u64) -> Result<(u128, u128)> {
+    let distributable = scaled_emission.checked_add(dust).ok_or(ErrorCode::Overflow)?;
+    let total_staked = total_staked as u128;
+    Ok((distributable / total_staked, distributable % total_staked))
+}
+
+/// Helper: rewards earned by `amount` staked tokens as an accumulator moved from `paid` to `stored`,
+/// rounded down; the scaled remainder is returned as dust.
+fn rewards_earned(amount: u64, stored: u128, paid: u128) -> Result<(u64, u128)> {
+    let delta = stored.checked_sub(paid).ok_or(ErrorCode::Underflow)?;
+    math::reward_mul_floor(amount, delta)
+}
+
+/// Helper: LP boost earned by a weighted LP deposit held for `elapsed` seconds, at
+/// `LP_BOOST_APR_BPS` a year.
+fn lp_reward_boost(weighted_lp_deposit: u64, elapsed: i64) -> Result<u64> {
+    mul_div_floor(
+        (weighted_lp_deposit as u128).checked_mul(LP_BOOST_APR_BPS as u128).ok_or(ErrorCode::Overflow)?,
+        elapsed.max(0) as u128,
+        BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128,
+    )
+}
+
+/// Helper: returns a VIP multiplier based on staked amount.
+fn vip_multiplier(staked_amount: u64) -> u64 {
+    if staked_amount >= 10_000 * 1_000_000 {
+        130
+    } else if staked_amount >= 5_000 * 1_000_000 {
+        115
+    } else if staked_amount >= 1_000 * 1_000_000 {
+        105
+    } else {
+        100
+    }
+}
+
+/// Helper: annualizes rewards paid over `elapsed` seconds against `total_staked`, in basis points.
+fn calculate_apr_bps(period_rewards: u64, total_staked: u64, elapsed: i64) -> Result<u64> {
+    if total_staked == 0 || elapsed <= 0 {
+        return Ok(0);
+    }
+    let apr = (period_rewards as u128)
+        .checked_mul(BPS_DENOMINATOR as u128).ok_or(ErrorCode::Overflow)?
+        .checked_mul(SECONDS_PER_YEAR as u128).ok_or(ErrorCode::Overflow)?
+        .checked_div((total_staked as u128).checked_mul(elapsed as u128).ok_or(ErrorCode::Overflow)?)
+        .ok_or(ErrorCode::Underflow)?;
+    Ok(u64::try_from(apr).unwrap_or(u64::MAX))
+}
+
+/// Helper: calculates voting power based on staked amount and duration (+1% per full month staked).
+fn calculate_voting_power(stake_info: &StakeInfo, snapshot_at: i64) -> Result<u64> {
+    let (amount, _) = stake_info.voting_position_at(snapshot_at);
+    // Stake withdrawn since the snapshot no longer counts.
+    let base_power = amount.min(stake_info.amount);
+    Ok(base_power.saturating_add(stake_info.ve_balance_at(snapshot_at)?))
+}
+
+/// Helper: checks the proposer's position against the governance config's minimum proposer stake.
+fn ensure_proposer_stake(governance_config: &GovernanceConfig, proposer_stake_info: Option<&Account<StakeInfo>>) -> Result<()> {
+    if governance_config.min_proposer_stake == 0 {
+        return Ok(());
+    }
+    let staked = proposer_stake_info.map(|stake_info| stake_info.amount).unwrap_or(0);
+    require!(staked >= governance_config.min_proposer_stake, ErrorCode::InsufficientProposerStake);
+    Ok(())
+}
+
+/// Helper: transfers the governance config's proposal bond from the proposer into the bond vault
+/// and returns the amount bonded.
+fn take_proposal_bond<'info>(
+    governance_config: &GovernanceConfig,
+    proposer: &Signer<'info>,
+    proposer_token_account: Option<&Account<'info, TokenAccount>>,
+    bond_vault: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+) -> Result<u64> {
+    let bond = governance_config.proposal_bond;
+    if bond == 0 {
+        return Ok(0);
+    }
+    let (Some(proposer_token_account), Some(bond_vault)) = (proposer_token_account, bond_vault) else {
+        return err!(ErrorCode::ProposalBondRequired);
+    };
+    let cpi_accounts = Transfer {
+        from: proposer_token_account.to_account_info(),
+        to: bond_vault.to_account_info(),
+        authority: proposer.to_account_info(),
+    };
+    token::transfer(CpiContext::new(token_program.to_account_info(), cpi_accounts), bond)?;
+    Ok(bond)
+}
+
+/// Helper: early unlock penalty, scaled linearly by the fraction of the lock period remaining.
+fn calculate_early_unlock_penalty(
+    locked_amount: u64,
+    time_remaining: i64,
+    lock_period: u64,
+    max_penalty_bps: u64,
+) -> Result<u64> {
+    if lock_period == 0 || time_remaining <= 0 {
+        return Ok(0);
+    }
+    let remaining = std::cmp::min(time_remaining as u64, lock_period);
+    let charged = (locked_amount as u128)
+        .checked_mul(max_penalty_bps as u128).ok_or(ErrorCode::Overflow)?;
+    mul_div_ceil(charged, remaining as u128, (lock_period as u128) * (BPS_DENOMINATOR as u128))
+}
+
+/// Helper: receipt shares minted for an underwriting deposit at the current share price.
+fn calculate_underwriting_shares(amount: u64, total_shares: u64, fund_balance: u64) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(amount);
+    }
+    require!(fund_balance > 0, ErrorCode::InsuranceFundDepleted);
+    mul_div_floor(amount as u128, total_shares as u128, fund_balance as u128)
+}
+
+/// Helper: tokens paid out when redeeming receipt shares at the current share price.
+fn calculate_underwriting_redemption(shares: u64, total_shares: u64, fund_balance: u64) -> Result<u64> {
+    require!(shares <= total_shares, ErrorCode::InvalidAmount);
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    mul_div_floor(shares as u128, fund_balance as u128, total_shares as u128)
+}
+
+/// Helper: day bucket used as the scan prefix for unlock beacons.
+fn unlock_day_bucket(unlock_at: i64) -> u64 {
+    (unlock_at.max(0) / (24 * 60 * 60)) as u64
+}
+
+/// Helper: emission after `epochs_elapsed` epochs of decay, i.e.
+/// `max(floor_rate, initial_rate * (1 - decay_bps / 10_000) ^ epochs_elapsed)`.
+/// The power is computed by squaring in Q64.64, so any epoch costs O(log n).
+fn calculate_epoch_emission(initial_rate: u64, decay_bps: u64, floor_rate: u64, epochs_elapsed: u64) -> Result<u64> {
+    let retention_bps = BPS_DENOMINATOR.checked_sub(decay_bps).ok_or(ErrorCode::Underflow)?;
+    let retention = math::ratio_floor(retention_bps, BPS_DENOMINATOR)?;
+    let factor = math::pow_floor(retention, epochs_elapsed)?;
+    let (emission, _) = math::mul_floor(initial_rate, factor)?;
+    Ok(emission.max(floor_rate))
+}
+
+/// Helper: mints the receipt NFT for a new position and freezes it in the staker's wallet so it
+/// cannot be transferred. The metadata name encodes the position size and lock tier (in days).
+fn mint_position_receipt(accounts: &StakeAccounts, vault_bump: u8, amount: u64, lock_period: u64) -> Result<()> {
+    let bump = [vault_bump];
+    let seeds = &[b"vault".as_ref(), &bump];
+    let signer = &[&seeds[..]];
+    let lock_days = lock_period / (24 * 60 * 60);
+    let data = DataV2 {
+        name: format!("SST {} L{}d", amount, lock_days),
+        symbol: RECEIPT_SYMBOL.to_string(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    };
+    let cpi_accounts = CreateMetadataAccountsV3 {
+        metadata: accounts.receipt_metadata.to_account_info(),
+        mint: accounts.receipt_mint.to_account_info(),
+        mint_authority: accounts.vault_authority.to_account_info(),
+        payer: accounts.staker.to_account_info(),
+        update_authority: accounts.vault_authority.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        rent: accounts.rent.to_account_info(),
+    };
+    let cpi_program = accounts.token_metadata_program.to_account_info();
+    create_metadata_accounts_v3(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), data, true, true, None)?;
+
+    let cpi_program = accounts.token_program.to_account_info();
+    let cpi_accounts = MintTo {
+        mint: accounts.receipt_mint.to_account_info(),
+        to: accounts.receipt_token_account.to_account_info(),
+        authority: accounts.vault_authority.to_account_info(),
+    };
+    token::mint_to(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), 1)?;
+    let cpi_accounts = FreezeAccount {
+        account: accounts.receipt_token_account.to_account_info(),
+        mint: accounts.receipt_mint.to_account_info(),
+        authority: accounts.vault_authority.to_account_info(),
+    };
+    token::freeze_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+    msg!("Position receipt minted: {}", accounts.receipt_mint.key());
+    Ok(())
+}
+
+/// Helper: length in seconds of the overlap between `[start, end)` and `[from, to)`.
+fn segment_overlap(start: i64, end: i64, from: i64, to: i64) -> u128 {
+    let lo = start.max(from);
+    let hi = end.min(to);
+    if hi > lo { (hi - lo) as u128 } else { 0 }
+}
+
+/// Helper: sets newly reached milestones in the position's achievement bitmask and emits an
+/// `AchievementUnlocked` event for each.
+fn record_achievements(stake_info: &mut StakeInfo, now: i64) {
+    let mut reached = 0u64;
+    if stake_info.lifetime_staked > 0 {
+        reached |= ACHIEVEMENT_FIRST_STAKE;
+    }
+    if stake_info.lifetime_staked >= ACHIEVEMENT_STAKE_MILESTONE {
+        reached |= ACHIEVEMENT_STAKED_100K;
+    }
+    if stake_info.amount > 0 && now.saturating_sub(stake_info.last_staked_time) >= SECONDS_PER_YEAR {
+        reached |= ACHIEVEMENT_ONE_YEAR_STAKED;
+    }
+    if stake_info.proposals_voted >= ACHIEVEMENT_VOTES_MILESTONE {
+        reached |= ACHIEVEMENT_TEN_VOTES;
+    }
+    let unlocked = reached & !stake_info.achievements;
+    for bit in 0..u64::BITS as u8 {
+        if unlocked & (1 << bit) != 0 {
+            emit!(AchievementUnlocked {
+                staker: stake_info.staker,
+                achievement: bit,
+                unlocked_at: now,
+            });
+        }
+    }
+    stake_info.achievements |= unlocked;
+}
+
+/// Helper: pre-flight error code (0 = valid) for a program-owned singleton PDA derived from `seed`.
+fn check_protocol_account(info: &AccountInfo, seed: &[u8]) -> u32 {
+    let (expected, _) = Pubkey::find_program_address(&[seed], &crate::ID);
+    if info.key() != expected || *info.owner != crate::ID {
+        u32::from(ErrorCode::InvalidProtocolAccount)
+    } else {
+        0
+    }
+}
+
+/// Helper: pre-flight error code (0 = valid) for a token account that must hold `mint` and belong
+/// to `owner`; frozen accounts are only acceptable where payouts can be escrowed.
+fn check_token_account(info: &AccountInfo, mint: Pubkey, owner: Pubkey, allow_frozen: bool) -> Result<u32> {
+    if *info.owner != token::ID {
+        return Ok(u32::from(ErrorCode::InvalidTokenAccountOwner));
+    }
+    let token_account = match TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..]) {
+        Ok(token_account) => token_account,
+        Err(_) => return Ok(u32::from(ErrorCode::InvalidTokenAccountOwner)),
+    };
+    let code = if token_account.mint != mint {
+        u32::from(ErrorCode::InvalidMint)
+    } else if token_account.owner != owner {
+        u32::from(ErrorCode::InvalidTokenAccountOwner)
+    } else if token_account.is_frozen() && !allow_frozen {
+        u32::from(ErrorCode::TokenAccountFrozen)
+    } else {
+        0
+    };
+    Ok(code)
+}
+
+
+/// Helper: tallies a deposit against the integration it is attributed to, if any.
+fn record_integration_deposit(
+    integration_stats: Option<&mut IntegrationStats>,
+    staker: Pubkey,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if let Some(integration_stats) = integration_stats {
+        integration_stats.deposit_count = integration_stats.deposit_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        integration_stats.total_deposited = integration_stats.total_deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        integration_stats.last_deposit_at = now;
+        emit!(IntegrationDeposit { code: integration_stats.code, staker, amount });
+    }
+    Ok(())
+}
+
+
+/// Helper: re-syncs every boost campaign the position holds weight in after its stake changed, so
+/// campaign weights never outlive the stake behind them. `accounts` holds a writable
+/// (boost_campaign, boost_position) pair per enrolled campaign, in enrollment order.
+fn sync_boost_enrollments(accounts: &[AccountInfo], stake_info: &mut StakeInfo, now: i64) -> Result<()> {
+    let enrolled: Vec<Pubkey> = stake_info
+        .boost_campaigns
+        .iter()
+        .copied()
+        .filter(|key| *key != Pubkey::default())
+        .collect();
+    require!(accounts.len() == enrolled.len() * 2, ErrorCode::BoostSyncRequired);
+    for (campaign_key, pair) in enrolled.iter().zip(accounts.chunks(2)) {
+        let (campaign_info, position_info) = (&pair[0], &pair[1]);
+        require!(
+            campaign_info.key() == *campaign_key
+                && campaign_info.is_writable
+                && position_info.is_writable
+                && *campaign_info.owner == crate::ID
+                && *position_info.owner == crate::ID,
+            ErrorCode::BoostSyncRequired
+        );
+        let mut campaign = BoostCampaign::try_deserialize(&mut &campaign_info.try_borrow_data()?[..])?;
+        let mut boost_position = BoostPosition::try_deserialize(&mut &position_info.try_borrow_data()?[..])?;
+        require!(
+            boost_position.campaign == *campaign_key && boost_position.staker == stake_info.staker,
+            ErrorCode::BoostSyncRequired
+        );
+        settle_boost_position(&mut campaign, &mut boost_position, stake_info, now)?;
+        campaign.try_serialize(&mut &mut campaign_info.try_borrow_mut_data()?[..])?;
+        boost_position.try_serialize(&mut &mut position_info.try_borrow_mut_data()?[..])?;
+        stake_info.set_boost_enrollment(*campaign_key, boost_position.weight)?;
+    }
+    Ok(())
+}
+
+/// Helper: settles a boost position against its campaign accumulator, then re-weights it from the
+/// position's current stake.
+fn settle_boost_position(
+    campaign: &mut BoostCampaign,
+    boost_position: &mut BoostPosition,
+    stake_info: &StakeInfo,
+    now: i64,
+) -> Result<()> {
+    campaign.update_reward_per_token(now)?;
+    let (earned, dust) = rewards_earned(boost_position.weight, campaign.reward_per_token_stored, boost_position.reward_per_token_paid)?;
+    boost_position.accrued = boost_position.accrued.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+    boost_position.reward_per_token_paid = campaign.reward_per_token_stored;
+    campaign.reward_dust = campaign.reward_dust.checked_add(dust).ok_or(ErrorCode::Overflow)?;
+    emit!(BoostRewardsSettled {
+        campaign: boost_position.campaign,
+        staker: boost_position.staker,
+        weight: boost_position.weight,
+        campaign_boost: earned,
+        accrued: boost_position.accrued,
+        timestamp: now,
+    });
+
+    let weight = if now < campaign.end_time && campaign.eligibility.is_eligible(stake_info) {
+        stake_info.amount
+    } else {
+        0
+    };
+    campaign.total_weight = campaign.total_weight
+        .checked_sub(boost_position.weight).ok_or(ErrorCode::Underflow)?
+        .checked_add(weight).ok_or(ErrorCode::Overflow)?;
+    boost_position.weight = weight;
+    Ok(())
+}
+
+
+/// Helper: credits the referrer of `stake_info` with `config.referral_bps` of a claimed reward,
+/// moving the share from the reward vault into the referral vault. Skipped for positions without a
+/// referrer and while no referral vault is configured. `vault_remaining` is the reward vault
+/// balance not already committed by this instruction; returns the share paid.
+#[allow(clippy::too_many_arguments)]
+fn credit_referral<'info>(
+    config: &ProtocolConfig,
+    stake_info: &StakeInfo,
+    referral_account: Option<&mut ReferralAccount>,
+    referral_vault: Option<AccountInfo<'info>>,
+    reward_vault: AccountInfo<'info>,
+    vault_authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    claimed: u64,
+    vault_remaining: u64,
+) -> Result<u64> {
+    if stake_info.referrer == Pubkey::default()
+        || config.referral_bps == 0
+        || config.referral_vault == Pubkey::default()
+    {
+        return Ok(0);
+    }
+    let (referral_account, referral_vault) = match (referral_account, referral_vault) {
+        (Some(account), Some(vault)) => (account, vault),
+        _ => return err!(ErrorCode::ReferralAccountsRequired),
+    };
+    require_keys_eq!(referral_account.referrer, stake_info.referrer, ErrorCode::InvalidReferrer);
+    require_keys_eq!(referral_vault.key(), config.referral_vault, ErrorCode::InvalidReferralVault);
+    let share = ((claimed as u128)
+        .checked_mul(config.referral_bps as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / BPS_DENOMINATOR as u128) as u64;
+    let share = share.min(vault_remaining);
+    if share == 0 {
+        return Ok(0);
+    }
+    let seeds = config.vault_signer_seeds();
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: reward_vault,
+        to: referral_vault,
+        authority: vault_authority,
+    };
+    token::transfer(CpiContext::new_with_signer(token_program, cpi_accounts, signer), share)?;
+    referral_account.pending = referral_account.pending.checked_add(share).ok_or(ErrorCode::Overflow)?;
+    referral_account.total_earned = referral_account.total_earned.checked_add(share).ok_or(ErrorCode::Overflow)?;
+    emit!(ReferralCredited {
+        referrer: referral_account.referrer,
+        referee: stake_info.staker,
+        amount: share,
+    });
+    Ok(share)
+}
+
+/// Helper: links a new position to the referrer whose referral account was passed at stake time.
+fn record_referral(referral_account: Option<&mut ReferralAccount>, stake_info: &mut StakeInfo) -> Result<()> {
+    if let Some(referral_account) = referral_account {
+        require_keys_neq!(referral_account.referrer, stake_info.staker, ErrorCode::InvalidReferrer);
+        stake_info.referrer = referral_account.referrer;
+        referral_account.referee_count = referral_account.referee_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    }
+    Ok(())
+}
+
+/// Helper: accrues loyalty points for `stake_info` when its loyalty account was passed.
+fn accrue_loyalty(
+    loyalty_account: &mut LoyaltyAccount,
+    config: &ProtocolConfig,
+    stake_info: &StakeInfo,
+    now: i64,
+) -> Result<()> {
+    if loyalty_account.staker == Pubkey::default() {
+        // Opened by this instruction: points start accruing from now.
+        loyalty_account.staker = stake_info.staker;
+        loyalty_account.last_accrued_at = now;
+        return Ok(());
+    }
+    let earned = loyalty_account.accrue(config, stake_info, now)?;
+    if earned > 0 {
+        emit!(LoyaltyPointsAccrued {
+            staker: stake_info.staker,
+            earned,
+            points: loyalty_account.points,
+        });
+    }
+    Ok(())
+}
+
+/// Helper: extra fee discount (percentage points) unlocked by accumulated loyalty points.
+fn loyalty_fee_discount(points: u64) -> u64 {
+    if points >= 1_000_000 {
+        5
+    } else if points >= 100_000 {
+        3
+    } else if points >= 10_000 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Helper: scores a position's season on time-weighted net stake. The stake recorded at the last
+/// update counts for the part of the interval since then that falls inside the season, capped at
+/// the current stake so a drop outside a synced instruction scores at the lower amount. Must run
+/// before the stake changes; `new_amount` is the stake the instruction leaves. Positions outside a
+/// season skip; a finalized season releases the position.
+fn record_season_score(
+    season: Option<&mut Account<Season>>,
+    season_score: Option<&mut Account<SeasonScore>>,
+    stake_info: &mut StakeInfo,
+    new_amount: u64,
+    now: i64,
+) -> Result<()> {
+    if stake_info.season_score == Pubkey::default() {
+        return Ok(());
+    }
+    let (season, season_score) = match (season, season_score) {
+        (Some(season), Some(season_score)) => (season, season_score),
+        _ => return err!(ErrorCode::SeasonSyncRequired),
+    };
+    require_keys_eq!(season_score.key(), stake_info.season_score, ErrorCode::InvalidSeasonAccounts);
+    require_keys_eq!(season_score.season, season.key(), ErrorCode::InvalidSeasonAccounts);
+    if season.finalized {
+        stake_info.season_score = Pubkey::default();
+        return Ok(());
+    }
+    let from = season_score.updated_at.max(season.start_time);
+    let to = now.min(season.end_time);
+    if to > from {
+        let held = season_score.stake.min(stake_info.amount);
+        let points = mul_div_floor(held as u128, (to - from) as u128, SEASON_SCORE_UNIT as u128)?;
+        if points > 0 {
+            season_score.score = season_score.score.checked_add(points).ok_or(ErrorCode::Overflow)?;
+            season.record_score(season_score.participant, season_score.score);
+        }
+    }
+    season_score.stake = new_amount;
+    season_score.updated_at = now;
+    Ok(())
+}
+
+/// Helper: settles an LP farm position against its pool's accumulator. Must run before the
+/// position's amount changes.
+fn settle_lp_farm(lp_pool: &mut LpPool, position: &mut LpFarmPosition, now: i64) -> Result<()> {
+    lp_pool.rewards.update_reward_per_token(lp_pool.total_deposited, now)?;
+    let stored = lp_pool.rewards.reward_per_token_stored;
+    let (earned, dust) = rewards_earned(position.amount, stored, position.reward_per_token_paid)?;
+    position.accrued_rewards = position.accrued_rewards.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+    position.reward_per_token_paid = stored;
+    lp_pool.rewards.reward_dust = lp_pool.rewards.reward_dust.checked_add(dust).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Helper: validates an LP deposit of `amount` into `vault` against the LP mint registry (mint
+/// approved and active, vault matching, cap respected, one mint per position) and records it.
+fn record_lp_deposit(
+    lp_mints: &mut LpMintRegistry,
+    stake_info: &mut StakeInfo,
+    lp_mint: Pubkey,
+    vault: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let entry = lp_mints.mints
+        .iter_mut()
+        .find(|entry| entry.active && entry.mint == lp_mint)
+        .ok_or(ErrorCode::LpMintNotApproved)?;
+    require_keys_eq!(vault, entry.vault, ErrorCode::InvalidLpPoolAccounts);
+    require!(
+        (stake_info.lp_deposit == 0 && stake_info.locked_lp_deposit == 0) || stake_info.lp_mint == entry.mint,
+        ErrorCode::LpMintMismatch
+    );
+    let total_deposited = entry.total_deposited.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(entry.cap == 0 || total_deposited <= entry.cap, ErrorCode::LpMintCapExceeded);
+    entry.total_deposited = total_deposited;
+    stake_info.lp_mint = entry.mint;
+    stake_info.lp_vault = entry.vault;
+    stake_info.lp_boost_weight_bps = entry.boost_weight_bps;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct StakeAccounts<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + StakeInfo::LEN,
+        seeds = [b"stake", staker.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// Receipt NFT for the position; mint and freeze authority is the vault authority.
+    #[account(
+        init,
+        payer = staker,
+        seeds = [b"position_receipt", staker.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vault_authority,
+        mint::freeze_authority = vault_authority
+    )]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = staker,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = staker
+    )]
+    pub receipt_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metadata PDA of `receipt_mint`; validated by the token metadata program.
+    #[account(mut)]
+    pub receipt_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    #[account(seeds = [b"vault"], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// Integration (frontend or partner) the deposit is attributed to, if any.
+    #[account(mut, seeds = [b"integration", integration_stats.code.as_ref()], bump)]
+    pub integration_stats: Option<Account<'info, IntegrationStats>>,
+
+    /// Referrer of the new position, if any.
+    #[account(mut, seeds = [b"referral", referral_account.referrer.as_ref()], bump)]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+
+    /// Accrues on every balance change; opened on the staker's first deposit.
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = 8 + LoyaltyAccount::LEN,
+        seeds = [b"loyalty", staker.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Box<Account<'info, LoyaltyAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        mut,
+        constraint = stake_info.withdrawal_address_allows(staker_token_account.key())
+            @ ErrorCode::WithdrawalAddressMismatch
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Registered second signer; required for unstakes above the two-factor threshold.
+    pub second_signer: Option<Signer<'info>>,
+
+    /// Position receipt NFT; required when the unstake empties the position.
+    #[account(mut)]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut, token::authority = staker)]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Original vault authority, which stays the receipt freeze authority across vault rotations.
+    #[account(seeds = [b"vault"], bump)]
+    pub receipt_authority: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Accrues on every balance change.
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = 8 + LoyaltyAccount::LEN,
+        seeds = [b"loyalty", staker.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Box<Account<'info, LoyaltyAccount>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Season the position scores in; required when `stake_info.season_score` is set.
+    #[account(mut, seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Option<Account<'info, Season>>,
+
+    #[account(mut, seeds = [b"season_score", season_score.season.as_ref(), stake_info.staker.as_ref()], bump)]
+    pub season_score: Option<Account<'info, SeasonScore>>,
+
+    /// USDC market and SST price feed; required when the position owes USDC, so the health check
+    /// values that loan at the live price.
+    #[account(seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Option<Account<'info, UsdcMarket>>,
+
+    /// CHECK: Checked against the config's oracle in `read_sst_price`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTrade<'info> {
+    /// Position owner or its registered operator.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", stake_info.staker.as_ref()],
+        bump,
+        constraint = stake_info.is_owner_or_operator(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// Optional fee-tier lease granting this staker the lessor's tier.
+    pub fee_lease: Option<Account<'info, FeeTierLease>>,
+
+    /// Lessor's position backing `fee_lease`; required when a lease is supplied.
+    pub lessor_stake_info: Option<Account<'info, StakeInfo>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: SST price feed; without it a USD VIP threshold is not met. Checked in `is_vip`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds = [b"loyalty", stake_info.staker.as_ref()], bump)]
+    pub loyalty_account: Option<Account<'info, LoyaltyAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    /// Position owner or its registered operator.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", stake_info.staker.as_ref()],
+        bump,
+        constraint = stake_info.is_owner_or_operator(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// Reward destination: the allowlisted withdrawal address or registered beneficiary if set.
+    #[account(
+        mut,
+        constraint = stake_info.reward_destination_allowed(staker_token_account.key())
+            @ ErrorCode::InvalidRewardBeneficiary
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Vesting escrow receiving the claim while reward vesting is enabled.
+    #[account(mut, seeds = [b"reward_vesting", stake_info.staker.as_ref()], bump)]
+    pub reward_vesting: Option<Account<'info, RewardVesting>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Referral account of `stake_info.referrer`; required when the position has a referrer.
+    #[account(mut, seeds = [b"referral", stake_info.referrer.as_ref()], bump)]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Accrues on every balance change, including auto-restaked claims.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + LoyaltyAccount::LEN,
+        seeds = [b"loyalty", stake_info.staker.as_ref()],
+        bump
+    )]
+    pub loyalty_account: Box<Account<'info, LoyaltyAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleAutoRestake<'info> {
+    #[account(
+        mut,
+        seeds = [b"stake", stake_info.staker.as_ref()],
+        bump,
+        constraint = stake_info.is_owner_or_operator(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+    /// Position owner or its registered operator.
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoRelock<'info> {
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+    pub staker: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RelockMatured<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleWithdrawal<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        constraint = stake_info.payout_account_allowed(staker_token_account.key(), staker_token_account.owner)
+            @ ErrorCode::WithdrawalAddressMismatch
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Position receipt NFT; required when the position has one.
+    #[account(mut)]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut, token::authority = staker)]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Original vault authority, which stays the receipt freeze authority across vault rotations.
+    #[account(seeds = [b"vault"], bump)]
+    pub receipt_authority: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMaturedUnstake<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = stake_info.scheduled_payout_account() @ ErrorCode::InvalidPayoutAccount)]
+    pub payout_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Registered second signer; required when the position is above the two-factor threshold.
+    pub second_signer: Option<Signer<'info>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Position receipt NFT; required when the position has one.
+    #[account(mut)]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut, token::authority = stake_info.staker)]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Original vault authority, which stays the receipt freeze authority across vault rotations.
+    #[account(seeds = [b"vault"], bump)]
+    pub receipt_authority: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeDual<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + StakeInfo::LEN,
+        seeds = [b"stake", staker.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub staker_usdc_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_usdc_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// Integration (frontend or partner) the deposit is attributed to, if any.
+    #[account(mut, seeds = [b"integration", integration_stats.code.as_ref()], bump)]
+    pub integration_stats: Option<Account<'info, IntegrationStats>>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLP<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to find the repayment.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: Allowlist PDA, deserialized in `flash_loan`; no allowlist applies until it is created.
+    #[account(seeds = [b"flash_loan_allowlist"], bump)]
+    pub flash_loan_allowlist: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Account order is relied on by `require_flash_loan_repayment`.
+#[derive(Accounts)]
+pub struct RepayFlashLoan<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SlashStake<'info> {
+    #[account(mut)]
+    pub gov_authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"governance_config"],
+        bump,
+        constraint = governance_config.authority == gov_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    /// Passed slash proposal targeting `staker`.
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: The staker whose stake will be slashed; checked against the proposal.
+    pub staker: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct VoteProposal<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", voter.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteOptions<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(seeds = [b"stake", voter.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + OptionVoteRecord::LEN,
+        seeds = [b"option_vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub option_vote: Account<'info, OptionVoteRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    pub voter: Signer<'info>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = voter @ ErrorCode::Unauthorized
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = voter @ ErrorCode::Unauthorized,
+        close = voter
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateVotes<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"stake", delegate_stake_info.staker.as_ref()], bump)]
+    pub delegate_stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UndelegateVotes<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", staker.key().as_ref()],
+        bump,
+        constraint = stake_info.delegate != Pubkey::default() @ ErrorCode::NotDelegated
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"stake", stake_info.delegate.as_ref()], bump)]
+    pub delegate_stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct VeLock<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_token_account.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.ve_vault @ ErrorCode::InvalidVeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct AddProposalInstruction<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(mut, has_one = proposer @ ErrorCode::Unauthorized)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + ProposalInstruction::LEN,
+        seeds = [b"proposal_ix", proposal.key().as_ref(), &[index]],
+        bump
+    )]
+    pub proposal_instruction: Account<'info, ProposalInstruction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal_ix", proposal.key().as_ref(), &[proposal_instruction.index]],
+        bump,
+        has_one = proposal @ ErrorCode::InvalidProposalInstruction
+    )]
+    pub proposal_instruction: Account<'info, ProposalInstruction>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteParameterChanges<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(init, payer = authority, space = 8 + Treasury::LEN, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(constraint = treasury_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub treasury_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasurySpendLimit<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTreasurySpendProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        space = 8 + ProposerRecord::LEN,
+        seeds = [b"proposer", proposer.key().as_ref()],
+        bump
+    )]
+    pub proposer_record: Account<'info, ProposerRecord>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::LEN,
+        seeds = [b"proposal", proposer.key().as_ref(), proposer_record.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(address = treasury.vault)]
+    pub treasury_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = recipient.mint == treasury_vault.mint @ ErrorCode::InvalidMint)]
+    pub recipient: Box<Account<'info, TokenAccount>>,
+
+    /// Proposer's position; required when the governance config sets a minimum proposer stake.
+    #[account(seeds = [b"stake", proposer.key().as_ref()], bump)]
+    pub proposer_stake_info: Option<Box<Account<'info, StakeInfo>>>,
+
+    /// Pays the proposal bond; required when the governance config sets one.
+    #[account(mut, constraint = proposer_token_account.owner == proposer.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub proposer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut, address = governance_config.bond_vault @ ErrorCode::InvalidBondVault)]
+    pub bond_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTreasurySpend<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut, address = treasury.vault)]
+    pub treasury_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    /// The proposer or the governance config authority.
+    pub authority: Signer<'info>,
+
+    #[account(mut, close = proposer)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Receives the proposal's rent; must be its proposer.
+    #[account(mut, address = proposal.proposer @ ErrorCode::Unauthorized)]
+    pub proposer: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    /// Bond refund accounts; required when the proposal posted a bond.
+    #[account(mut, address = governance_config.bond_vault @ ErrorCode::InvalidBondVault)]
+    pub bond_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut, constraint = proposer_token_account.owner == proposal.proposer @ ErrorCode::InvalidTokenAccountOwner)]
+    pub proposer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(mut, close = proposer)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Receives the proposal's rent; must be its proposer.
+    #[account(mut, address = proposal.proposer @ ErrorCode::Unauthorized)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(mut, address = config.borrow_vault @ ErrorCode::InvalidBorrowVault)]
+    pub borrow_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = borrower_token_account.mint == borrow_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Treasury and its vault, which receives the origination fee; required only when a fee is due.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: SST price feed; required when the position owes USDC, and without it a USD VIP
+    /// threshold is not met. Checked in `read_sst_price`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// USDC market; required when the position owes USDC.
+    #[account(seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Option<Account<'info, UsdcMarket>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RepayBorrow<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(mut, address = config.borrow_vault @ ErrorCode::InvalidBorrowVault)]
+    pub borrow_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = borrower_token_account.mint == borrow_vault.mint @ ErrorCode::InvalidMint)]
+    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    pub liquidator: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(mut, address = config.borrow_vault @ ErrorCode::InvalidBorrowVault)]
+    pub borrow_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = liquidator_token_account.mint == borrow_vault.mint @ ErrorCode::InvalidMint)]
+    pub liquidator_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Receives the seized stake.
+    #[account(mut, constraint = liquidator_collateral_account.mint == vault_token_account.mint @ ErrorCode::InvalidMint)]
+    pub liquidator_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// USDC market and SST price feed; required when the position owes USDC, so the health check
+    /// values that loan at the live price.
+    #[account(seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Option<Account<'info, UsdcMarket>>,
+
+    /// CHECK: Checked against the config's oracle in `read_sst_price`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUsdcMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(init, payer = authority, space = 8 + UsdcMarket::LEN, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Account<'info, UsdcMarket>,
+
+    #[account(constraint = usdc_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetUsdcMarketParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Account<'info, UsdcMarket>,
+
+    #[account(address = usdc_market.usdc_vault @ ErrorCode::InvalidBorrowVault)]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowUsdc<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Account<'info, UsdcMarket>,
+
+    #[account(mut, address = usdc_market.usdc_vault @ ErrorCode::InvalidBorrowVault)]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: SST price feed; checked against the config's oracle in `read_sst_price`.
+    pub price_feed: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = borrower_usdc_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = borrower_usdc_account.mint == usdc_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub borrower_usdc_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RepayUsdc<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Account<'info, UsdcMarket>,
+
+    #[account(mut, address = usdc_market.usdc_vault @ ErrorCode::InvalidBorrowVault)]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = borrower_usdc_account.mint == usdc_vault.mint @ ErrorCode::InvalidMint)]
+    pub borrower_usdc_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateUsdc<'info> {
+    pub liquidator: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(mut, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Account<'info, UsdcMarket>,
+
+    #[account(mut, address = usdc_market.usdc_vault @ ErrorCode::InvalidBorrowVault)]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: SST price feed; checked against the config's oracle in `read_sst_price`.
+    pub price_feed: UncheckedAccount<'info>,
+
+    #[account(mut, constraint = liquidator_usdc_account.mint == usdc_vault.mint @ ErrorCode::InvalidMint)]
+    pub liquidator_usdc_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Receives the seized stake.
+    #[account(mut, constraint = liquidator_collateral_account.mint == vault_token_account.mint @ ErrorCode::InvalidMint)]
+    pub liquidator_collateral_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        space = 8 + ProposerRecord::LEN,
+        seeds = [b"proposer", proposer.key().as_ref()],
+        bump
+    )]
+    pub proposer_record: Account<'info, ProposerRecord>,
+
+    #[account(
+        init, 
+        payer = proposer, 
+        space = 8 + Proposal::LEN, 
+        seeds = [b"proposal", proposer.key().as_ref(), proposer_record.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    /// Proposer's position; required when the governance config sets a minimum proposer stake.
+    #[account(seeds = [b"stake", proposer.key().as_ref()], bump)]
+    pub proposer_stake_info: Option<Box<Account<'info, StakeInfo>>>,
+
+    /// Pays the proposal bond; required when the governance config sets one.
+    #[account(mut, constraint = proposer_token_account.owner == proposer.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub proposer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut, address = governance_config.bond_vault @ ErrorCode::InvalidBondVault)]
+    pub bond_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DonateInsurance<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(mut)]
+    pub donor_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = insurance_fund.token_account)]
+    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProtocolStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProtocolStats::LEN,
+        seeds = [b"protocol_stats"],
+        bump
+    )]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct SnapshotProtocolStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StatsSnapshot::LEN,
+        seeds = [b"stats_snapshot", epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, StatsSnapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// The new borrow vault; required when `borrow_vault` is set to a non-default key.
+    #[account(
+        constraint = borrow_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = borrow_vault.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub borrow_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolVaults<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(constraint = stake_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        constraint = reward_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = reward_vault.mint == stake_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        constraint = ve_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = ve_vault.mint == stake_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub ve_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct EarlyUnlock<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.penalty_destination @ ErrorCode::InvalidPenaltyDestination)]
+    pub penalty_destination: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// USDC market and SST price feed; required when the position owes USDC, so the health check
+    /// values that loan at the live price.
+    #[account(seeds = [b"usdc_market"], bump)]
+    pub usdc_market: Option<Account<'info, UsdcMarket>>,
+
+    /// CHECK: Checked against the config's oracle in `read_sst_price`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceFund::LEN,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        constraint = insurance_fund_token_account.owner == insurance_fund.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = insurance_fund_token_account.mint == sst_mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub sst_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"insurance_receipt"],
+        bump,
+        mint::decimals = sst_mint.decimals,
+        mint::authority = insurance_fund
+    )]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UnderwriteInsurance<'info> {
+    #[account(mut)]
+    pub underwriter: Signer<'info>,
+
+    #[account(mut)]
+    pub underwriter_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = receipt_mint)]
+    pub underwriter_receipt_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut, address = insurance_fund.token_account)]
+    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = insurance_fund.receipt_mint)]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemInsuranceReceipt<'info> {
+    #[account(mut)]
+    pub underwriter: Signer<'info>,
+
+    #[account(mut)]
+    pub underwriter_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = receipt_mint)]
+    pub underwriter_receipt_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut, address = insurance_fund.token_account)]
+    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = insurance_fund.receipt_mint)]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CoverInsuranceLoss<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut, address = insurance_fund.token_account)]
+    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuildLockLadder<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + LockLadder::LEN,
+        seeds = [b"ladder", staker.key().as_ref()],
+        bump
+    )]
+    pub ladder: Account<'info, LockLadder>,
+
+    /// CHECK: The staker's position PDA, read for the wallet cap; may not exist yet.
+    #[account(seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLadderTranche<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"ladder", staker.key().as_ref()], bump, has_one = staker)]
+    pub ladder: Account<'info, LockLadder>,
+
+    #[account(mut)]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAndRestake<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Referral account of `stake_info.referrer`; required when the position has a referrer.
+    #[account(mut, seeds = [b"referral", stake_info.referrer.as_ref()], bump)]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterUnlockBeacon<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + UnlockBeacon::LEN,
+        seeds = [b"unlock_beacon", staker.key().as_ref()],
+        bump
+    )]
+    pub beacon: Account<'info, UnlockBeacon>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncUnlockBeacon<'info> {
+    #[account(seeds = [b"stake", beacon.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"unlock_beacon", beacon.staker.as_ref()], bump)]
+    pub beacon: Account<'info, UnlockBeacon>,
+}
+
+#[derive(Accounts)]
+pub struct PruneUnlockBeacon<'info> {
+    pub keeper: Signer<'info>,
+
+    /// CHECK: Rent recipient; must be the beacon owner.
+    #[account(mut, address = beacon.staker)]
+    pub staker: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"stake", beacon.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"unlock_beacon", beacon.staker.as_ref()], bump, close = staker)]
+    pub beacon: Account<'info, UnlockBeacon>,
+}
+
+#[derive(Accounts)]
+pub struct OfferFeeTierLease<'info> {
+    #[account(mut)]
+    pub lessor: Signer<'info>,
+
+    #[account(seeds = [b"stake", lessor.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: SST price feed; without it a USD VIP threshold is not met. Checked in `is_vip`.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init,
+        payer = lessor,
+        space = 8 + FeeTierLease::LEN,
+        seeds = [b"fee_lease", lessor.key().as_ref()],
+        bump
+    )]
+    pub fee_lease: Account<'info, FeeTierLease>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptFeeTierLease<'info> {
+    pub lessee: Signer<'info>,
+
+    #[account(mut, seeds = [b"fee_lease", fee_lease.lessor.as_ref()], bump, has_one = lessee @ ErrorCode::InvalidLease)]
+    pub fee_lease: Account<'info, FeeTierLease>,
+
+    #[account(mut)]
+    pub lessee_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = lessor_token_account.owner == fee_lease.lessor @ ErrorCode::InvalidTokenAccountOwner)]
+    pub lessor_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeFeeTierLease<'info> {
+    #[account(mut)]
+    pub lessor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_lease", lessor.key().as_ref()],
+        bump,
+        has_one = lessor @ ErrorCode::Unauthorized,
+        close = lessor
+    )]
+    pub fee_lease: Account<'info, FeeTierLease>,
+
+    #[account(mut)]
+    pub lessor_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = lessee_token_account.owner == fee_lease.lessee @ ErrorCode::InvalidTokenAccountOwner)]
+    pub lessee_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPendingWithdrawal<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// Registered second signer; required for escrowed payouts above the two-factor threshold.
+    pub second_signer: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        constraint = staker_token_account.key() == stake_info.pending_withdrawal_destination()
+            @ ErrorCode::WithdrawalAddressMismatch
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardBeneficiary<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    pub beneficiary_token_account: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalAddress<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    pub withdrawal_token_account: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyWithdrawalAddressChange<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperator<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureTwoFactor<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// Currently registered second signer; required to change an enabled configuration.
+    pub current_second_signer: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RequestTwoFactorBypass<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTwoFactorBypass<'info> {
+    pub second_signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", stake_info.staker.as_ref()],
+        bump,
+        constraint = stake_info.second_signer == second_signer.key() @ ErrorCode::Unauthorized
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct RageQuit<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"vote", proposal.key().as_ref(), staker.key().as_ref()], bump)]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// Registered second signer; required for exits above the two-factor threshold.
+    pub second_signer: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        constraint = stake_info.withdrawal_address_allows(staker_token_account.key())
+            @ ErrorCode::WithdrawalAddressMismatch
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Position receipt NFT; required when the position has one.
+    #[account(mut)]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut, token::authority = staker)]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Original vault authority, which stays the receipt freeze authority across vault rotations.
+    #[account(seeds = [b"vault"], bump)]
+    pub receipt_authority: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GiftStake<'info> {
+    #[account(mut)]
+    pub gifter: Signer<'info>,
+
+    /// CHECK: Gift recipient; only used to derive their position.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = gifter,
+        space = 8 + StakeInfo::LEN,
+        seeds = [b"stake", recipient.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub gifter_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Derived PDA for the vault authority.
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Integration (frontend or partner) the deposit is attributed to, if any.
+    #[account(mut, seeds = [b"integration", integration_stats.code.as_ref()], bump)]
+    pub integration_stats: Option<Account<'info, IntegrationStats>>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpStake<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Accrues on every balance change.
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = 8 + LoyaltyAccount::LEN,
+        seeds = [b"loyalty", staker.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Box<Account<'info, LoyaltyAccount>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Season the position scores in; required when `stake_info.season_score` is set.
+    #[account(mut, seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Option<Account<'info, Season>>,
+
+    #[account(mut, seeds = [b"season_score", season_score.season.as_ref(), stake_info.staker.as_ref()], bump)]
+    pub season_score: Option<Account<'info, SeasonScore>>,
+}
+
+#[derive(Accounts)]
+pub struct FundReengagementBudget<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.reward_vault @ ErrorCode::InvalidRewardVault,
+        constraint = reward_vault.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateParameterProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        space = 8 + ProposerRecord::LEN,
+        seeds = [b"proposer", proposer.key().as_ref()],
+        bump
+    )]
+    pub proposer_record: Account<'info, ProposerRecord>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::LEN,
+        seeds = [b"proposal", proposer.key().as_ref(), proposer_record.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    /// Proposer's position; required when the governance config sets a minimum proposer stake.
+    #[account(seeds = [b"stake", proposer.key().as_ref()], bump)]
+    pub proposer_stake_info: Option<Box<Account<'info, StakeInfo>>>,
+
+    /// Pays the proposal bond; required when the governance config sets one.
+    #[account(mut, constraint = proposer_token_account.owner == proposer.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub proposer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut, address = governance_config.bond_vault @ ErrorCode::InvalidBondVault)]
+    pub bond_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewConfigAfter<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGovernanceConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GovernanceConfig::LEN,
+        seeds = [b"governance_config"],
+        bump
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetSecurityCouncil<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SecurityCouncil::LEN,
+        seeds = [b"security_council"],
+        bump
+    )]
+    pub security_council: Account<'info, SecurityCouncil>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFlashLoanAllowlist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + FlashLoanAllowlist::LEN,
+        seeds = [b"flash_loan_allowlist"],
+        bump
+    )]
+    pub flash_loan_allowlist: Account<'info, FlashLoanAllowlist>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CouncilVeto<'info> {
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    #[account(seeds = [b"security_council"], bump)]
+    pub security_council: Account<'info, SecurityCouncil>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init_if_needed,
+        payer = member,
+        space = 8 + CouncilVetoRecord::LEN,
+        seeds = [b"council_veto", proposal.key().as_ref()],
+        bump
+    )]
+    pub council_veto: Account<'info, CouncilVetoRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FastTrackProposal<'info> {
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    #[account(seeds = [b"security_council"], bump)]
+    pub security_council: Account<'info, SecurityCouncil>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init_if_needed,
+        payer = member,
+        space = 8 + CouncilFastTrackRecord::LEN,
+        seeds = [b"council_fast_track", proposal.key().as_ref()],
+        bump
+    )]
+    pub fast_track: Account<'info, CouncilFastTrackRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OverturnCouncilVeto<'info> {
+    #[account(seeds = [GOVERNANCE_SIGNER_SEED], bump)]
+    pub governance_signer: Signer<'info>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut, seeds = [b"council_veto", proposal.key().as_ref()], bump)]
+    pub council_veto: Account<'info, CouncilVetoRecord>,
+}
+
+#[derive(Accounts)]
+pub struct VetoProposal<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct SetProposalBond<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(
+        constraint = bond_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = bond_vault.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub bond_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SettleProposalBond<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(mut, address = governance_config.bond_vault @ ErrorCode::InvalidBondVault)]
+    pub bond_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = proposer_token_account.owner == proposal.proposer @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = proposer_token_account.mint == bond_vault.mint @ ErrorCode::InvalidMint
+    )]
+    pub proposer_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut, address = treasury.vault)]
+    pub treasury_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetDescriptionPolicy<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"governance_config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"governance_config"],
+        bump,
+        constraint = governance_config.pending_authority == new_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStakeInfo<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in an older, shorter layout; owner and discriminator are checked in the handler.
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: UncheckedAccount<'info>,
+
+    /// CHECK: only used to derive the stake PDA.
+    pub staker: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in an older, shorter layout; owner and discriminator are checked in the handler.
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGovernanceConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in an older, shorter layout; owner and discriminator are checked in the handler.
+    #[account(mut, seeds = [b"governance_config"], bump)]
+    pub governance_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUsdcMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in the legacy layout; owner and discriminator are checked in the handler.
+    #[account(mut, seeds = [b"usdc_market"], bump)]
+    pub usdc_market: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEpochEmission<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EpochEmissionSchedule::LEN,
+        seeds = [b"epoch_emission"],
+        bump
+    )]
+    pub epoch_emission_schedule: Account<'info, EpochEmissionSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetEpochEmission<'info> {
+    #[account(seeds = [b"epoch_emission"], bump)]
+    pub epoch_emission_schedule: Account<'info, EpochEmissionSchedule>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureMigration<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MigrationGate::LEN,
+        seeds = [b"migration_gate"],
+        bump
+    )]
+    pub migration_gate: Account<'info, MigrationGate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExportPosition<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump, close = staker)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + PositionExport::LEN,
+        seeds = [b"position_export", staker.key().as_ref()],
+        bump
+    )]
+    pub position_export: Account<'info, PositionExport>,
+
+    #[account(seeds = [b"migration_gate"], bump)]
+    pub migration_gate: Account<'info, MigrationGate>,
+
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Vault token account of the target program; must be owned by its `vault` PDA.
+    #[account(
+        mut,
+        constraint = target_vault_token_account.owner == migration_gate.target_vault_authority()
+            @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = target_vault_token_account.mint == vault_token_account.mint @ ErrorCode::InvalidMint
+    )]
+    pub target_vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Position receipt NFT; required when the position has one.
+    #[account(mut)]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut, token::authority = staker)]
+    pub receipt_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Original vault authority, which stays the receipt freeze authority across vault rotations.
+    #[account(seeds = [b"vault"], bump)]
+    pub receipt_authority: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ImportPosition<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + StakeInfo::LEN,
+        seeds = [b"stake", staker.key().as_ref()],
+        bump
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// CHECK: owned by the source program; owner, address and contents are verified in the handler.
+    pub position_export: UncheckedAccount<'info>,
+
+    /// Marks the export proof as consumed so it can only be imported once.
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + PositionImport::LEN,
+        seeds = [b"position_import", position_export.key().as_ref()],
+        bump
+    )]
+    pub position_import: Account<'info, PositionImport>,
+
+    #[account(seeds = [b"migration_gate"], bump)]
+    pub migration_gate: Account<'info, MigrationGate>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncPositionIndex<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PositionIndex::LEN,
+        seeds = [b"position_index", owner.key().as_ref()],
+        bump
+    )]
+    pub position_index: Account<'info, PositionIndex>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EmissionSchedule::LEN,
+        seeds = [b"emission_schedule"],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEmissionSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLottery<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Lottery::LEN,
+        seeds = [b"lottery"],
+        bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        constraint = lottery_vault.owner == lottery.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = lottery_vault.mint == sst_mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub lottery_vault: Box<Account<'info, TokenAccount>>,
+
+    pub sst_mint: Box<Account<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundLottery<'info> {
+    #[account(seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut, address = lottery.vault)]
+    pub lottery_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Stake vault holding the withheld penalties.
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Receives the stakers' share of the penalties.
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"bonus_pool"], bump)]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Vault authority PDA recorded in config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EnterLottery<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + LotteryTicket::LEN,
+        seeds = [b"lottery_ticket", lottery.round.to_le_bytes().as_ref(), staker.key().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, LotteryTicket>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RequestLotteryDraw<'info> {
+    #[account(mut, seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+
+    /// CHECK: Switchboard randomness account; owner, layout and seed slot are checked in the handler.
+    pub randomness_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleLotteryDraw<'info> {
+    #[account(mut, seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(address = lottery.vault)]
+    pub lottery_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Must be the randomness account committed by `request_lottery_draw`.
+    pub randomness_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLotteryPrize<'info> {
+    pub winner: Signer<'info>,
+
+    #[account(mut, seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        seeds = [b"lottery_ticket", ticket.round.to_le_bytes().as_ref(), winner.key().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, LotteryTicket>,
+
+    #[account(seeds = [b"stake", winner.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = lottery.vault)]
+    pub lottery_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub winner_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireLotteryPrize<'info> {
+    #[account(mut, seeds = [b"lottery"], bump)]
+    pub lottery: Account<'info, Lottery>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAchievements<'info> {
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    pub funder: Signer<'info>,
+
+    #[account(mut)]
+    pub funder_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The configured SST reward vault.
+    #[account(
+        mut,
+        address = config.reward_vault @ ErrorCode::InvalidRewardVault,
+        constraint = reward_vault.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(campaign_id: u64)]
+pub struct CreateBonusCampaign<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BonusCampaign::LEN,
+        seeds = [b"bonus_campaign", campaign_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bonus_campaign: Account<'info, BonusCampaign>,
+
+    #[account(mut)]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.reward_vault @ ErrorCode::InvalidRewardVault,
+        constraint = reward_vault.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeBonus<'info> {
+    pub distributor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bonus_campaign", bonus_campaign.campaign_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = distributor @ ErrorCode::Unauthorized
+    )]
+    pub bonus_campaign: Account<'info, BonusCampaign>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardTokenRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RewardTokenRegistry::LEN,
+        seeds = [b"reward_tokens"],
+        bump
+    )]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterRewardToken<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Vault paying out this reward token; owned by the vault authority like the main reward vault.
+    #[account(
+        constraint = reward_token_vault.mint == reward_mint.key() @ ErrorCode::InvalidRewardTokenAccounts,
+        constraint = reward_token_vault.owner == vault_authority.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub reward_token_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewardToken<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+}
+
+#[derive(Accounts)]
+pub struct GetPendingRewards<'info> {
+    #[account(seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct OpenRewardVesting<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + RewardVesting::LEN,
+        seeds = [b"reward_vesting", staker.key().as_ref()],
+        bump
+    )]
+    pub reward_vesting: Account<'info, RewardVesting>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vesting", staker.key().as_ref()],
+        bump,
+        has_one = staker @ ErrorCode::Unauthorized
+    )]
+    pub reward_vesting: Account<'info, RewardVesting>,
+
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_token_account.mint == config.sst_mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRewardVesting<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still be in the layout from before tranches; owner and discriminator are checked in the handler.
+    #[account(mut, seeds = [b"reward_vesting", staker.key().as_ref()], bump)]
+    pub reward_vesting: UncheckedAccount<'info>,
+
+    /// CHECK: only used to derive the escrow PDA.
+    pub staker: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BeginVaultRotation<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultRotation::LEN,
+        seeds = [b"vault_rotation"],
+        bump
+    )]
+    pub vault_rotation: Account<'info, VaultRotation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVaultBatch<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"vault_rotation"], bump)]
+    pub vault_rotation: Account<'info, VaultRotation>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteVaultRotation<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"vault_rotation"], bump, close = authority)]
+    pub vault_rotation: Account<'info, VaultRotation>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateAccounts<'info> {
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub stake_info: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub staker_token_account: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub vault_token_account: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub protocol_stats: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub emission_schedule: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub reward_tokens: UncheckedAccount<'info>,
+    /// CHECK: Validated by the instruction and reported, never trusted.
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(code: [u8; 16])]
+pub struct RegisterIntegrationCode<'info> {
+    #[account(mut)]
+    pub registrant: Signer<'info>,
+
+    #[account(
+        init,
+        payer = registrant,
+        space = 8 + IntegrationStats::LEN,
+        seeds = [b"integration", code.as_ref()],
+        bump
+    )]
+    pub integration_stats: Account<'info, IntegrationStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(campaign_id: u64)]
+pub struct CreateBoostCampaign<'info> {
+    #[account(mut)]
+    pub partner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = partner,
+        space = 8 + BoostCampaign::LEN,
+        seeds = [b"boost_campaign", partner.key().as_ref(), campaign_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub boost_campaign: Account<'info, BoostCampaign>,
+
+    pub funding_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = partner,
+        token::mint = funding_mint,
+        token::authority = boost_campaign,
+        seeds = [b"boost_vault", boost_campaign.key().as_ref()],
+        bump
+    )]
+    pub boost_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub partner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBoostPosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub boost_campaign: Account<'info, BoostCampaign>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BoostPosition::LEN,
+        seeds = [b"boost_position", boost_campaign.key().as_ref(), stake_info.staker.as_ref()],
+        bump
+    )]
+    pub boost_position: Account<'info, BoostPosition>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimBoostRemainder<'info> {
+    pub partner: Signer<'info>,
+
+    #[account(mut, has_one = partner @ ErrorCode::Unauthorized)]
+    pub boost_campaign: Account<'info, BoostCampaign>,
+
+    #[account(mut, address = boost_campaign.vault)]
+    pub boost_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = partner_token_account.mint == boost_campaign.funding_mint @ ErrorCode::InvalidMint
+    )]
+    pub partner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBoostRewards<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut)]
+    pub boost_campaign: Account<'info, BoostCampaign>,
+
+    #[account(
+        mut,
+        seeds = [b"boost_position", boost_campaign.key().as_ref(), staker.key().as_ref()],
+        bump
+    )]
+    pub boost_position: Account<'info, BoostPosition>,
+
+    #[account(mut, address = boost_campaign.vault)]
+    pub boost_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_token_account.mint == boost_campaign.funding_mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAll<'info> {
+    /// Owner or registered operator of every position in the batch.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Stake vault receiving rewards compounded by auto-restake positions.
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Vault authority PDA recorded in config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Receives the referral share of referred positions; required when any position has a referrer.
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetIncidentMode<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeConfigAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptConfigAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        constraint = config.pending_authority == new_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct PauseProtocol<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump, has_one = guardian @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(seeds = [GOVERNANCE_SIGNER_SEED], bump)]
+    pub governance_signer: Signer<'info>,
+
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Stake vault; pinned since the crank is permissionless.
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidStakeVault)]
+    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Vault authority PDA recorded in config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Referral account of `stake_info.referrer`; required when the position has a referrer.
+    #[account(mut, seeds = [b"referral", stake_info.referrer.as_ref()], bump)]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePayoutRoutes<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PayoutRouteTable::LEN,
+        seeds = [b"payout_routes"],
+        bump
+    )]
+    pub payout_routes: Account<'info, PayoutRouteTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutRoute<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"payout_routes"], bump)]
+    pub payout_routes: Account<'info, PayoutRouteTable>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardsRouted<'info> {
+    /// Position owner or its registered operator.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", stake_info.staker.as_ref()],
+        bump,
+        constraint = stake_info.is_owner_or_operator(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// Payout-mint destination: the allowlisted withdrawal address or registered beneficiary if set.
+    #[account(
+        mut,
+        constraint = stake_info.reward_destination_allowed(payout_token_account.key())
+            @ ErrorCode::InvalidRewardBeneficiary
+    )]
+    pub payout_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = config.reward_vault @ ErrorCode::InvalidRewardVault)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA owning the route escrow; the only signer the route program sees.
+    #[account(seeds = [b"route_escrow"], bump)]
+    pub route_escrow_authority: UncheckedAccount<'info>,
+
+    /// Holds just the claimed amount while the swap runs.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = sst_mint,
+        token::authority = route_escrow_authority,
+        seeds = [b"route_escrow_vault"],
+        bump
+    )]
+    pub route_escrow_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = config.sst_mint @ ErrorCode::InvalidMint)]
+    pub sst_mint: Box<Account<'info, Mint>>,
+
+    #[account(seeds = [b"payout_routes"], bump)]
+    pub payout_routes: Account<'info, PayoutRouteTable>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    /// CHECK: Vault authority PDA recorded in config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: AMM program; must match the route's program, verified in the handler.
+    pub amm_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Referral account of `stake_info.referrer`; required when the position has a referrer.
+    #[account(mut, seeds = [b"referral", stake_info.referrer.as_ref()], bump)]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeBonusPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BonusPool::LEN,
+        seeds = [b"bonus_pool"],
+        bump
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FlagDormantPosition<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDormantPosition<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump, close = staker)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    /// CHECK: Position owner; receives the reclaimed rent.
+    #[account(mut, address = stake_info.staker)]
+    pub staker: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositRevenue<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(mut)]
+    pub depositor_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Distribution vault of the revenue mint's registry slot.
+    #[account(mut)]
+    pub revenue_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(mut, seeds = [b"reward_tokens"], bump)]
+    pub reward_tokens: Account<'info, RewardTokenRegistry>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferralAccount::LEN,
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    pub referrer: Signer<'info>,
+
+    #[account(mut, seeds = [b"referral", referrer.key().as_ref()], bump)]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    #[account(mut, address = config.referral_vault @ ErrorCode::InvalidReferralVault)]
+    pub referral_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = referrer_token_account.owner == referrer.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub referrer_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLoyaltyAccount<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + LoyaltyAccount::LEN,
+        seeds = [b"loyalty", staker.key().as_ref()],
+        bump
+    )]
+    pub loyalty_account: Account<'info, LoyaltyAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_id: u64)]
+pub struct CreateSeason<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::LEN,
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+
+    /// Dedicated SST prize vault owned by the season; funded by plain transfers.
+    #[account(
+        init,
+        payer = authority,
+        token::mint = sst_mint,
+        token::authority = season,
+        seeds = [b"season_prize", season.key().as_ref()],
+        bump
+    )]
+    pub prize_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = config.sst_mint @ ErrorCode::InvalidMint)]
+    pub sst_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinSeason<'info> {
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    #[account(seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Account<'info, Season>,
+
+    #[account(mut, seeds = [b"stake", participant.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init,
+        payer = participant,
+        space = 8 + SeasonScore::LEN,
+        seeds = [b"season_score", season.key().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub season_score: Account<'info, SeasonScore>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSeason<'info> {
+    #[account(mut, seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Account<'info, Season>,
+
+    #[account(address = season.prize_vault @ ErrorCode::InvalidSeasonAccounts)]
+    pub prize_vault: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSeasonPrize<'info> {
+    pub participant: Signer<'info>,
+
+    #[account(seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Account<'info, Season>,
+
+    #[account(mut, seeds = [b"season_score", season.key().as_ref(), participant.key().as_ref()], bump)]
+    pub season_score: Account<'info, SeasonScore>,
+
+    #[account(mut, address = season.prize_vault @ ErrorCode::InvalidSeasonAccounts)]
+    pub prize_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = destination.mint == prize_vault.mint @ ErrorCode::InvalidMint)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSeasonScore<'info> {
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"season", season.id.to_le_bytes().as_ref()], bump)]
+    pub season: Account<'info, Season>,
+
+    #[account(mut, seeds = [b"season_score", season.key().as_ref(), stake_info.staker.as_ref()], bump)]
+    pub season_score: Account<'info, SeasonScore>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimAs<'info> {
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+    pub staker: Signer<'info>,
+
+    #[account(seeds = [b"payout_routes"], bump)]
+    pub payout_routes: Account<'info, PayoutRouteTable>,
+
+    /// SST reward vault; its mint is the reward side of the payout route.
+    #[account(constraint = reward_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLP<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        mut,
+        constraint = staker_lp_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_lp_token_account.mint == vault_lp_token_account.mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = vault_lp_token_account.owner == vault_authority.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub vault_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLpPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LpPool::LEN,
+        seeds = [b"lp_pool", lp_mint.key().as_ref()],
+        bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+
+    /// Holds the pool's deposited LP tokens.
+    #[account(
+        constraint = lp_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = lp_vault.mint == lp_mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Holds the farm's reward budget.
+    #[account(constraint = reward_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLpPool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"lp_pool", lp_pool.lp_mint.as_ref()], bump)]
+    pub lp_pool: Account<'info, LpPool>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLpFarm<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"lp_pool", lp_pool.lp_mint.as_ref()], bump)]
+    pub lp_pool: Account<'info, LpPool>,
+
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = 8 + LpFarmPosition::LEN,
+        seeds = [b"lp_farm", lp_pool.key().as_ref(), staker.key().as_ref()],
+        bump
+    )]
+    pub lp_farm_position: Account<'info, LpFarmPosition>,
+
+    #[account(mut)]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = lp_pool.lp_vault @ ErrorCode::InvalidLpPoolAccounts)]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLpFarm<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"lp_pool", lp_pool.lp_mint.as_ref()], bump)]
+    pub lp_pool: Account<'info, LpPool>,
+
+    #[account(mut, seeds = [b"lp_farm", lp_pool.key().as_ref(), staker.key().as_ref()], bump)]
+    pub lp_farm_position: Account<'info, LpFarmPosition>,
+
+    #[account(mut, constraint = staker_lp_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner)]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = lp_pool.lp_vault @ ErrorCode::InvalidLpPoolAccounts)]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpFarmRewards<'info> {
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"lp_pool", lp_pool.lp_mint.as_ref()], bump)]
+    pub lp_pool: Account<'info, LpPool>,
+
+    #[account(mut, seeds = [b"lp_farm", lp_pool.key().as_ref(), staker.key().as_ref()], bump)]
+    pub lp_farm_position: Account<'info, LpFarmPosition>,
+
+    #[account(mut, address = lp_pool.rewards.vault @ ErrorCode::InvalidRewardTokenAccounts)]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = staker_reward_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_reward_account.mint == lp_pool.rewards.mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_reward_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLpMintRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LpMintRegistry::LEN,
+        seeds = [b"lp_mints"],
+        bump
+    )]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssignLpVault<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"stake", stake_info.staker.as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct SetLpMint<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    /// Vault holding deposits of this LP mint.
+    #[account(constraint = lp_vault.owner == config.vault_authority @ ErrorCode::InvalidTokenAccountOwner)]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+#[instruction(lock_id: u64)]
+pub struct DepositLpLocked<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init,
+        payer = staker,
+        space = 8 + LpLock::LEN,
+        seeds = [b"lp_lock", staker.key().as_ref(), lock_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub lp_lock: Account<'info, LpLock>,
+
+    #[account(mut)]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLockedLp<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        mut,
+        close = staker,
+        seeds = [b"lp_lock", staker.key().as_ref(), lp_lock.lock_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub lp_lock: Account<'info, LpLock>,
+
+    #[account(
+        mut,
+        constraint = staker_lp_token_account.owner == staker.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = staker_lp_token_account.mint == lp_lock.lp_mint @ ErrorCode::InvalidMint
+    )]
+    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [b"lp_mints"], bump)]
+    pub lp_mints: Account<'info, LpMintRegistry>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"protocol_stats"], bump)]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    /// CHECK: The active vault authority recorded in the config.
+    #[account(address = config.vault_authority @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
 }
 
-#[derive(Accounts)]
-pub struct ExecuteTrade<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+#[account]
+pub struct InsuranceFund {
+    pub balance: u64,
+    /// Outstanding underwriting receipt shares.
+    pub total_shares: u64,
+    pub receipt_mint: Pubkey,
+    pub token_account: Pubkey,
+}
+
+impl InsuranceFund {
+    const LEN: usize = 8 + 8 + 32 + 32;
+}
+
+/// Global, continuously updated protocol metrics.
+#[account]
+pub struct ProtocolStats {
+    pub total_staked: u64,
+    pub total_usdc_staked: u64,
+    pub total_lp_deposited: u64,
+    pub total_rewards_distributed: u64,
+    pub last_snapshot_epoch: u64,
+    pub last_snapshot_time: i64,
+    pub rewards_at_last_snapshot: u64,
+    pub snapshot_count: u64,
+    /// Cumulative rewards per staked token, scaled by `math::REWARD_SCALE`.
+    pub reward_per_token_stored: u128,
+    pub last_reward_update: i64,
+    /// Early-unstake penalties withheld in the stake vault until `fund_lottery` splits them.
+    pub penalties_withheld: u64,
+    /// Total ever deposited through `fund_rewards`.
+    pub total_rewards_funded: u64,
+    /// Funded rewards not yet paid out.
+    pub rewards_outstanding: u64,
+    /// Reward remainders lost to rounding, scaled by `math::REWARD_SCALE`; swept into the next update.
+    pub reward_dust: u128,
+    /// Stake deposited during the epoch of `last_reward_update`, not yet earning.
+    pub pending_stake_total: u64,
+    /// Accumulator values at recent epoch boundaries where pending stake was activated (ring buffer).
+    pub reward_checkpoints: [RewardCheckpoint; REWARD_CHECKPOINT_HISTORY],
+    pub next_reward_checkpoint: u8,
+    /// Reward epoch `epoch_rewards_paid` refers to.
+    pub reward_cap_epoch: u64,
+    /// Rewards paid out during `reward_cap_epoch`, limited by `ProtocolConfig::epoch_reward_cap`.
+    pub epoch_rewards_paid: u64,
+    /// Outstanding borrowed principal across all positions.
+    pub total_borrowed: u64,
+    /// Cumulative borrow interest factor, in Q64.64 (starts at `BORROW_INDEX_ONE`).
+    pub borrow_index: u128,
+    pub borrow_index_updated_at: i64,
+}
+
+impl ProtocolStats {
+    const LEN: usize = 8 * 8 + 16 + 8 + 8 + 8 + 8 + 16 + 8 + RewardCheckpoint::LEN * REWARD_CHECKPOINT_HISTORY + 1 + 8 + 8
+        + 8 + 16 + 8;
+
+    /// Records `amount` of funded rewards as paid out. Paying more than was funded is an error
+    /// rather than a silent deficit.
+    fn pay_out_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards_outstanding = self.rewards_outstanding.checked_sub(amount).ok_or(ErrorCode::RewardsUnderfunded)?;
+        Ok(())
+    }
+
+    /// Grows the borrow index to `now` at the curve's rate for the current utilization of the
+    /// borrow vault (`available` tokens left to lend).
+    fn accrue_borrow_index(&mut self, curve: &BorrowRateCurve, available: u64, now: i64) -> Result<()> {
+        if self.borrow_index == 0 {
+            self.borrow_index = BORROW_INDEX_ONE;
+            self.borrow_index_updated_at = now;
+        }
+        let elapsed = now.saturating_sub(self.borrow_index_updated_at);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+        self.borrow_index = curve.grow_index(self.borrow_index, self.total_borrowed, available, elapsed)?;
+        self.borrow_index_updated_at = now;
+        Ok(())
+    }
+
+    /// Advances the reward-per-token accumulator to `now`. Stake deposited during the epoch of
+    /// the last update only joins the earning balance at the following epoch boundary, where the
+    /// accumulator is checkpointed so positions can settle their pending deposits against it.
+    fn update_reward_per_token(&mut self, schedule: &EmissionSchedule, now: i64) -> Result<()> {
+        if now <= self.last_reward_update {
+            return Ok(());
+        }
+        let boundary = reward_epoch(self.last_reward_update)
+            .checked_add(1)
+            .and_then(|epoch| (epoch as i64).checked_mul(REWARD_EPOCH_DURATION))
+            .ok_or(ErrorCode::Overflow)?;
+        if self.pending_stake_total > 0 && now >= boundary {
+            self.accrue_rewards(schedule, boundary)?;
+            let slot = self.next_reward_checkpoint as usize % REWARD_CHECKPOINT_HISTORY;
+            self.reward_checkpoints[slot] = RewardCheckpoint {
+                epoch: reward_epoch(boundary),
+                reward_per_token: self.reward_per_token_stored,
+            };
+            self.next_reward_checkpoint = ((slot + 1) % REWARD_CHECKPOINT_HISTORY) as u8;
+            self.pending_stake_total = 0;
+        }
+        self.accrue_rewards(schedule, now)
+    }
+
+    /// Distributes what the emission schedule released up to `to` (plus swept dust) pro rata
+    /// across the earning stake.
+    fn accrue_rewards(&mut self, schedule: &EmissionSchedule, to: i64) -> Result<()> {
+        let earning_stake = self.total_staked.checked_sub(self.pending_stake_total).ok_or(ErrorCode::Underflow)?;
+        if earning_stake > 0 {
+            let emitted = math::to_reward_scale(schedule.emitted_between(self.last_reward_update, to)?)?;
+            let (increment, dust) = sweep_reward_dust(emitted, self.reward_dust, earning_stake)?;
+            self.reward_per_token_stored = self.reward_per_token_stored
+                .checked_add(increment)
+                .ok_or(ErrorCode::Overflow)?;
+            self.reward_dust = dust;
+        }
+        self.last_reward_update = to;
+        Ok(())
+    }
+
+    /// Reserves up to `requested` from the current reward epoch's payout allowance (`cap == 0` =
+    /// unlimited) and returns the amount granted.
+    fn take_epoch_allowance(&mut self, cap: u64, requested: u64, now: i64) -> Result<u64> {
+        let epoch = reward_epoch(now);
+        if epoch != self.reward_cap_epoch {
+            self.reward_cap_epoch = epoch;
+            self.epoch_rewards_paid = 0;
+        }
+        let granted = if cap == 0 {
+            requested
+        } else {
+            requested.min(cap.saturating_sub(self.epoch_rewards_paid))
+        };
+        self.epoch_rewards_paid = self.epoch_rewards_paid.checked_add(granted).ok_or(ErrorCode::Overflow)?;
+        Ok(granted)
+    }
+
+    /// Accumulator value at which stake deposited during `deposit_epoch` started earning. If that
+    /// checkpoint has rotated out, the oldest later one is used, which under-pays rather than over-pays.
+    fn activation_reward_per_token(&self, deposit_epoch: u64) -> u128 {
+        self.reward_checkpoints
+            .iter()
+            .filter(|checkpoint| checkpoint.epoch > deposit_epoch)
+            .min_by_key(|checkpoint| checkpoint.epoch)
+            .map(|checkpoint| checkpoint.reward_per_token)
+            .unwrap_or(self.reward_per_token_stored)
+    }
+}
+
+/// Immutable record of protocol metrics at a given epoch.
+#[account]
+pub struct StatsSnapshot {
+    pub epoch: u64,
+    pub timestamp: i64,
+    pub previous_epoch: u64,
+    pub total_staked: u64,
+    pub total_usdc_staked: u64,
+    pub total_lp_deposited: u64,
+    pub total_rewards_distributed: u64,
+    pub period_rewards: u64,
+    pub apr_bps: u64,
+}
+
+impl StatsSnapshot {
+    const LEN: usize = 9 * 8;
+}
+
+#[account]
+pub struct StakeInfo {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub last_staked_time: i64,
+    pub lock_period: u64,
+    pub locked_until: i64,
+    pub borrowed_amount: u64,
+    pub locked: bool,
+    pub auto_restake: bool,
+    pub usdc_amount: u64,
+    pub lp_deposit: u64,
+    /// Re-lock the position for the same period when it matures.
+    pub auto_relock: bool,
+    /// Keeper may push the whole position out once the lock matures and `scheduled_withdrawal_at` passes.
+    pub unstake_at_maturity: bool,
+    /// Token account that receives a scheduled withdrawal.
+    pub maturity_payout_account: Pubkey,
+    /// Settled unstake payout held in the vault because the destination account was frozen.
+    pub pending_withdrawal: u64,
+    /// Token account that receives claimed rewards (default = staker's choice).
+    pub reward_beneficiary: Pubkey,
+    /// Allowlisted token account for all unstake and claim payouts (default = unrestricted).
+    pub withdrawal_address: Pubkey,
+    /// Staged replacement for `withdrawal_address`, applicable after `withdrawal_address_change_at`.
+    pub pending_withdrawal_address: Pubkey,
+    pub withdrawal_address_change_at: i64,
+    /// Once set, the withdrawal address can never be changed.
+    pub withdrawal_address_immutable: bool,
+    /// Hot key allowed to claim, toggle auto-restake and trade on behalf of the owner.
+    pub operator: Pubkey,
+    /// Second signer required for unstakes above `two_factor_threshold` (default = disabled).
+    pub second_signer: Pubkey,
+    pub two_factor_threshold: u64,
+    /// When set, the owner may act without the second signer from this time on.
+    pub two_factor_bypass_at: i64,
+    /// Gifted stake that vests linearly between `gift_vesting_start` and `gift_vesting_end`.
+    pub gift_vesting_amount: u64,
+    pub gift_vesting_start: i64,
+    pub gift_vesting_end: i64,
+    /// Locked amount the progressive unlock schedule applies to.
+    pub vesting_principal: u64,
+    /// Portion of the vested principal already withdrawn during the current lock.
+    pub vested_withdrawn: u64,
+    /// Layout version (0 = written before versioning); new fields go after this one.
+    pub version: u8,
+    /// Lifetime statistics (v2): tokens ever staked, rewards earned, penalties paid and trades executed.
+    pub lifetime_staked: u64,
+    pub lifetime_rewards: u64,
+    pub lifetime_penalties: u64,
+    pub trade_count: u64,
+    /// Receipt NFT minted for this position (v3; default = none).
+    pub receipt_mint: Pubkey,
+    /// Reward-per-token accumulator value at the last settlement (v4).
+    pub reward_per_token_paid: u128,
+    /// Rewards settled but not yet claimed.
+    pub accrued_rewards: u64,
+    /// Bitmask of reached milestones (`ACHIEVEMENT_*`, v5).
+    pub achievements: u64,
+    pub proposals_voted: u64,
+    /// Per-slot accumulator checkpoints and unclaimed balances for registry reward tokens (v6).
+    pub reward_token_paid: [u128; MAX_REWARD_TOKENS],
+    pub reward_token_accrued: [u64; MAX_REWARD_TOKENS],
+    /// Deposited during reward epoch `pending_stake_epoch`; starts earning SST at the next boundary (v7).
+    pub pending_stake: u64,
+    pub pending_stake_epoch: u64,
+    /// Earliest time a scheduled withdrawal may execute, on top of lock maturity (v8).
+    pub scheduled_withdrawal_at: i64,
+    /// Portion of `ProtocolConfig::incident_offset` already applied to this position's timers (v9).
+    pub incident_offset_applied: i64,
+    /// Rules the compounding crank follows for auto-restake positions (v10).
+    pub compound_policy: CompoundPolicy,
+    pub last_compounded_at: i64,
+    /// Last deposit, withdrawal or claim by the staker (v11).
+    pub last_activity_at: i64,
+    /// When the position was flagged as dormant (0 = not flagged).
+    pub dormant_flagged_at: i64,
+    /// Referrer credited with a share of this position's claims (v12; default = none).
+    pub referrer: Pubkey,
+    /// Claim-as mode: mint rewards are swapped into on claim (v13; default = paid in SST).
+    pub claim_as_mint: Pubkey,
+    /// Last LP deposit; starts the LP lockup (v14).
+    pub lp_deposited_at: i64,
+    /// Registered mint of `lp_deposit` (v15; default for deposits made before the LP registry).
+    pub lp_mint: Pubkey,
+    /// Boost weight of `lp_mint` as of the latest LP deposit (0 = 1x).
+    pub lp_boost_weight_bps: u64,
+    /// LP held in `LpLock` records (v16); not withdrawable through `withdraw_lp`.
+    pub locked_lp_deposit: u64,
+    /// Sum of the tier-weighted amounts of those records.
+    pub locked_lp_weight: u64,
+    /// Position state before each of the latest deposits (v17), used to snapshot voting power.
+    pub voting_checkpoints: [VotingCheckpoint; VOTING_CHECKPOINTS],
+    pub next_voting_checkpoint: u8,
+    /// Staker this position's voting power is delegated to (v18; default = votes itself).
+    pub delegate: Pubkey,
+    /// Positions currently delegating to this one.
+    pub delegator_count: u32,
+    /// SST in the vote-escrow lock (v19); backs a non-transferable veSST balance.
+    pub ve_locked: u64,
+    pub ve_unlock_at: i64,
+    /// Last time the lock was created or increased.
+    pub ve_updated_at: i64,
+    /// Unpaid interest on `borrowed_amount` (v20).
+    pub borrow_interest: u64,
+    /// Retired v20 accrual timestamp, kept for layout; interest now follows `borrow_index_paid`.
+    pub borrow_accrued_at: i64,
+    /// USDC loan from the USDC market (v21): principal, unpaid interest and index last accrued at.
+    pub usdc_borrowed: u64,
+    pub usdc_borrow_interest: u64,
+    pub usdc_borrow_index_paid: u128,
+    /// SST backing the USDC loan at the latest borrow's price. Informational: health checks value
+    /// the loan at the live feed price.
+    pub usdc_collateral: u64,
+    /// Flash loan taken earlier in the current transaction (v22); 0 once repaid.
+    pub flash_loan_outstanding: u64,
+    /// Frozen token account `pending_withdrawal` was escrowed for (v23).
+    pub pending_withdrawal_account: Pubkey,
+    /// When SST rewards were last settled into `accrued_rewards` (v24); bounds the lock-multiplier
+    /// window.
+    pub rewards_settled_at: i64,
+    /// Boost campaigns the position holds weight in (v25; default = free slot). Every instruction
+    /// that changes the stake re-syncs them from `remaining_accounts`.
+    pub boost_campaigns: [Pubkey; MAX_BOOST_ENROLLMENTS],
+    /// Season score account the position scores in (v26; default = none). Instructions that change
+    /// the stake must pass its season accounts.
+    pub season_score: Pubkey,
+    /// LP boost earned and not yet claimed (v27).
+    pub lp_boost_accrued: u64,
+    /// When the LP boost was last accrued (v27); 0 until the first accrual starts the clock.
+    pub lp_boost_updated_at: i64,
+    /// Vault holding `lp_deposit` (v28); the only vault `withdraw_lp` releases it from. Default for
+    /// deposits made before it was recorded.
+    pub lp_vault: Pubkey,
+    /// Borrow index `borrowed_amount` was last accrued at (v29); 0 until the next accrual stamps it.
+    pub borrow_index_paid: u128,
+    /// Collateral factor in force at the latest borrow (v30); liquidation never uses a lower one.
+    pub borrow_collateral_factor_bps: u64,
+}
+
+impl StakeInfo {
+    /// Records the position's weight in `campaign`: enrolls it while the weight is nonzero and
+    /// releases the slot once it drops to zero.
+    fn set_boost_enrollment(&mut self, campaign: Pubkey, weight: u64) -> Result<()> {
+        let enrolled = self.boost_campaigns.iter().position(|key| *key == campaign);
+        match (enrolled, weight > 0) {
+            (Some(slot), false) => self.boost_campaigns[slot] = Pubkey::default(),
+            (None, true) => {
+                let slot = self
+                    .boost_campaigns
+                    .iter()
+                    .position(|key| *key == Pubkey::default())
+                    .ok_or(ErrorCode::TooManyBoostCampaigns)?;
+                self.boost_campaigns[slot] = campaign;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Record the current stake before any increase of it at `now`. Several deposits in the same second
+    /// share the checkpoint of the first.
+    fn checkpoint_voting_power(&mut self, now: i64) {
+        self.push_voting_checkpoint(VotingCheckpoint {
+            amount: self.amount,
+            last_staked_time: self.last_staked_time,
+            recorded_at: now,
+        });
+    }
+
+    fn push_voting_checkpoint(&mut self, checkpoint: VotingCheckpoint) {
+        let latest = (self.next_voting_checkpoint as usize + VOTING_CHECKPOINTS - 1) % VOTING_CHECKPOINTS;
+        if self.voting_checkpoints[latest].recorded_at == checkpoint.recorded_at && checkpoint.recorded_at != 0 {
+            return;
+        }
+        let slot = self.next_voting_checkpoint as usize % VOTING_CHECKPOINTS;
+        self.voting_checkpoints[slot] = checkpoint;
+        self.next_voting_checkpoint = ((slot + 1) % VOTING_CHECKPOINTS) as u8;
+    }
+
+    /// veSST balance at `at`: the locked amount scaled by the time left to unlock over
+    /// `MAX_VE_LOCK_DURATION`. A lock changed at or after `at` counts for nothing then.
+    fn ve_balance_at(&self, at: i64) -> Result<u64> {
+        if self.ve_locked == 0 || self.ve_updated_at >= at || self.ve_unlock_at <= at {
+            return Ok(0);
+        }
+        let remaining = (self.ve_unlock_at - at).min(MAX_VE_LOCK_DURATION);
+        mul_div_floor(self.ve_locked as u128, remaining as u128, MAX_VE_LOCK_DURATION as u128)
+    }
+
+    /// Stake amount and last stake time in effect before `at`: the state saved by the first
+    /// deposit at or after `at`, or the current state if there was none. If that deposit has
+    /// already rotated out of the history the position counts as empty.
+    fn voting_position_at(&self, at: i64) -> (u64, i64) {
+        let later = self.voting_checkpoints.iter().filter(|checkpoint| checkpoint.recorded_at >= at && checkpoint.recorded_at != 0);
+        match later.min_by_key(|checkpoint| checkpoint.recorded_at) {
+            None => (self.amount, self.last_staked_time),
+            Some(checkpoint) => {
+                let history_full = self.voting_checkpoints.iter().all(|checkpoint| checkpoint.recorded_at != 0);
+                let oldest = self.voting_checkpoints[self.next_voting_checkpoint as usize % VOTING_CHECKPOINTS];
+                if history_full && oldest.recorded_at == checkpoint.recorded_at {
+                    (0, at)
+                } else {
+                    (checkpoint.amount, checkpoint.last_staked_time)
+                }
+            }
+        }
+    }
+
+    const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 1 + 1 + 32 + 8 + 32 + 32 + 32 + 8 + 1 + 32 + 32 + 8 + 8
+        + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 32 + 16 + 8 + 8 + 8
+        + 16 * MAX_REWARD_TOKENS + 8 * MAX_REWARD_TOKENS + 8 + 8 + 8 + 8
+        + CompoundPolicy::LEN + 8 + 8 + 8 + 32 + 32 + 8 + 32 + 8 + 8 + 8
+        + VotingCheckpoint::LEN * VOTING_CHECKPOINTS + 1 + 32 + 4 + 8 + 8 + 8 + 8 + 8
+        + 8 + 8 + 16 + 8 + 8 + 32 + 8
+        + 32 * MAX_BOOST_ENROLLMENTS + 32 + 8 + 8 + 32 + 16 + 8;
+
+    /// Accrues the LP boost earned by the weighted LP deposit since the last accrual. Must run
+    /// before the LP deposit, its lock weight or its mint's boost weight changes.
+    fn accrue_lp_boost(&mut self, now: i64) -> Result<()> {
+        if self.lp_boost_updated_at > 0 && now > self.lp_boost_updated_at {
+            let earned = lp_reward_boost(self.weighted_lp_deposit()?, now - self.lp_boost_updated_at)?;
+            self.lp_boost_accrued = self.lp_boost_accrued.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+        }
+        self.lp_boost_updated_at = now;
+        Ok(())
+    }
+
+    /// Accrues the LP boost and hands all of it to a claim.
+    fn take_lp_boost(&mut self, now: i64) -> Result<u64> {
+        self.accrue_lp_boost(now)?;
+        Ok(std::mem::take(&mut self.lp_boost_accrued))
+    }
+
+    /// LP deposit (locked LP at its tier weight) weighted by its mint's boost weight; the input
+    /// to the LP boost.
+    fn weighted_lp_deposit(&self) -> Result<u64> {
+        let lp_weight = self.lp_deposit.checked_add(self.locked_lp_weight).ok_or(ErrorCode::Overflow)?;
+        if self.lp_boost_weight_bps == 0 {
+            return Ok(lp_weight);
+        }
+        mul_div_floor(lp_weight as u128, self.lp_boost_weight_bps as u128, BPS_DENOMINATOR as u128)
+    }
+
+    /// Forgets the position's LP mint once it holds no LP, so another mint can be deposited.
+    fn clear_lp_mint_if_empty(&mut self) {
+        if self.lp_deposit == 0 && self.locked_lp_deposit == 0 {
+            self.lp_mint = Pubkey::default();
+            self.lp_vault = Pubkey::default();
+            self.lp_boost_weight_bps = 0;
+        }
+    }
+
+    /// Vested, not-yet-withdrawn amount of a locked position (the full amount once matured).
+    fn unlocked_amount(&self, now: i64) -> u64 {
+        if self.lock_period == 0 || now >= self.locked_until {
+            return self.amount;
+        }
+        let lock_start = self.locked_until.saturating_sub(self.lock_period as i64);
+        let elapsed = now.saturating_sub(lock_start).max(0) as u64;
+        let vested = math::pro_rata_floor(self.vesting_principal, elapsed, self.lock_period);
+        vested.saturating_sub(self.vested_withdrawn).min(self.amount)
+    }
+
+    /// Outstanding borrowed principal plus unpaid interest.
+    fn debt(&self) -> Result<u64> {
+        Ok(self.borrowed_amount.checked_add(self.borrow_interest).ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Stake needed to back the debt at a loan-to-value of `ltv_bps`. At the position's collateral
+    /// factor this is the stake reserved against unstaking.
+    fn collateral_required(&self, ltv_bps: u64) -> Result<u64> {
+        let debt = self.debt()?;
+        if debt == 0 {
+            return Ok(0);
+        }
+        mul_div_ceil(debt as u128, BPS_DENOMINATOR as u128, ltv_bps as u128)
+    }
+
+    /// Stake needed to back both loans together: the SST debt at `ltv_bps`, plus the USDC debt at
+    /// the `usdc` price and loan-to-value when the position owes USDC.
+    fn total_collateral_required(&self, ltv_bps: u64, usdc: Option<(u128, u64)>) -> Result<u64> {
+        let required = self.collateral_required(ltv_bps)?;
+        let usdc_debt = self.usdc_debt()?;
+        if usdc_debt == 0 {
+            return Ok(required);
+        }
+        let (price, usdc_ltv_bps) = usdc.ok_or(ErrorCode::InvalidPriceFeed)?;
+        Ok(required
+            .checked_add(usdc_collateral_required(usdc_debt, price, usdc_ltv_bps)?)
+            .ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Rejects a withdrawal that would leave `remaining` stake below the collateral backing both
+    /// loans, the USDC loan valued at the `usdc` price and the market's maximum loan-to-value.
+    fn ensure_collateralized(
+        &self,
+        config: &ProtocolConfig,
+        remaining: u64,
+        now: i64,
+        usdc: Option<(u128, u64)>,
+    ) -> Result<()> {
+        let required = self.total_collateral_required(config.collateral_factor_bps(self, now), usdc)?;
+        require!(
+            remaining >= required,
+            ErrorCode::InsufficientCollateral
+        );
+        Ok(())
+    }
+
+    /// Whether both loans together exceed what the stake supports at the collateral factor plus
+    /// `LIQUIDATION_LTV_MARGIN_BPS` and, for the USDC loan, the `usdc` price and liquidation
+    /// loan-to-value.
+    fn is_liquidatable(&self, collateral_factor_bps: u64, usdc: Option<(u128, u64)>) -> Result<bool> {
+        let liquidation_ltv_bps = collateral_factor_bps + LIQUIDATION_LTV_MARGIN_BPS;
+        Ok((self.debt()? > 0 || self.usdc_debt()? > 0)
+            && self.amount < self.total_collateral_required(liquidation_ltv_bps, usdc)?)
+    }
+
+    /// Applies a repayment to unpaid interest first, then principal; returns both parts.
+    fn apply_repayment(&mut self, amount: u64) -> Result<(u64, u64)> {
+        let interest_paid = amount.min(self.borrow_interest);
+        let principal_paid = amount - interest_paid;
+        self.borrow_interest -= interest_paid;
+        self.borrowed_amount = self.borrowed_amount.checked_sub(principal_paid).ok_or(ErrorCode::Underflow)?;
+        Ok((interest_paid, principal_paid))
+    }
+
+    /// Charges interest on the outstanding principal for the growth of the global borrow index
+    /// since the position last accrued.
+    fn accrue_borrow_interest(&mut self, borrow_index: u128) -> Result<()> {
+        let interest = index_interest(self.borrowed_amount, borrow_index, self.borrow_index_paid)?;
+        self.borrow_interest = self.borrow_interest.checked_add(interest).ok_or(ErrorCode::Overflow)?;
+        self.borrow_index_paid = borrow_index;
+        Ok(())
+    }
+
+    /// Same as `accrue_borrow_interest`, for the USDC loan and the USDC market's index.
+    fn accrue_usdc_interest(&mut self, borrow_index: u128) -> Result<()> {
+        let interest = index_interest(self.usdc_borrowed, borrow_index, self.usdc_borrow_index_paid)?;
+        self.usdc_borrow_interest = self.usdc_borrow_interest.checked_add(interest).ok_or(ErrorCode::Overflow)?;
+        self.usdc_borrow_index_paid = borrow_index;
+        Ok(())
+    }
+
+    /// Outstanding USDC principal plus unpaid interest.
+    fn usdc_debt(&self) -> Result<u64> {
+        Ok(self.usdc_borrowed.checked_add(self.usdc_borrow_interest).ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Applies a USDC repayment to interest first, then principal, and releases the reserved SST
+    /// in proportion to the debt repaid; returns the interest and principal parts.
+    fn apply_usdc_repayment(&mut self, amount: u64) -> Result<(u64, u64)> {
+        let debt = self.usdc_debt()?;
+        let interest_paid = amount.min(self.usdc_borrow_interest);
+        let principal_paid = amount - interest_paid;
+        self.usdc_borrow_interest -= interest_paid;
+        self.usdc_borrowed = self.usdc_borrowed.checked_sub(principal_paid).ok_or(ErrorCode::Underflow)?;
+        self.usdc_collateral = math::pro_rata_floor(self.usdc_collateral, debt - amount, debt);
+        Ok((interest_paid, principal_paid))
+    }
+
+    /// Whether the position owes anything to either borrow market. Interest is always repaid
+    /// before principal, so open principal is enough to tell.
+    fn has_debt(&self) -> bool {
+        self.borrowed_amount > 0 || self.usdc_borrowed > 0
+    }
+
+    /// Portion of gifted stake that has not vested yet and cannot be unstaked.
+    fn unvested_gift_amount(&self, now: i64) -> u64 {
+        if self.gift_vesting_amount == 0 || now >= self.gift_vesting_end {
+            return 0;
+        }
+        let total = self.gift_vesting_end.saturating_sub(self.gift_vesting_start).max(1) as u64;
+        let remaining = self.gift_vesting_end.saturating_sub(now).max(0) as u64;
+        // Withheld from the user, so rounded up.
+        math::pro_rata_ceil(self.gift_vesting_amount, remaining, total)
+    }
+
+    /// Whether an unstake of `amount` passes the optional two-factor check.
+    fn second_factor_satisfied(&self, amount: u64, co_signer: Option<Pubkey>, now: i64) -> bool {
+        if self.second_signer == Pubkey::default() || amount <= self.two_factor_threshold {
+            return true;
+        }
+        if co_signer == Some(self.second_signer) {
+            return true;
+        }
+        self.two_factor_bypass_at != 0 && now >= self.two_factor_bypass_at
+    }
+
+    /// Pushes the lock and staking-duration clocks forward by incident pauses not yet applied,
+    /// so time spent paused neither matures the lock nor counts toward duration bonuses. Only
+    /// clocks still running when the latest pause began are shifted.
+    fn apply_incident_offset(&mut self, config: &ProtocolConfig) {
+        let delta = config.incident_offset.saturating_sub(self.incident_offset_applied);
+        if delta > 0 {
+            if self.lock_period > 0 && self.locked_until > config.last_incident_started_at {
+                self.locked_until = self.locked_until.saturating_add(delta);
+            }
+            if self.amount > 0 && self.last_staked_time < config.last_incident_started_at {
+                self.last_staked_time = self.last_staked_time.saturating_add(delta);
+            }
+        }
+        self.incident_offset_applied = config.incident_offset;
+    }
+
+    /// Lifecycle state derived from the position's balances, flags and timers. The checks are
+    /// ordered so that the most restrictive condition wins.
+    fn state(&self, now: i64) -> PositionState {
+        if self.amount == 0 && self.pending_withdrawal == 0 {
+            PositionState::Closed
+        } else if self.pending_withdrawal > 0 {
+            PositionState::Frozen
+        } else if self.has_debt() {
+            PositionState::Liquidating
+        } else if self.unstake_at_maturity {
+            PositionState::Cooldown
+        } else if self.lock_period > 0 && now < self.locked_until {
+            PositionState::Locked
+        } else {
+            PositionState::Active
+        }
+    }
+
+    /// Whether the position holds nothing and owes nothing, so closing it loses no value.
+    fn is_empty(&self) -> bool {
+        self.amount == 0
+            && self.accrued_rewards == 0
+            && self.pending_withdrawal == 0
+            && self.pending_stake == 0
+            && self.borrowed_amount == 0
+            && self.borrow_interest == 0
+            && self.usdc_borrowed == 0
+            && self.usdc_borrow_interest == 0
+            && self.usdc_amount == 0
+            && self.lp_deposit == 0
+            && self.locked_lp_deposit == 0
+            && self.delegate == Pubkey::default()
+            && self.delegator_count == 0
+            && self.ve_locked == 0
+            && self.reward_token_accrued.iter().all(|accrued| *accrued == 0)
+    }
+
+    /// Whether `signer` is the position owner or its registered operator.
+    fn is_owner_or_operator(&self, signer: Pubkey) -> bool {
+        signer == self.staker || (self.operator != Pubkey::default() && signer == self.operator)
+    }
+
+    /// Whether `destination` may receive principal withdrawals from this position.
+    fn withdrawal_address_allows(&self, destination: Pubkey) -> bool {
+        self.withdrawal_address == Pubkey::default() || destination == self.withdrawal_address
+    }
+
+    /// Destination of a scheduled withdrawal: the registered withdrawal address when one is set
+    /// (even if it changed after scheduling), else the account chosen at scheduling time.
+    fn scheduled_payout_account(&self) -> Pubkey {
+        if self.withdrawal_address != Pubkey::default() {
+            self.withdrawal_address
+        } else {
+            self.maturity_payout_account
+        }
+    }
+
+    /// Destination of an escrowed payout: the registered withdrawal address when one is set, else
+    /// the frozen account the payout was held for.
+    fn pending_withdrawal_destination(&self) -> Pubkey {
+        if self.withdrawal_address != Pubkey::default() {
+            self.withdrawal_address
+        } else {
+            self.pending_withdrawal_account
+        }
+    }
+
+    /// Whether a token account may receive a deferred payout: the allowlisted address if one
+    /// is registered, otherwise any account owned by the staker.
+    fn payout_account_allowed(&self, destination: Pubkey, destination_owner: Pubkey) -> bool {
+        if self.withdrawal_address != Pubkey::default() {
+            destination == self.withdrawal_address
+        } else {
+            destination_owner == self.staker
+        }
+    }
+
+    /// Whether `destination` may receive claimed rewards. An allowlisted withdrawal address
+    /// takes precedence over the reward beneficiary.
+    fn reward_destination_allowed(&self, destination: Pubkey) -> bool {
+        if self.withdrawal_address != Pubkey::default() {
+            return destination == self.withdrawal_address;
+        }
+        self.reward_beneficiary == Pubkey::default() || destination == self.reward_beneficiary
+    }
+}
+
+/// Per-proposer counter used to derive a fresh proposal PDA for every proposal.
+#[account]
+pub struct ProposerRecord {
+    pub proposer: Pubkey,
+    pub proposal_count: u64,
+}
+
+impl ProposerRecord {
+    const LEN: usize = 32 + 8;
+
+    /// Returns the index for the next proposal and advances the counter.
+    fn take_index(&mut self, proposer: Pubkey) -> Result<u64> {
+        self.proposer = proposer;
+        let index = self.proposal_count;
+        self.proposal_count = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(index)
+    }
+}
+
+#[account]
+pub struct Proposal {
+    pub proposer: Pubkey,
+    /// SHA-256 of the full proposal text, which lives off-chain.
+    pub description_hash: [u8; 32],
+    /// Where the text can be fetched (may be empty).
+    pub description_uri: String,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub created_at: i64,
+    /// Exact config diff for parameter-change proposals (empty for text proposals).
+    pub parameter_changes: Vec<ParameterChange>,
+    /// Position in the proposer's sequence; part of the PDA seeds.
+    pub index: u64,
+    pub voting_ends_at: i64,
+    pub status: ProposalStatus,
+    /// `ProposalInstruction` accounts attached, and how many of them have been executed.
+    pub instruction_count: u8,
+    pub executed_count: u8,
+    /// Whether `parameter_changes` have been written to the config.
+    pub parameters_applied: bool,
+    /// Transfer out of the treasury vault made by `execute_treasury_spend` (treasury-spend proposals only).
+    pub treasury_spend: Option<TreasurySpend>,
+    pub votes_abstain: u64,
+    /// Option labels and per-option tallies of multi-option proposals (empty for yes/no proposals).
+    pub options: Vec<String>,
+    pub option_votes: Vec<u64>,
+    /// Set by `finalize_proposal` when a multi-option proposal passes.
+    pub winning_option: Option<u8>,
+    /// SST bond posted by the proposer, refunded or slashed by `settle_proposal_bond`.
+    pub bond: u64,
+    /// Whether the votes cast met the quorum at finalization.
+    pub quorum_reached: bool,
+    pub bond_settled: bool,
+    /// End of the execution delay; set when the proposal passes.
+    pub executable_at: i64,
+    /// Stake slashed by `slash_stake` (slash proposals only).
+    pub slash: Option<SlashTarget>,
+    /// Fast-tracked by the security council: shorter voting, higher approval threshold.
+    pub emergency: bool,
+}
+
+impl Proposal {
+    const LEN: usize = 32 + 32 + 4 + MAX_PROPOSAL_URI_LEN + 8 + 8 + 8
+        + 4 + MAX_PARAMETER_CHANGES * ParameterChange::LEN + 8 + 8 + 1 + 1 + 1 + 1
+        + 1 + TreasurySpend::LEN + 8
+        + 4 + MAX_PROPOSAL_OPTIONS * (4 + MAX_PROPOSAL_OPTION_LEN) + 4 + MAX_PROPOSAL_OPTIONS * 8 + 1 + 1
+        + 8 + 1 + 1 + 8 + 1 + SlashTarget::LEN + 1;
+
+    /// Resets every field for a new proposal; the caller then fills in its kind-specific payload.
+    fn init(
+        &mut self,
+        proposer: Pubkey,
+        index: u64,
+        description_hash: [u8; 32],
+        description_uri: String,
+        bond: u64,
+        now: i64,
+    ) -> Result<()> {
+        *self = Proposal {
+            proposer,
+            description_hash,
+            description_uri,
+            votes_for: 0,
+            votes_against: 0,
+            created_at: now,
+            parameter_changes: Vec::new(),
+            index,
+            voting_ends_at: now.checked_add(PROPOSAL_VOTING_PERIOD).ok_or(ErrorCode::Overflow)?,
+            status: ProposalStatus::Active,
+            instruction_count: 0,
+            executed_count: 0,
+            parameters_applied: false,
+            treasury_spend: None,
+            votes_abstain: 0,
+            options: Vec::new(),
+            option_votes: Vec::new(),
+            winning_option: None,
+            bond,
+            quorum_reached: false,
+            bond_settled: false,
+            executable_at: 0,
+            slash: None,
+            emergency: false,
+        };
+        Ok(())
+    }
+
+    /// Whether the votes cast (any choice or option) reach the quorum share of `total_staked`.
+    fn quorum_met(&self, governance_config: &GovernanceConfig, total_staked: u64) -> bool {
+        let votes_cast = self.votes_for as u128
+            + self.votes_against as u128
+            + self.votes_abstain as u128
+            + self.option_votes.iter().map(|votes| *votes as u128).sum::<u128>();
+        votes_cast * (BPS_DENOMINATOR as u128) >= total_staked as u128 * governance_config.quorum_bps as u128
+    }
+
+    /// Whether the tallies meet the quorum (votes cast relative to `total_staked`) and the approval threshold.
+    /// Abstentions count toward the quorum only.
+    fn passed(&self, governance_config: &GovernanceConfig, total_staked: u64) -> bool {
+        let votes_for = self.votes_for as u128;
+        let votes_decided = votes_for + self.votes_against as u128;
+        let votes_cast = votes_decided + self.votes_abstain as u128;
+        let bps = BPS_DENOMINATOR as u128;
+        let approval_threshold_bps = if self.emergency {
+            governance_config.approval_threshold_bps.max(EMERGENCY_APPROVAL_THRESHOLD_BPS)
+        } else {
+            governance_config.approval_threshold_bps
+        };
+        votes_cast * bps >= total_staked as u128 * governance_config.quorum_bps as u128
+            && votes_for * bps > votes_decided * approval_threshold_bps as u128
+    }
+
+    /// The option with the most votes, if the votes cast meet the quorum and no other option ties it.
+    fn leading_option(&self, governance_config: &GovernanceConfig, total_staked: u64) -> Option<u8> {
+        let votes_cast: u128 = self.option_votes.iter().map(|votes| *votes as u128).sum();
+        if votes_cast == 0
+            || votes_cast * (BPS_DENOMINATOR as u128) < total_staked as u128 * governance_config.quorum_bps as u128
+        {
+            return None;
+        }
+        let most = *self.option_votes.iter().max()?;
+        let mut leaders = self.option_votes.iter().enumerate().filter(|(_, votes)| **votes == most);
+        match (leaders.next(), leaders.next()) {
+            (Some((option, _)), None) => Some(option as u8),
+            _ => None,
+        }
+    }
+
+    /// Add (or remove) `power` split by `weights_bps` to the option tallies.
+    fn apply_option_votes(&mut self, weights_bps: &[u64], power: u64, add: bool) -> Result<()> {
+        for (tally, weight) in self.option_votes.iter_mut().zip(weights_bps.iter()) {
+            let votes = mul_div_floor(power as u128, *weight as u128, BPS_DENOMINATOR as u128)?;
+            *tally = if add {
+                tally.checked_add(votes).ok_or(ErrorCode::Overflow)?
+            } else {
+                tally.checked_sub(votes).ok_or(ErrorCode::Underflow)?
+            };
+        }
+        Ok(())
+    }
+
+    fn tally_mut(&mut self, choice: VoteChoice) -> &mut u64 {
+        match choice {
+            VoteChoice::For => &mut self.votes_for,
+            VoteChoice::Against => &mut self.votes_against,
+            VoteChoice::Abstain => &mut self.votes_abstain,
+        }
+    }
+
+    fn add_votes(&mut self, choice: VoteChoice, power: u64) -> Result<()> {
+        let tally = self.tally_mut(choice);
+        *tally = tally.checked_add(power).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    fn remove_votes(&mut self, choice: VoteChoice, power: u64) -> Result<()> {
+        let tally = self.tally_mut(choice);
+        *tally = tally.checked_sub(power).ok_or(ErrorCode::Underflow)?;
+        Ok(())
+    }
+
+    /// Whether `now` falls in the execution delay of a passed proposal.
+    fn in_timelock(&self, now: i64) -> bool {
+        self.status == ProposalStatus::Passed && now < self.executable_at
+    }
+
+    fn ensure_executable(&self, now: i64) -> Result<()> {
+        require!(self.status == ProposalStatus::Passed, ErrorCode::ProposalNotPassed);
+        require!(now >= self.executable_at, ErrorCode::ProposalTimelockActive);
+        require!(now < self.expires_at(), ErrorCode::ProposalExpired);
+        Ok(())
+    }
+
+    fn expires_at(&self) -> i64 {
+        self.executable_at.saturating_add(PROPOSAL_EXECUTION_WINDOW)
+    }
+
+    /// Whether every action a passed proposal carries has been carried out.
+    fn fully_executed(&self) -> bool {
+        self.executed_count == self.instruction_count
+            && (self.parameter_changes.is_empty() || self.parameters_applied)
+            && self.treasury_spend.map_or(true, |spend| spend.executed)
+            && self.slash.map_or(true, |slash| slash.executed)
+    }
+}
+
+/// A single voter's ballot on a proposal; its existence prevents double voting.
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub choice: VoteChoice,
+    pub voting_power: u64,
+    /// Delegate that cast this record's power (default = cast by the voter themselves).
+    pub delegate: Pubkey,
+    /// Delegator records cast along with this vote; they follow it on change and withdrawal.
+    pub delegator_count: u8,
+}
+
+impl VoteRecord {
+    const LEN: usize = 32 + 32 + 1 + 8 + 32 + 1;
+}
+
+/// A voter's split across the options of a multi-option proposal.
+#[account]
+pub struct OptionVoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    /// Own power plus the power of delegators cast with it.
+    pub voting_power: u64,
+    pub weights_bps: Vec<u64>,
+    /// Delegate that cast this record's power (default = cast by the voter themselves).
+    pub delegate: Pubkey,
+}
+
+impl OptionVoteRecord {
+    const LEN: usize = 32 + 32 + 8 + 4 + MAX_PROPOSAL_OPTIONS * 8 + 32;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoteChoice {
+    For,
+    Against,
+    /// Counts toward quorum but not toward approval.
+    Abstain,
+}
+
+/// Admin-controlled protocol parameters.
+#[account]
+pub struct ProtocolConfig {
+    pub authority: Pubkey,
+    pub penalty_destination: Pubkey,
+    pub early_unlock_penalty_bps: u64,
+    /// Maximum SST a single wallet may stake (0 = unlimited).
+    pub max_stake_per_wallet: u64,
+    /// Maximum total SST staked protocol-wide (0 = unlimited).
+    pub global_stake_cap: u64,
+    /// Minimum SST accepted per deposit; also the smallest position left behind by an unstake.
+    pub min_stake_amount: u64,
+    /// Minimum SST per partial unstake (full exits are always allowed).
+    pub min_withdrawal_amount: u64,
+    /// Fee-discount curve applied to trades.
+    pub fee_discount_version: u8,
+    /// Curve evaluated alongside the active one and only reported in events (0 = shadow mode off).
+    pub shadow_fee_discount_version: u8,
+    /// When set, claimed rewards go to the staker's vesting escrow instead of being paid out.
+    pub reward_vesting_enabled: bool,
+    /// Share of the still-vesting balance forfeited on an early exit from the escrow.
+    pub reward_vesting_haircut_bps: u64,
+    /// Active vault authority; generation 0 is the original `[b"vault"]` PDA.
+    pub vault_authority: Pubkey,
+    pub vault_generation: u8,
+    pub vault_bump: u8,
+    /// Reward multiplier per lock tier (bps, aligned with `ALLOWED_LOCK_PERIODS`); unlocked stake earns 1x.
+    pub lock_reward_multipliers_bps: [u64; 3],
+    /// Start of the active incident pause (0 = none).
+    pub incident_started_at: i64,
+    /// Start of the most recent incident pause, kept after it is resolved.
+    pub last_incident_started_at: i64,
+    /// Total seconds of resolved incident pauses; positions shift their timers by this lazily.
+    pub incident_offset: i64,
+    /// SST credited when a position flagged as dormant is topped up before closure.
+    pub reengagement_bonus: u64,
+    /// Maximum SST rewards paid out per reward epoch (0 = unlimited); excess claims carry over.
+    pub epoch_reward_cap: u64,
+    /// Share of a referee's claimed rewards credited to the referrer.
+    pub referral_bps: u64,
+    /// Token account (owned by the vault authority) holding credited referral rewards.
+    pub referral_vault: Pubkey,
+    /// Seconds LP tokens stay locked after the latest LP deposit (0 = withdraw any time).
+    pub lp_lockup_period: i64,
+    /// LP boost multiplier per LP lock tier (bps, aligned with `ALLOWED_LOCK_PERIODS`).
+    pub lp_lock_multipliers_bps: [u64; 3],
+    /// Stake (including leased tiers) that unlocks the institutional VIP boost and fee-tier leasing.
+    pub vip_threshold: u64,
+    /// Key allowed to pause the protocol; only governance can unpause or replace it.
+    pub guardian: Pubkey,
+    /// Set by the guardian; blocks the same instructions as an incident pause.
+    pub paused: bool,
+    /// Nominee of a two-step authority transfer (default = none).
+    pub pending_authority: Pubkey,
+    /// Token account (owned by the vault authority) that funds `borrow`.
+    pub borrow_vault: Pubkey,
+    /// Utilization-driven annual borrow rate applied to the global borrow index.
+    pub borrow_rate_curve: BorrowRateCurve,
+    /// Largest loan-to-value (bps) of flexible stake, and of stake still locked in each lock tier
+    /// (aligned with `ALLOWED_LOCK_PERIODS`).
+    pub flexible_collateral_factor_bps: u64,
+    pub lock_collateral_factors_bps: [u64; 3],
+    /// SST/USDC price feed used to value collateral and USD thresholds.
+    pub price_oracle: PriceOracle,
+    /// VIP threshold in USDC base units, valued at the oracle price (0 = use `vip_threshold` in SST).
+    pub vip_threshold_usd: u64,
+    /// Upfront fee on new borrows, paid to the treasury out of the disbursement.
+    pub borrow_origination_fee_bps: u64,
+    /// Share of the origination fee waived for VIP stakers.
+    pub vip_borrow_fee_discount_bps: u64,
+    /// Maximum SST lent out across all positions (0 = unlimited).
+    pub global_borrow_cap: u64,
+    /// Maximum SST principal a single position may owe (0 = unlimited).
+    pub max_borrow_per_wallet: u64,
+    /// Token account holding staked SST principal; fixed once set.
+    pub stake_vault: Pubkey,
+    /// Token account that SST rewards are funded into and paid from.
+    pub reward_vault: Pubkey,
+    /// Mint of `stake_vault`.
+    pub sst_mint: Pubkey,
+    /// Funded SST, held in the reward vault, left for re-engagement bonuses.
+    pub reengagement_budget: u64,
+    /// Token account holding SST locked in the vote escrow; fixed once set.
+    pub ve_vault: Pubkey,
+}
+
+impl ProtocolConfig {
+    const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 32 + 1 + 1 + 8 * 3 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + 8 * 3 + 8 + 32 + 1 + 32 + 32 + BorrowRateCurve::LEN
+        + 8 + 8 * 3 + PriceOracle::LEN + 8 + 8 + 8 + 8 + 8 + 32 * 3 + 8 + 32;
+
+    /// Reward multiplier (bps) for a position's lock period.
+    fn lock_reward_multiplier_bps(&self, lock_period: u64) -> u64 {
+        ALLOWED_LOCK_PERIODS
+            .iter()
+            .position(|period| *period == lock_period)
+            .map(|tier| self.lock_reward_multipliers_bps[tier])
+            .unwrap_or(BPS_DENOMINATOR)
+    }
+
+    /// Largest reward weight (bps) any position can earn at; the par weight at minimum.
+    fn max_lock_reward_multiplier_bps(&self) -> u64 {
+        self.lock_reward_multipliers_bps.iter().copied().fold(BPS_DENOMINATOR, u64::max)
+    }
+
+    /// LP boost multiplier (bps) for an LP lock period.
+    fn lp_lock_multiplier_bps(&self, lock_period: u64) -> u64 {
+        ALLOWED_LOCK_PERIODS
+            .iter()
+            .position(|period| *period == lock_period)
+            .map(|tier| self.lp_lock_multipliers_bps[tier])
+            .unwrap_or(BPS_DENOMINATOR)
+    }
+
+    /// Loan-to-value (bps) a position's stake supports: its lock tier's factor while the lock
+    /// runs, the flexible factor otherwise. Configs created before collateral factors use the default.
+    fn collateral_factor_bps(&self, stake_info: &StakeInfo, now: i64) -> u64 {
+        let factor_bps = if stake_info.lock_period > 0 && now < stake_info.locked_until {
+            ALLOWED_LOCK_PERIODS
+                .iter()
+                .position(|period| *period == stake_info.lock_period)
+                .map(|tier| self.lock_collateral_factors_bps[tier])
+                .unwrap_or(self.flexible_collateral_factor_bps)
+        } else {
+            self.flexible_collateral_factor_bps
+        };
+        if factor_bps == 0 {
+            DEFAULT_COLLATERAL_FACTOR_BPS
+        } else {
+            factor_bps
+        }
+    }
+
+    /// Collateral factor a position is liquidated at: the current one, or the one it borrowed at if
+    /// higher, so neither a lock expiring nor a config change makes an existing loan liquidatable.
+    fn liquidation_factor_bps(&self, stake_info: &StakeInfo, now: i64) -> u64 {
+        self.collateral_factor_bps(stake_info, now).max(stake_info.borrow_collateral_factor_bps)
+    }
+
+    /// Origination fee on borrowing `amount`, reduced for VIP stakers.
+    fn borrow_origination_fee(&self, amount: u64, vip: bool) -> Result<u64> {
+        let fee_bps = if vip {
+            math::pro_rata_floor(
+                self.borrow_origination_fee_bps,
+                BPS_DENOMINATOR - self.vip_borrow_fee_discount_bps,
+                BPS_DENOMINATOR,
+            )
+        } else {
+            self.borrow_origination_fee_bps
+        };
+        mul_div_floor(amount as u128, fee_bps as u128, BPS_DENOMINATOR as u128)
+    }
+
+    /// Signer seeds of the active vault authority.
+    fn vault_signer_seeds(&self) -> Vec<&[u8]> {
+        if self.vault_generation == 0 {
+            vec![b"vault".as_ref(), std::slice::from_ref(&self.vault_bump)]
+        } else {
+            vec![
+                b"vault".as_ref(),
+                std::slice::from_ref(&self.vault_generation),
+                std::slice::from_ref(&self.vault_bump),
+            ]
+        }
+    }
+
+    /// Repoints every vault pinned in the config from a migrated token account to its replacement.
+    fn remap_vault(&mut self, old: Pubkey, new: Pubkey) {
+        for vault in [
+            &mut self.stake_vault,
+            &mut self.reward_vault,
+            &mut self.borrow_vault,
+            &mut self.referral_vault,
+            &mut self.ve_vault,
+        ] {
+            if *vault == old {
+                *vault = new;
+            }
+        }
+    }
+
+    /// Distinct vaults pinned in the config, in address order.
+    fn pinned_vaults(&self) -> Vec<Pubkey> {
+        let mut vaults: Vec<Pubkey> = [self.stake_vault, self.reward_vault, self.borrow_vault, self.referral_vault, self.ve_vault]
+            .into_iter()
+            .filter(|vault| *vault != Pubkey::default())
+            .collect();
+        vaults.sort();
+        vaults.dedup();
+        vaults
+    }
+
+    fn parameter_value(&self, parameter: ConfigParameter) -> u64 {
+        match parameter {
+            ConfigParameter::EarlyUnlockPenaltyBps => self.early_unlock_penalty_bps,
+            ConfigParameter::MaxStakePerWallet => self.max_stake_per_wallet,
+            ConfigParameter::GlobalStakeCap => self.global_stake_cap,
+            ConfigParameter::MinStakeAmount => self.min_stake_amount,
+            ConfigParameter::MinWithdrawalAmount => self.min_withdrawal_amount,
+            ConfigParameter::FeeDiscountVersion => self.fee_discount_version as u64,
+            ConfigParameter::ShadowFeeDiscountVersion => self.shadow_fee_discount_version as u64,
+            ConfigParameter::RewardVestingHaircutBps => self.reward_vesting_haircut_bps,
+            ConfigParameter::VipThreshold => self.vip_threshold,
+            ConfigParameter::LockTier0MultiplierBps => self.lock_reward_multipliers_bps[0],
+            ConfigParameter::LockTier1MultiplierBps => self.lock_reward_multipliers_bps[1],
+            ConfigParameter::LockTier2MultiplierBps => self.lock_reward_multipliers_bps[2],
+        }
+    }
+
+    fn set_parameter(&mut self, parameter: ConfigParameter, value: u64) -> Result<()> {
+        match parameter {
+            ConfigParameter::EarlyUnlockPenaltyBps => {
+                require!(value <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.early_unlock_penalty_bps = value;
+            }
+            ConfigParameter::MaxStakePerWallet => self.max_stake_per_wallet = value,
+            ConfigParameter::GlobalStakeCap => self.global_stake_cap = value,
+            ConfigParameter::MinStakeAmount => self.min_stake_amount = value,
+            ConfigParameter::MinWithdrawalAmount => self.min_withdrawal_amount = value,
+            ConfigParameter::FeeDiscountVersion => {
+                require!(
+                    value >= 1 && value <= LATEST_FEE_DISCOUNT_VERSION as u64,
+                    ErrorCode::InvalidFormulaVersion
+                );
+                self.fee_discount_version = value as u8;
+            }
+            ConfigParameter::ShadowFeeDiscountVersion => {
+                require!(value <= LATEST_FEE_DISCOUNT_VERSION as u64, ErrorCode::InvalidFormulaVersion);
+                self.shadow_fee_discount_version = value as u8;
+            }
+            ConfigParameter::RewardVestingHaircutBps => {
+                require!(value <= BPS_DENOMINATOR, ErrorCode::InvalidBasisPoints);
+                self.reward_vesting_haircut_bps = value;
+            }
+            ConfigParameter::VipThreshold => self.vip_threshold = value,
+            ConfigParameter::LockTier0MultiplierBps
+            | ConfigParameter::LockTier1MultiplierBps
+            | ConfigParameter::LockTier2MultiplierBps => {
+                require!(
+                    value >= BPS_DENOMINATOR && value <= MAX_LOCK_REWARD_MULTIPLIER_BPS,
+                    ErrorCode::InvalidRewardMultiplier
+                );
+                let tier = parameter as usize - ConfigParameter::LockTier0MultiplierBps as usize;
+                self.lock_reward_multipliers_bps[tier] = value;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Members able to veto passed proposals during their execution delay.
+#[account]
+pub struct SecurityCouncil {
+    pub members: Vec<Pubkey>,
+    /// Member approvals needed to veto.
+    pub threshold: u8,
+}
+
+impl SecurityCouncil {
+    const LEN: usize = 4 + MAX_COUNCIL_MEMBERS * 32 + 1;
+}
+
+/// Programs that may be invoked between a flash loan and its repayment.
+#[account]
+pub struct FlashLoanAllowlist {
+    pub programs: Vec<Pubkey>,
+    pub enforced: bool,
+}
+
+impl FlashLoanAllowlist {
+    const LEN: usize = 4 + MAX_FLASH_LOAN_PROGRAMS * 32 + 1;
+}
+
+/// Council approvals towards vetoing one proposal.
+#[account]
+pub struct CouncilVetoRecord {
+    pub proposal: Pubkey,
+    pub approvals: Vec<Pubkey>,
+    /// 0 until the threshold was reached.
+    pub vetoed_at: i64,
+    /// Set when governance overturns the veto.
+    pub overturned: bool,
+}
+
+impl CouncilVetoRecord {
+    const LEN: usize = 32 + 4 + MAX_COUNCIL_MEMBERS * 32 + 8 + 1;
+}
+
+/// Council approvals towards fast-tracking one proposal as an emergency.
+#[account]
+pub struct CouncilFastTrackRecord {
+    pub proposal: Pubkey,
+    pub approvals: Vec<Pubkey>,
+}
+
+impl CouncilFastTrackRecord {
+    const LEN: usize = 32 + 4 + MAX_COUNCIL_MEMBERS * 32;
+}
+
+/// Protocol treasury: a vault-authority-owned token account spent only by passed proposals.
+#[account]
+pub struct Treasury {
+    pub vault: Pubkey,
+    /// Maximum spent per reward epoch across all treasury-spend proposals.
+    pub epoch_spend_limit: u64,
+    pub spend_epoch: u64,
+    pub epoch_spent: u64,
+}
+
+impl Treasury {
+    const LEN: usize = 32 + 8 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SlashTarget {
+    pub staker: Pubkey,
+    pub slash_percentage: u64,
+    pub executed: bool,
+}
+
+impl SlashTarget {
+    const LEN: usize = 32 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TreasurySpend {
+    /// Token account paid from the treasury vault.
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub executed: bool,
+}
+
+impl TreasurySpend {
+    const LEN: usize = 32 + 8 + 1;
+}
+
+/// Instruction carried by a proposal and CPI'd by `execute_proposal`.
+#[account]
+pub struct ProposalInstruction {
+    pub proposal: Pubkey,
+    pub index: u8,
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+    /// 0 until executed.
+    pub executed_at: i64,
+}
+
+impl ProposalInstruction {
+    const LEN: usize = 32 + 1 + 32 + 4 + MAX_PROPOSAL_INSTRUCTION_ACCOUNTS * ProposalAccountMeta::LEN
+        + 4 + MAX_PROPOSAL_INSTRUCTION_DATA + 8;
+}
+
+/// Serializable `AccountMeta`; the governance signer PDA may be marked as a signer.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccountMeta {
+    const LEN: usize = 32 + 1 + 1;
+}
+
+/// Numeric config parameters that governance proposals can change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigParameter {
+    EarlyUnlockPenaltyBps,
+    MaxStakePerWallet,
+    GlobalStakeCap,
+    MinStakeAmount,
+    MinWithdrawalAmount,
+    FeeDiscountVersion,
+    ShadowFeeDiscountVersion,
+    RewardVestingHaircutBps,
+    VipThreshold,
+    /// Reward multipliers (bps) of the three lock tiers in `ALLOWED_LOCK_PERIODS`.
+    LockTier0MultiplierBps,
+    LockTier1MultiplierBps,
+    LockTier2MultiplierBps,
+}
+
+/// One before/after entry of a parameter-change proposal.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ParameterChange {
+    pub parameter: ConfigParameter,
+    pub old_value: u64,
+    pub new_value: u64,
+}
+
+impl ParameterChange {
+    const LEN: usize = 1 + 8 + 8;
+}
+
+/// Active and shadow fee discounts for a trade, emitted while shadow mode is on.
+#[event]
+pub struct FeeDiscountComputed {
+    pub staker: Pubkey,
+    pub active_version: u8,
+    pub active_discount: u64,
+    pub shadow_version: u8,
+    pub shadow_discount: u64,
+}
+
+/// Partial update for `ProtocolConfig`; `None` fields are left unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ConfigUpdate {
+    pub penalty_destination: Option<Pubkey>,
+    pub early_unlock_penalty_bps: Option<u64>,
+    pub max_stake_per_wallet: Option<u64>,
+    pub global_stake_cap: Option<u64>,
+    pub min_stake_amount: Option<u64>,
+    pub min_withdrawal_amount: Option<u64>,
+    pub fee_discount_version: Option<u8>,
+    pub shadow_fee_discount_version: Option<u8>,
+    pub reward_vesting_enabled: Option<bool>,
+    pub reward_vesting_haircut_bps: Option<u64>,
+    pub lock_reward_multipliers_bps: Option<[u64; 3]>,
+    pub reengagement_bonus: Option<u64>,
+    pub epoch_reward_cap: Option<u64>,
+    pub referral_bps: Option<u64>,
+    pub referral_vault: Option<Pubkey>,
+    pub borrow_vault: Option<Pubkey>,
+    pub borrow_rate_curve: Option<BorrowRateCurve>,
+    pub lp_lockup_period: Option<i64>,
+    pub lp_lock_multipliers_bps: Option<[u64; 3]>,
+    pub flexible_collateral_factor_bps: Option<u64>,
+    pub lock_collateral_factors_bps: Option<[u64; 3]>,
+    pub price_oracle: Option<PriceOracle>,
+    pub vip_threshold_usd: Option<u64>,
+    pub borrow_origination_fee_bps: Option<u64>,
+    pub vip_borrow_fee_discount_bps: Option<u64>,
+    pub global_borrow_cap: Option<u64>,
+    pub max_borrow_per_wallet: Option<u64>,
+}
+
+/// A staker's withdrawal ladder: one deposit split into tranches with staggered lock expiries.
+#[account]
+pub struct LockLadder {
+    pub staker: Pubkey,
+    pub tranches: Vec<LadderTranche>,
+}
+
+impl LockLadder {
+    const LEN: usize = 32 + 4 + MAX_LADDER_TRANCHES * LadderTranche::LEN;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LadderTranche {
+    pub amount: u64,
+    pub lock_period: u64,
+    pub locked_until: i64,
+    pub withdrawn: bool,
+}
+
+impl LadderTranche {
+    const LEN: usize = 8 + 8 + 8 + 1;
+}
+
+/// Per-position unlock index entry. `unlock_day` sits directly after the discriminator so
+/// services can memcmp-filter beacons for a given day.
+#[account]
+pub struct UnlockBeacon {
+    pub unlock_day: u64,
+    pub unlock_at: i64,
+    pub staker: Pubkey,
+    pub stake_info: Pubkey,
+}
+
+impl UnlockBeacon {
+    const LEN: usize = 8 + 8 + 32 + 32;
+}
+
+/// Lease of a VIP staker's fee tier to another wallet for a fixed term.
+#[account]
+pub struct FeeTierLease {
+    pub lessor: Pubkey,
+    pub lessee: Pubkey,
+    pub fee: u64,
+    pub duration: i64,
+    pub starts_at: i64,
+    pub expires_at: i64,
+    pub accepted: bool,
+}
+
+impl FeeTierLease {
+    const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    fn is_active(&self, now: i64) -> bool {
+        self.accepted && now < self.expires_at
+    }
+}
+
+/// Instruction input for a single proposed parameter change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ParameterChangeRequest {
+    pub parameter: ConfigParameter,
+    pub new_value: u64,
+}
+
+/// Governance settings, including the policy every proposal description must satisfy.
+#[account]
+pub struct GovernanceConfig {
+    pub authority: Pubkey,
+    pub description_policy: DescriptionPolicy,
+    /// Minimum votes cast at finalization, in bps of total staked.
+    pub quorum_bps: u64,
+    /// Share of votes cast that must be in favour, in bps; a proposal passes only above it.
+    pub approval_threshold_bps: u64,
+    /// SST bond required to create a proposal (0 = none) and the vault-authority-owned account holding bonds.
+    pub proposal_bond: u64,
+    pub bond_vault: Pubkey,
+    /// SST a proposer must have staked to create a proposal (0 = anyone).
+    pub min_proposer_stake: u64,
+    /// Seconds between a proposal passing and becoming executable.
+    pub execution_delay: i64,
+    /// Nominee of a two-step authority transfer (default = none).
+    pub pending_authority: Pubkey,
+}
+
+impl GovernanceConfig {
+    const LEN: usize = 32 + DescriptionPolicy::LEN + 8 + 8 + 8 + 32 + 8 + 8 + 32;
+}
+
+/// Rules applied to proposal description URIs so oversized or malformed links never land on-chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct DescriptionPolicy {
+    /// Maximum URI length in bytes (at most `MAX_PROPOSAL_URI_LEN`).
+    pub max_uri_len: u16,
+    /// Require the URI to be a `<forum_uri_prefix><post hash>` reference.
+    pub require_forum_uri: bool,
+    pub forum_uri_prefix: String,
+    /// Whether URIs other than forum references are accepted.
+    pub allow_external_urls: bool,
+    /// Case-insensitive terms that may not appear in a URI.
+    pub banned_terms: Vec<String>,
+}
+
+impl DescriptionPolicy {
+    const LEN: usize = 2 + 1 + 4 + MAX_FORUM_URI_PREFIX_LEN + 1 + 4 + MAX_BANNED_TERMS * (4 + MAX_BANNED_TERM_LEN);
+
+    /// Rejects policies that would not fit the account or could never be satisfied.
+    fn check_bounds(&self) -> Result<()> {
+        require!(
+            self.max_uri_len > 0 && self.max_uri_len as usize <= MAX_PROPOSAL_URI_LEN,
+            ErrorCode::InvalidDescriptionPolicy
+        );
+        require!(self.forum_uri_prefix.len() <= MAX_FORUM_URI_PREFIX_LEN, ErrorCode::InvalidDescriptionPolicy);
+        require!(!self.require_forum_uri || !self.forum_uri_prefix.is_empty(), ErrorCode::InvalidDescriptionPolicy);
+        require!(self.banned_terms.len() <= MAX_BANNED_TERMS, ErrorCode::InvalidDescriptionPolicy);
+        require!(
+            self.banned_terms.iter().all(|term| !term.is_empty() && term.len() <= MAX_BANNED_TERM_LEN),
+            ErrorCode::InvalidDescriptionPolicy
+        );
+        Ok(())
+    }
+
+    /// Validates a proposal's description hash and URI. Borsh already guarantees the URI is valid UTF-8.
+    fn validate(&self, description_hash: &[u8; 32], uri: &str) -> Result<()> {
+        require!(*description_hash != [0u8; 32], ErrorCode::InvalidDescription);
+        if uri.is_empty() {
+            require!(!self.require_forum_uri, ErrorCode::MissingForumReference);
+            return Ok(());
+        }
+        require!(
+            uri.len() <= (self.max_uri_len as usize).min(MAX_PROPOSAL_URI_LEN),
+            ErrorCode::DescriptionTooLong
+        );
+        require!(
+            !uri.chars().any(|c| c.is_control() || c.is_whitespace()),
+            ErrorCode::InvalidDescription
+        );
+
+        let lowered = uri.to_lowercase();
+        require!(
+            !self.banned_terms.iter().any(|term| lowered.contains(&term.to_lowercase())),
+            ErrorCode::DescriptionPolicyViolation
+        );
+        if !self.is_forum_uri(uri) {
+            require!(!self.require_forum_uri, ErrorCode::MissingForumReference);
+            require!(self.allow_external_urls, ErrorCode::DescriptionPolicyViolation);
+        }
+        Ok(())
+    }
+
+    /// A forum reference is the configured prefix followed by a hex-encoded 32-byte post hash.
+    fn is_forum_uri(&self, word: &str) -> bool {
+        if self.forum_uri_prefix.is_empty() {
+            return false;
+        }
+        match word.strip_prefix(self.forum_uri_prefix.as_str()) {
+            Some(hash) => hash.len() == FORUM_POST_HASH_LEN && hash.chars().all(|c| c.is_ascii_hexdigit()),
+            None => false,
+        }
+    }
+}
+
+/// Immutable per-epoch emission schedule with geometric decay down to a floor.
+#[account]
+pub struct EpochEmissionSchedule {
+    /// Tokens emitted in `start_epoch`.
+    pub initial_rate: u64,
+    /// Fraction of the previous epoch's emission removed each epoch, in basis points.
+    pub decay_bps: u64,
+    /// Emission never decays below this rate.
+    pub floor_rate: u64,
+    pub start_epoch: u64,
+}
+
+impl EpochEmissionSchedule {
+    const LEN: usize = 8 + 8 + 8 + 8;
+
+    /// Scheduled emission for `epoch` (zero before the schedule starts).
+    fn emission_for_epoch(&self, epoch: u64) -> Result<u64> {
+        if epoch < self.start_epoch {
+            return Ok(0);
+        }
+        calculate_epoch_emission(self.initial_rate, self.decay_bps, self.floor_rate, epoch - self.start_epoch)
+    }
+}
+
+/// Program IDs positions may migrate to (export) and from (import).
+#[account]
+pub struct MigrationGate {
+    pub target_program: Pubkey,
+    pub source_program: Pubkey,
+}
+
+impl MigrationGate {
+    const LEN: usize = 32 + 32;
+
+    /// Vault PDA of the target program, which must own the receiving token account.
+    fn target_vault_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"vault"], &self.target_program).0
+    }
+}
+
+/// Proof of an exported position: the raw `StakeInfo` bytes and their SHA-256 hash.
+#[account]
+pub struct PositionExport {
+    pub staker: Pubkey,
+    pub source_program: Pubkey,
+    pub target_program: Pubkey,
+    pub exported_at: i64,
+    pub state_hash: [u8; 32],
+    pub state: Vec<u8>,
+}
+
+impl PositionExport {
+    const LEN: usize = 32 + 32 + 32 + 8 + 32 + 4 + StakeInfo::LEN;
+}
+
+/// Receipt for an imported export proof.
+#[account]
+pub struct PositionImport {
+    pub position_export: Pubkey,
+    pub imported_at: i64,
+}
+
+impl PositionImport {
+    const LEN: usize = 32 + 8;
+}
+
+/// Per-wallet registry of position PDAs, so holdings can be listed without `getProgramAccounts`.
+#[account]
+pub struct PositionIndex {
+    pub owner: Pubkey,
+    pub positions: Vec<IndexedPosition>,
+    pub updated_at: i64,
+}
+
+impl PositionIndex {
+    const LEN: usize = 32 + 4 + MAX_INDEXED_POSITIONS * IndexedPosition::LEN + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct IndexedPosition {
+    pub kind: PositionKind,
+    pub address: Pubkey,
+}
+
+impl IndexedPosition {
+    const LEN: usize = 1 + 32;
+}
+
+/// Kinds of per-wallet position accounts tracked by the position index.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionKind {
+    Stake,
+    LockLadder,
+    FeeTierLease,
+}
+
+impl PositionKind {
+    fn from_discriminator(data: &[u8]) -> Option<Self> {
+        let discriminator = data.get(..8)?;
+        if discriminator == StakeInfo::DISCRIMINATOR {
+            Some(PositionKind::Stake)
+        } else if discriminator == LockLadder::DISCRIMINATOR {
+            Some(PositionKind::LockLadder)
+        } else if discriminator == FeeTierLease::DISCRIMINATOR {
+            Some(PositionKind::FeeTierLease)
+        } else {
+            None
+        }
+    }
+
+    /// PDA seed prefix; the second seed is always the owner's wallet.
+    fn seed(&self) -> &'static [u8] {
+        match self {
+            PositionKind::Stake => b"stake",
+            PositionKind::LockLadder => b"ladder",
+            PositionKind::FeeTierLease => b"fee_lease",
+        }
+    }
+}
+
+/// Reward emission budget: a per-second rate between `start_time` and `end_time` (0 = open-ended),
+/// optionally stepping down at fixed timestamps.
+#[account]
+pub struct EmissionSchedule {
+    pub tokens_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub step_downs: Vec<EmissionStep>,
+}
+
+impl EmissionSchedule {
+    const LEN: usize = 8 + 8 + 8 + 4 + MAX_EMISSION_STEPS * EmissionStep::LEN;
+
+    fn apply(&mut self, params: EmissionScheduleParams) -> Result<()> {
+        require!(
+            params.end_time == 0 || params.end_time > params.start_time,
+            ErrorCode::InvalidEmissionSchedule
+        );
+        require!(params.step_downs.len() <= MAX_EMISSION_STEPS, ErrorCode::InvalidEmissionSchedule);
+        let mut previous_at = params.start_time;
+        let mut previous_rate = params.tokens_per_second;
+        for step in params.step_downs.iter() {
+            require!(
+                step.at > previous_at && step.tokens_per_second <= previous_rate,
+                ErrorCode::InvalidEmissionSchedule
+            );
+            previous_at = step.at;
+            previous_rate = step.tokens_per_second;
+        }
+        self.tokens_per_second = params.tokens_per_second;
+        self.start_time = params.start_time;
+        self.end_time = params.end_time;
+        self.step_downs = params.step_downs;
+        Ok(())
+    }
+
+    /// Total tokens emitted over `[from, to)`.
+    fn emitted_between(&self, from: i64, to: i64) -> Result<u128> {
+        let to = if self.end_time > 0 { to.min(self.end_time) } else { to };
+        let mut total: u128 = 0;
+        let mut segment_start = self.start_time;
+        let mut rate = self.tokens_per_second;
+        for step in self.step_downs.iter() {
+            total = total
+                .checked_add(segment_overlap(segment_start, step.at, from, to) * rate as u128)
+                .ok_or(ErrorCode::Overflow)?;
+            segment_start = step.at;
+            rate = step.tokens_per_second;
+        }
+        let total = total
+            .checked_add(segment_overlap(segment_start, i64::MAX, from, to) * rate as u128)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(total)
+    }
+}
+
+/// Rate change of an emission schedule, effective from `at`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct EmissionStep {
+    pub at: i64,
+    pub tokens_per_second: u64,
+}
+
+impl EmissionStep {
+    const LEN: usize = 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EmissionScheduleParams {
+    pub tokens_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub step_downs: Vec<EmissionStep>,
+}
+
+/// Periodic stake-weighted lottery funded by a share of early-unstake penalties.
+#[account]
+pub struct Lottery {
+    pub round: u64,
+    pub status: LotteryStatus,
+    pub round_duration: i64,
+    pub draw_at: i64,
+    /// Sum of entry weights in the current round; tickets cover consecutive weight ranges.
+    pub total_weight: u128,
+    pub penalty_share_bps: u64,
+    pub vault: Pubkey,
+    /// Switchboard randomness account committed for the current draw.
+    pub randomness_account: Pubkey,
+    pub winning_weight: u128,
+    pub prize: u64,
+    /// Last moment the settled prize can be claimed; afterwards it rolls over.
+    pub claim_deadline: i64,
+}
+
+impl Lottery {
+    const LEN: usize = 8 + 1 + 8 + 8 + 16 + 8 + 32 + 32 + 16 + 8 + 8;
+
+    fn start_next_round(&mut self, now: i64) -> Result<()> {
+        self.round = self.round.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        self.status = LotteryStatus::Open;
+        self.draw_at = now.checked_add(self.round_duration).ok_or(ErrorCode::Overflow)?;
+        self.total_weight = 0;
+        self.randomness_account = Pubkey::default();
+        self.winning_weight = 0;
+        self.prize = 0;
+        self.claim_deadline = 0;
+        Ok(())
+    }
+}
+
+/// Proposal lifecycle; `finalize_proposal` moves it to `Passed` or `Failed`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProposalStatus {
+    Active,
+    Passed,
+    Failed,
+    /// Passed, then vetoed by the governance config authority during the execution delay.
+    Vetoed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LotteryStatus {
+    Open,
+    Drawing,
+    Settled,
+}
+
+/// A staker's entry in one lottery round, covering `[range_start, range_start + weight)`.
+#[account]
+pub struct LotteryTicket {
+    pub round: u64,
+    pub staker: Pubkey,
+    pub range_start: u128,
+    pub weight: u128,
+    /// Stake held at entry; the position must still hold it to claim a prize.
+    pub amount: u64,
+}
+
+impl LotteryTicket {
+    const LEN: usize = 8 + 32 + 16 + 16 + 8;
+}
+
+#[event]
+pub struct VeLockUpdated {
+    pub staker: Pubkey,
+    pub locked: u64,
+    pub unlock_at: i64,
+    pub ve_balance: u64,
+}
+
+#[event]
+pub struct VotesDelegated {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct VotesUndelegated {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct ProposalVetoedByCouncil {
+    pub proposal: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalFastTracked {
+    pub proposal: Pubkey,
+    pub approvals: u8,
+    pub voting_ends_at: i64,
+}
+
+#[event]
+pub struct ProposalFinalized {
+    pub proposal: Pubkey,
+    pub status: ProposalStatus,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+}
+
+/// A position reached a milestone; `achievement` is the bit index in `StakeInfo::achievements`.
+#[event]
+pub struct AchievementUnlocked {
+    pub staker: Pubkey,
+    pub achievement: u8,
+    pub unlocked_at: i64,
+}
+
+/// The reward vault was topped up.
+#[event]
+pub struct RewardsFunded {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub rewards_outstanding: u64,
+}
+
+/// Budget for a marketing or compensation campaign, credited to positions by its distributor.
+#[account]
+pub struct BonusCampaign {
+    pub campaign_id: u64,
+    pub distributor: Pubkey,
+    pub budget: u64,
+    pub distributed: u64,
+    pub created_at: i64,
+}
+
+impl BonusCampaign {
+    const LEN: usize = 8 + 32 + 8 + 8 + 8;
+}
+
+/// Breakdown of one reward settlement, so stakers can audit how their accrual was computed.
+#[event]
+pub struct RewardsSettled {
+    pub staker: Pubkey,
+    /// SST emission earned since the last settlement, before the lock-tier multiplier.
+    pub base_reward: u64,
+    pub lock_multiplier_bps: u64,
+    /// Part of the credited SST owed to the lock-tier (duration) multiplier.
+    pub lock_bonus: u64,
+    /// Emission below the top lock weight, returned to the pool for all stakers.
+    pub redistributed: u64,
+    /// Partner reward tokens earned, in registry order.
+    pub partner_rewards: [u64; MAX_REWARD_TOKENS],
+    /// Accrued, unclaimed SST after this settlement.
+    pub accrued_rewards: u64,
+    pub reward_per_token: u128,
+    pub timestamp: i64,
+}
+
+/// Breakdown of a reward claim as paid out (after payout caps): settled base rewards plus the LP
+/// boost.
+#[event]
+pub struct RewardsClaimed {
+    pub staker: Pubkey,
+    pub base_reward: u64,
+    pub lp_boost: u64,
+    pub total_reward: u64,
+    /// Whether the reward was restaked instead of paid out.
+    pub compounded: bool,
+}
+
+/// A boost position was settled against its campaign.
+#[event]
+pub struct BoostRewardsSettled {
+    pub campaign: Pubkey,
+    pub staker: Pubkey,
+    /// Weight the campaign reward was earned on.
+    pub weight: u64,
+    pub campaign_boost: u64,
+    pub accrued: u64,
+    pub timestamp: i64,
+}
+
+/// Early-unstake penalties were added to the reward accumulator.
+#[event]
+pub struct PenaltiesRedistributed {
+    pub amount: u64,
+    /// Stake the penalties were shared across.
+    pub earning_stake: u64,
+    pub reward_per_token: u128,
+}
+
+/// An empty, inactive position was flagged and may be closed after the notice period.
+#[event]
+pub struct DormantPositionFlagged {
+    pub staker: Pubkey,
+    pub last_activity_at: i64,
+    pub closable_at: i64,
+}
+
+/// A dormant position was closed and its rent returned to the staker.
+#[event]
+pub struct DormantPositionClosed {
+    pub staker: Pubkey,
+}
+
+/// A flagged dormant staker re-engaged with a deposit.
+#[event]
+pub struct ReengagementBonusGranted {
+    pub staker: Pubkey,
+    pub bonus: u64,
+}
+
+/// Protocol revenue was credited to stakers through a reward-token slot.
+#[event]
+pub struct RevenueDeposited {
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub reward_per_token: u128,
+}
+
+/// A position moved between lifecycle states.
+#[event]
+pub struct PositionStateChanged {
+    pub staker: Pubkey,
+    pub from: PositionState,
+    pub to: PositionState,
+}
+
+/// A claim was limited by the epoch reward cap or the reward vault balance.
+#[event]
+pub struct RewardsCarriedOver {
+    pub staker: Pubkey,
+    pub paid: u64,
+    /// Left accrued on the position for a later claim.
+    pub carried: u64,
+}
+
+/// A campaign bonus was credited to a position.
+#[event]
+pub struct BonusDistributed {
+    pub campaign_id: u64,
+    pub staker: Pubkey,
+    pub amount: u64,
+}
+
+/// Additional reward mint paid alongside SST, with its own vault and emission rate.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RewardToken {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub tokens_per_second: u64,
+    /// Cumulative rewards per staked token, scaled by `math::REWARD_SCALE`.
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    /// Paused tokens stop emitting; already accrued amounts remain claimable.
+    pub active: bool,
+    /// Reward remainders lost to rounding, scaled by `math::REWARD_SCALE`.
+    pub reward_dust: u128,
+}
+
+impl RewardToken {
+    const LEN: usize = 32 + 32 + 8 + 16 + 8 + 1 + 16;
+
+    /// Advances this token's accumulator to `now`, spreading its emission over `total_staked`.
+    fn update_reward_per_token(&mut self, total_staked: u64, now: i64) -> Result<()> {
+        if now <= self.last_update_time {
+            return Ok(());
+        }
+        if self.active && total_staked > 0 {
+            let elapsed = (now - self.last_update_time) as u128;
+            let emitted = math::to_reward_scale(
+                (self.tokens_per_second as u128).checked_mul(elapsed).ok_or(ErrorCode::Overflow)?,
+            )?;
+            let (increment, dust) = sweep_reward_dust(emitted, self.reward_dust, total_staked)?;
+            self.reward_per_token_stored = self.reward_per_token_stored
+                .checked_add(increment)
+                .ok_or(ErrorCode::Overflow)?;
+            self.reward_dust = dust;
+        }
+        self.last_update_time = now;
+        Ok(())
+    }
+
+    /// Credits a one-off deposit of `amount` to `total_staked` at once (the accumulator must
+    /// already be up to date).
+    fn distribute(&mut self, amount: u64, total_staked: u64) -> Result<()> {
+        let scaled = math::to_reward_scale(amount as u128)?;
+        let (increment, dust) = sweep_reward_dust(scaled, self.reward_dust, total_staked)?;
+        self.reward_per_token_stored = self.reward_per_token_stored
+            .checked_add(increment)
+            .ok_or(ErrorCode::Overflow)?;
+        self.reward_dust = dust;
+        Ok(())
+    }
+}
+
+/// Registry of partner reward tokens; slot `i` matches `StakeInfo::reward_token_paid[i]`.
+#[account]
+pub struct RewardTokenRegistry {
+    pub tokens: Vec<RewardToken>,
+}
+
+impl RewardTokenRegistry {
+    const LEN: usize = 4 + RewardToken::LEN * MAX_REWARD_TOKENS;
+
+    /// Advances every registered token's accumulator to `now`.
+    fn update(&mut self, total_staked: u64, now: i64) -> Result<()> {
+        for token in self.tokens.iter_mut() {
+            token.update_reward_per_token(total_staked, now)?;
+        }
+        Ok(())
+    }
+}
+
+/// Return data of `get_pending_rewards`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PendingRewards {
+    pub staker: Pubkey,
+    pub base_reward: u64,
+    pub lp_boost: u64,
+    pub total_reward: u64,
+    pub partner_rewards: Vec<PendingTokenReward>,
+}
+
+/// Claimable amount of one partner reward token.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PendingTokenReward {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Escrow holding claimed rewards while they vest linearly over `REWARD_VESTING_PERIOD`, each
+/// claim in its own tranche. Tokens stay in the reward vault; this account only tracks the
+/// staker's entitlement.
+#[account]
+pub struct RewardVesting {
+    pub staker: Pubkey,
+    /// Vested and not yet withdrawn.
+    pub vested: u64,
+    /// Still vesting, across all tranches.
+    pub vesting: u64,
+    /// Earliest start and latest end of the open tranches.
+    pub start: i64,
+    pub end: i64,
+    /// Portion of `ProtocolConfig::incident_offset` already applied to the tranches.
+    pub incident_offset_applied: i64,
+    pub tranches: [VestingTranche; REWARD_VESTING_TRANCHES],
+}
+
+impl RewardVesting {
+    const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + VestingTranche::LEN * REWARD_VESTING_TRANCHES;
+
+    /// Pushes unfinished tranches forward by incident pauses not yet applied.
+    fn apply_incident_offset(&mut self, config: &ProtocolConfig) {
+        let delta = config.incident_offset.saturating_sub(self.incident_offset_applied);
+        if delta > 0 {
+            for tranche in self.tranches.iter_mut() {
+                if tranche.amount > 0 && tranche.end > config.last_incident_started_at {
+                    tranche.start = tranche.start.saturating_add(delta);
+                    tranche.end = tranche.end.saturating_add(delta);
+                }
+            }
+            self.refresh_window();
+        }
+        self.incident_offset_applied = config.incident_offset;
+    }
+
+    /// Moves whatever vested by `now` from each tranche into `vested`; the rest keeps vesting.
+    fn checkpoint(&mut self, now: i64) -> Result<()> {
+        for tranche in self.tranches.iter_mut() {
+            let released = tranche.release(now);
+            self.vested = self.vested.checked_add(released).ok_or(ErrorCode::Overflow)?;
+            self.vesting = self.vesting.checked_sub(released).ok_or(ErrorCode::Underflow)?;
+        }
+        self.refresh_window();
+        Ok(())
+    }
+
+    /// Adds freshly claimed rewards as a new tranche vesting over a full period, leaving earlier
+    /// tranches on their own schedule. With every tranche in use, the newest one absorbs the
+    /// deposit and its end moves to the amount-weighted average of the two.
+    fn deposit(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.checkpoint(now)?;
+        let end = now.checked_add(REWARD_VESTING_PERIOD).ok_or(ErrorCode::Overflow)?;
+        if let Some(tranche) = self.tranches.iter_mut().find(|tranche| tranche.amount == 0) {
+            *tranche = VestingTranche { amount, start: now, end };
+        } else {
+            let newest = (0..REWARD_VESTING_TRANCHES).max_by_key(|&slot| self.tranches[slot].end).unwrap_or(0);
+            let tranche = &mut self.tranches[newest];
+            let merged = tranche.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+            let shift = math::pro_rata_floor(end.saturating_sub(tranche.end).max(0) as u64, amount, merged);
+            tranche.end = tranche.end.checked_add(shift as i64).ok_or(ErrorCode::Overflow)?;
+            tranche.start = tranche.start.max(now);
+            tranche.amount = merged;
+        }
+        self.vesting = self.vesting.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        self.refresh_window();
+        Ok(())
+    }
+
+    fn refresh_window(&mut self) {
+        let open = || self.tranches.iter().filter(|tranche| tranche.amount > 0);
+        if let (Some(start), Some(end)) = (open().map(|tranche| tranche.start).min(), open().map(|tranche| tranche.end).max()) {
+            self.start = start;
+            self.end = end;
+        }
+    }
+}
+
+/// Claimed rewards vesting linearly from `start` until `end`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct VestingTranche {
+    pub amount: u64,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl VestingTranche {
+    const LEN: usize = 8 + 8 + 8;
+
+    /// Releases and returns what vested since `start`; the tranche then vests the rest from `now`.
+    fn release(&mut self, now: i64) -> u64 {
+        if self.amount == 0 || now <= self.start {
+            return 0;
+        }
+        let released = if now >= self.end {
+            self.amount
+        } else {
+            math::pro_rata_floor(self.amount, (now - self.start) as u64, (self.end - self.start) as u64)
+        };
+        self.amount -= released;
+        self.start = now;
+        if self.amount == 0 {
+            *self = VestingTranche::default();
+        }
+        released
+    }
+}
+
+/// In-progress rotation of the vault authority to a new PDA generation.
+#[account]
+pub struct VaultRotation {
+    pub generation: u8,
+    pub new_vault_authority: Pubkey,
+    pub new_vault_bump: u8,
+    pub accounts_migrated: u64,
+    pub started_at: i64,
+}
+
+impl VaultRotation {
+    const LEN: usize = 1 + 32 + 1 + 8 + 8;
+}
+
+/// Operation whose account set `validate_accounts` checks.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StakeOperation {
+    Stake,
+    Unstake,
+}
+
+/// Return data entry of `validate_accounts`: the Anchor error code for one account (0 = valid).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AccountCheck {
+    pub address: Pubkey,
+    pub error_code: u32,
+}
+
+/// Deposit tally for one integration code.
+#[account]
+pub struct IntegrationStats {
+    pub code: [u8; 16],
+    pub registrant: Pubkey,
+    pub deposit_count: u64,
+    pub total_deposited: u64,
+    pub last_deposit_at: i64,
+}
+
+impl IntegrationStats {
+    const LEN: usize = 16 + 32 + 8 + 8 + 8;
+}
+
+/// A deposit was attributed to an integration code.
+#[event]
+pub struct IntegrationDeposit {
+    pub code: [u8; 16],
+    pub staker: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+/// Position lifecycle state (see `StakeInfo::state`).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionState {
+    /// Holding stake with no lock, loan, schedule or escrow.
+    Active,
+    /// Holding stake inside an unexpired lock.
+    Locked,
+    /// A scheduled withdrawal is waiting for a keeper.
+    Cooldown,
+    /// A payout is escrowed because the destination token account was frozen.
+    Frozen,
+    /// Stake backs an outstanding borrow and may be slashed.
+    Liquidating,
+    /// Nothing staked or escrowed.
+    Closed,
 }
 
-#[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+impl PositionState {
+    /// Allowed lifecycle transitions; staying in the same state is always allowed.
+    fn can_transition_to(self, next: PositionState) -> bool {
+        use PositionState::*;
+        match (self, next) {
+            (from, to) if from == to => true,
+            (Closed, Active | Locked) => true,
+            (Active | Locked, _) => true,
+            (Cooldown, Active | Locked | Frozen | Closed) => true,
+            (Liquidating, Active | Locked | Closed) => true,
+            (Frozen, Active | Locked | Closed) => true,
+            _ => false,
+        }
+    }
+}
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+/// USDC debt market lending from a vault-authority-owned USDC account against staked SST.
+#[account]
+pub struct UsdcMarket {
+    pub usdc_vault: Pubkey,
+    pub params: UsdcMarketParams,
+    /// Outstanding USDC principal across all positions.
+    pub total_borrowed: u64,
+    /// Cumulative borrow interest factor, in Q64.64 (starts at `BORROW_INDEX_ONE`).
+    pub borrow_index: u128,
+    pub borrow_index_updated_at: i64,
+}
 
-    #[account(mut)]
-    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+impl UsdcMarket {
+    const LEN: usize = 32 + UsdcMarketParams::LEN + 8 + 16 + 8;
 
-    #[account(mut)]
-    pub reward_vault: Box<Account<'info, TokenAccount>>,
+    /// Grows the USDC borrow index to `now` (`available` USDC left in the vault).
+    fn accrue_borrow_index(&mut self, available: u64, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.borrow_index_updated_at);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+        self.borrow_index = self.params.rate_curve.grow_index(self.borrow_index, self.total_borrowed, available, elapsed)?;
+        self.borrow_index_updated_at = now;
+        Ok(())
+    }
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+}
 
-    pub token_program: Program<'info, Token>,
+/// `UsdcMarket` layout from before `max_borrow_per_wallet`; read only by `migrate_usdc_market`.
+#[derive(AnchorDeserialize)]
+struct LegacyUsdcMarket {
+    usdc_vault: Pubkey,
+    borrow_cap: u64,
+    max_ltv_bps: u64,
+    liquidation_ltv_bps: u64,
+    rate_curve: BorrowRateCurve,
+    total_borrowed: u64,
+    borrow_index: u128,
+    borrow_index_updated_at: i64,
 }
 
-#[derive(Accounts)]
-pub struct ToggleAutoRestake<'info> {
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
-    pub staker: Signer<'info>,
+impl LegacyUsdcMarket {
+    const LEN: usize = 32 + 8 + 8 + 8 + BorrowRateCurve::LEN + 8 + 16 + 8;
 }
 
-#[derive(Accounts)]
-pub struct StakeDual<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+/// Switchboard pull feed quoting SST in USDC, and the checks its value must pass to be used.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PriceOracle {
+    pub feed: Pubkey,
+    pub max_staleness_slots: u64,
+    pub min_samples: u32,
+    /// Largest standard deviation across oracle samples, in bps of the price.
+    pub max_confidence_bps: u64,
+}
 
-    #[account(
-        init,
-        payer = staker,
-        space = 8 + StakeInfo::LEN,
-        seeds = [b"stake", staker.key().as_ref()],
-        bump
-    )]
-    pub stake_info: Account<'info, StakeInfo>,
+impl PriceOracle {
+    const LEN: usize = 32 + 8 + 4 + 8;
+}
 
-    #[account(mut)]
-    pub staker_token_account: Box<Account<'info, TokenAccount>>,
+/// Governance-set parameters of the USDC market.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct UsdcMarketParams {
+    /// Maximum USDC lent out in total (0 = unlimited).
+    pub borrow_cap: u64,
+    /// Largest USDC debt, in bps of the staked SST's USDC value, a position may take on.
+    pub max_ltv_bps: u64,
+    /// Debt-to-value ratio above which a USDC loan can be liquidated.
+    pub liquidation_ltv_bps: u64,
+    pub rate_curve: BorrowRateCurve,
+    /// Maximum USDC principal a single position may owe (0 = unlimited).
+    pub max_borrow_per_wallet: u64,
+}
 
-    #[account(mut)]
-    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+impl UsdcMarketParams {
+    const LEN: usize = 8 + 8 + 8 + BorrowRateCurve::LEN + 8;
 
-    #[account(mut)]
-    pub staker_usdc_token_account: Box<Account<'info, TokenAccount>>,
+    fn check_bounds(&self) -> Result<()> {
+        require!(
+            self.max_ltv_bps > 0 && self.max_ltv_bps < self.liquidation_ltv_bps && self.liquidation_ltv_bps < BPS_DENOMINATOR,
+            ErrorCode::InvalidBasisPoints
+        );
+        self.rate_curve.check_bounds()
+    }
+}
 
-    #[account(mut)]
-    pub vault_usdc_token_account: Box<Account<'info, TokenAccount>>,
+/// Annual borrow rate as a function of borrow-vault utilization: rises from `base_rate_bps` by
+/// `slope_low_bps` up to the kink, then by `slope_high_bps` between the kink and 100%.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct BorrowRateCurve {
+    pub base_rate_bps: u64,
+    pub kink_utilization_bps: u64,
+    pub slope_low_bps: u64,
+    pub slope_high_bps: u64,
+}
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+impl BorrowRateCurve {
+    const LEN: usize = 8 + 8 + 8 + 8;
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    fn check_bounds(&self) -> Result<()> {
+        require!(
+            self.kink_utilization_bps > 0
+                && self.kink_utilization_bps <= BPS_DENOMINATOR
+                && self
+                    .base_rate_bps
+                    .checked_add(self.slope_low_bps)
+                    .and_then(|rate| rate.checked_add(self.slope_high_bps))
+                    .is_some_and(|max_rate| max_rate <= MAX_BORROW_RATE_BPS),
+            ErrorCode::InvalidBasisPoints
+        );
+        Ok(())
+    }
+
+    /// `index` grown over `elapsed` seconds at the rate for the utilization of a market with
+    /// `total_borrowed` lent out and `available` left to lend.
+    fn grow_index(&self, index: u128, total_borrowed: u64, available: u64, elapsed: i64) -> Result<u128> {
+        let supplied = total_borrowed as u128 + available as u128;
+        let utilization_bps = if supplied == 0 {
+            0
+        } else {
+            (total_borrowed as u128 * BPS_DENOMINATOR as u128 / supplied) as u64
+        };
+        let growth = index
+            .checked_mul(self.rate_bps(utilization_bps) as u128 * elapsed.max(0) as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / (BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128);
+        Ok(index.checked_add(growth).ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Annual rate (bps) at `utilization_bps`.
+    fn rate_bps(&self, utilization_bps: u64) -> u64 {
+        let kink = self.kink_utilization_bps.max(1);
+        if utilization_bps <= kink {
+            self.base_rate_bps + self.slope_low_bps * utilization_bps / kink
+        } else {
+            let span = BPS_DENOMINATOR.saturating_sub(kink).max(1);
+            self.base_rate_bps + self.slope_low_bps + self.slope_high_bps * (utilization_bps - kink) / span
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct DepositLP<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+/// Auto-restake settings respected by `compound_rewards`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct CompoundPolicy {
+    /// Smallest reward worth compounding; smaller balances keep accruing.
+    pub min_compound_amount: u64,
+    /// Minimum seconds between compounds (at least `MIN_COMPOUND_INTERVAL`).
+    pub compound_interval: i64,
+    /// Whether the LP boost (from `lp_deposit`) is compounded along with the base reward.
+    pub include_lp_rewards: bool,
+}
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+impl CompoundPolicy {
+    const LEN: usize = 8 + 8 + 1;
+}
 
-    #[account(mut)]
-    pub staker_lp_token_account: Box<Account<'info, TokenAccount>>,
+/// Positions a boost campaign rewards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BoostEligibility {
+    /// Minimum staked amount.
+    pub min_amount: u64,
+    /// Minimum lock period (0 = unlocked positions qualify).
+    pub min_lock_period: u64,
+}
 
-    #[account(mut)]
-    pub vault_lp_token_account: Box<Account<'info, TokenAccount>>,
+impl BoostEligibility {
+    const LEN: usize = 8 + 8;
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+    fn is_eligible(&self, stake_info: &StakeInfo) -> bool {
+        stake_info.amount > 0
+            && stake_info.amount >= self.min_amount
+            && stake_info.lock_period >= self.min_lock_period
+    }
+}
 
-    pub token_program: Program<'info, Token>,
+/// Partner-funded, time-boxed incentive paid in `funding_mint` to eligible positions.
+#[account]
+pub struct BoostCampaign {
+    pub partner: Pubkey,
+    pub campaign_id: u64,
+    pub funding_mint: Pubkey,
+    pub vault: Pubkey,
+    pub tokens_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub eligibility: BoostEligibility,
+    /// Sum of the weights of synced eligible positions.
+    pub total_weight: u64,
+    /// Cumulative rewards per unit of weight, scaled by `math::REWARD_SCALE`.
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub reward_dust: u128,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct FlashLoan<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+impl BoostCampaign {
+    const LEN: usize = 32 + 8 + 32 + 32 + 8 + 8 + 8 + BoostEligibility::LEN + 8 + 16 + 8 + 16 + 1;
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+    /// Advances the accumulator to `now`, clamped to the campaign window. Emission while no
+    /// position holds weight is carried in the dust for the next eligible positions.
+    fn update_reward_per_token(&mut self, now: i64) -> Result<()> {
+        let to = now.min(self.end_time);
+        let from = self.last_update_time.max(self.start_time);
+        if to > from {
+            let emitted = math::to_reward_scale(
+                (self.tokens_per_second as u128).checked_mul((to - from) as u128).ok_or(ErrorCode::Overflow)?,
+            )?;
+            if self.total_weight > 0 {
+                let (increment, dust) = sweep_reward_dust(emitted, self.reward_dust, self.total_weight)?;
+                self.reward_per_token_stored = self.reward_per_token_stored
+                    .checked_add(increment)
+                    .ok_or(ErrorCode::Overflow)?;
+                self.reward_dust = dust;
+            } else {
+                self.reward_dust = self.reward_dust.checked_add(emitted).ok_or(ErrorCode::Overflow)?;
+            }
+        }
+        self.last_update_time = self.last_update_time.max(to);
+        Ok(())
+    }
+}
 
-    #[account(mut)]
-    pub vault_token_account: Box<Account<'info, TokenAccount>>,
+/// A position's participation in a boost campaign.
+#[account]
+pub struct BoostPosition {
+    pub campaign: Pubkey,
+    pub staker: Pubkey,
+    pub weight: u64,
+    pub reward_per_token_paid: u128,
+    pub accrued: u64,
+}
 
-    #[account(mut)]
-    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+impl BoostPosition {
+    const LEN: usize = 32 + 32 + 8 + 16 + 8;
+}
 
-    /// CHECK: Derived PDA for the vault authority.
-    pub vault_authority: UncheckedAccount<'info>,
+/// Stake state that was in effect until a deposit at `recorded_at` (0 = unused slot).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct VotingCheckpoint {
+    pub amount: u64,
+    pub last_staked_time: i64,
+    pub recorded_at: i64,
+}
 
-    pub token_program: Program<'info, Token>,
+impl VotingCheckpoint {
+    const LEN: usize = 8 + 8 + 8;
 }
 
-#[derive(Accounts)]
-pub struct SlashStake<'info> {
-    #[account(mut)]
-    pub gov_authority: Signer<'info>, // Governance authority
+/// Reward-per-token accumulator value at the start of a reward epoch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardCheckpoint {
+    pub epoch: u64,
+    pub reward_per_token: u128,
+}
 
-    /// The staker whose stake will be slashed.
-    pub staker: AccountInfo<'info>,
+impl RewardCheckpoint {
+    const LEN: usize = 8 + 16;
+}
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+/// Emitted when an incident pause is declared or resolved.
+#[event]
+pub struct IncidentModeChanged {
+    pub active: bool,
+    pub timestamp: i64,
+    /// Total seconds of resolved incident pauses.
+    pub incident_offset: i64,
 }
 
-#[derive(Accounts)]
-pub struct VoteProposal<'info> {
-    #[account(mut)]
-    pub proposer: Signer<'info>,
+/// Emitted when a nominated authority accepts control of the governance or protocol config.
+#[event]
+pub struct AuthorityTransferred {
+    pub config: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
 
-    #[account(mut)]
-    pub stake_info: Account<'info, StakeInfo>,
+/// Emitted when a liquidator repays part of an unhealthy position's debt for its stake.
+#[event]
+pub struct PositionLiquidated {
+    pub staker: Pubkey,
+    pub liquidator: Pubkey,
+    pub repaid: u64,
+    pub seized: u64,
+}
 
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+/// Emitted when the guardian pauses or governance unpauses the protocol.
+#[event]
+pub struct ProtocolPauseChanged {
+    pub paused: bool,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
+/// Swap-on-claim routing table: which payout mints each reward mint may be claimed in.
+#[account]
+pub struct PayoutRouteTable {
+    pub routes: Vec<PayoutRoute>,
 }
 
-#[derive(Accounts)]
-pub struct Borrow<'info> {
-    #[account(mut)]
-    pub staker: Signer<'info>,
+impl PayoutRouteTable {
+    const LEN: usize = 4 + PayoutRoute::LEN * MAX_PAYOUT_ROUTES;
 
-    #[account(mut, seeds = [b"stake", staker.key().as_ref()], bump)]
-    pub stake_info: Account<'info, StakeInfo>,
+    /// Active route converting `reward_mint` into `payout_mint`, if any.
+    fn find(&self, reward_mint: Pubkey, payout_mint: Pubkey) -> Option<&PayoutRoute> {
+        self.routes
+            .iter()
+            .find(|route| route.active && route.reward_mint == reward_mint && route.payout_mint == payout_mint)
+    }
+}
 
-    pub token_program: Program<'info, Token>,
+/// AMM route for one (reward mint, payout mint) pair. The swap is invoked with
+/// `swap_discriminator ++ amount_in ++ min_amount_out` (little-endian u64s) as instruction data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PayoutRoute {
+    pub reward_mint: Pubkey,
+    pub payout_mint: Pubkey,
+    pub amm_program: Pubkey,
+    pub swap_discriminator: [u8; 8],
+    /// Number of AMM accounts the claimer must pass via `remaining_accounts`.
+    pub route_account_count: u8,
+    pub active: bool,
 }
 
-#[derive(Accounts)]
-pub struct CreateProposal<'info> {
-    #[account(mut)]
-    pub proposer: Signer<'info>,
+impl PayoutRoute {
+    const LEN: usize = 32 + 32 + 32 + 8 + 1 + 1;
+}
 
-    #[account(
-        init, 
-        payer = proposer, 
-        space = 8 + Proposal::LEN, 
-        seeds = [b"proposal", proposer.key().as_ref(), proposer.to_account_info().key.as_ref()],
-        bump
-    )]
-    pub proposal: Account<'info, Proposal>,
+/// Early-unstake penalties collected for redistribution to the remaining stakers.
+#[account]
+pub struct BonusPool {
+    pub total_collected: u64,
+    pub total_distributed: u64,
+    /// Collected while nobody was earning; distributed with the next penalty.
+    pub undistributed: u64,
+}
 
-    pub system_program: Program<'info, System>,
+impl BonusPool {
+    const LEN: usize = 8 + 8 + 8;
 }
 
-#[derive(Accounts)]
-pub struct DonateInsurance<'info> {
-    #[account(mut)]
-    pub donor: Signer<'info>,
+/// Referral record of a referrer; credits accumulate here until claimed from the referral vault.
+#[account]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,
+    pub referee_count: u32,
+    /// Credited and not yet claimed.
+    pub pending: u64,
+    pub total_earned: u64,
+}
 
-    #[account(mut)]
-    pub donor_token_account: Box<Account<'info, TokenAccount>>,
+impl ReferralAccount {
+    const LEN: usize = 32 + 4 + 8 + 8;
+}
 
-    #[account(mut)]
-    pub insurance_fund_token_account: Box<Account<'info, TokenAccount>>,
+/// A referee's claim credited its referrer.
+#[event]
+pub struct ReferralCredited {
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(mut, seeds = [b"insurance_fund"], bump)]
-    pub insurance_fund: Account<'info, InsuranceFund>,
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+/// Loyalty points of a staker, earned from stake held (weighted by lock tier); they raise the fee
+/// discount and are the basis for future airdrops.
+#[account]
+pub struct LoyaltyAccount {
+    pub staker: Pubkey,
+    pub points: u64,
+    pub last_accrued_at: i64,
+}
+
+impl LoyaltyAccount {
+    const LEN: usize = 32 + 8 + 8;
+
+    /// Accrues points for the stake held since the last accrual and returns the points earned.
+    /// Runs on every balance change, so `stake_info.amount` was held for the whole interval.
+    fn accrue(&mut self, config: &ProtocolConfig, stake_info: &StakeInfo, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.last_accrued_at).max(0) as u128;
+        let token_seconds = (stake_info.amount as u128).checked_mul(elapsed).ok_or(ErrorCode::Overflow)?;
+        let earned = mul_div_floor(
+            token_seconds,
+            config.lock_reward_multiplier_bps(stake_info.lock_period) as u128,
+            LOYALTY_TOKEN_DAY as u128 * BPS_DENOMINATOR as u128,
+        )?;
+        self.points = self.points.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+        self.last_accrued_at = now;
+        Ok(earned)
+    }
+}
+
+#[event]
+pub struct LoyaltyPointsAccrued {
+    pub staker: Pubkey,
+    pub earned: u64,
+    pub points: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SeasonEntry {
+    pub participant: Pubkey,
+    pub score: u64,
 }
 
+impl SeasonEntry {
+    const LEN: usize = 32 + 8;
+}
+
+/// Staking competition; keeps the running top `SEASON_WINNERS` participants by score.
 #[account]
-pub struct InsuranceFund {
-    pub balance: u64,
+pub struct Season {
+    pub id: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub prize_vault: Pubkey,
+    /// Share of the prize vault paid to each rank, best first.
+    pub prize_shares_bps: [u64; SEASON_WINNERS],
+    /// Sorted by score, highest first; unused entries have a default participant.
+    pub leaderboard: [SeasonEntry; SEASON_WINNERS],
+    pub finalized: bool,
+    /// Prize vault balance frozen at finalization; each rank claims its share of it.
+    pub prize_pool: u64,
 }
 
-impl InsuranceFund {
-    const LEN: usize = 8;
+impl Season {
+    const LEN: usize = 8 + 8 + 8 + 32 + 8 * SEASON_WINNERS + SeasonEntry::LEN * SEASON_WINNERS + 1 + 8;
+
+    /// Updates `participant`'s leaderboard entry, or lets them replace the lowest entry when
+    /// their score beats it.
+    fn record_score(&mut self, participant: Pubkey, score: u64) {
+        let slot = self
+            .leaderboard
+            .iter()
+            .position(|entry| entry.participant == participant)
+            .or_else(|| {
+                let (index, lowest) = self.leaderboard.iter().enumerate().min_by_key(|(_, entry)| entry.score)?;
+                (lowest.participant == Pubkey::default() || score > lowest.score).then_some(index)
+            });
+        if let Some(index) = slot {
+            self.leaderboard[index] = SeasonEntry { participant, score };
+            self.leaderboard.sort_by(|a, b| b.score.cmp(&a.score));
+        }
+    }
 }
 
+/// A participant's score in one season.
 #[account]
-pub struct StakeInfo {
+pub struct SeasonScore {
+    pub season: Pubkey,
+    pub participant: Pubkey,
+    pub score: u64,
+    /// Stake held since `updated_at`.
+    pub stake: u64,
+    pub updated_at: i64,
+    pub prize_claimed: bool,
+}
+
+impl SeasonScore {
+    const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[event]
+pub struct SeasonPrizePaid {
+    pub season_id: u64,
+    pub participant: Pubkey,
+    pub rank: u8,
+    pub score: u64,
+    pub prize: u64,
+}
+
+#[event]
+pub struct ClaimAsModeSet {
+    pub staker: Pubkey,
+    /// Default when the position is back to claiming SST.
+    pub payout_mint: Pubkey,
+}
+
+/// LP farm for one LP mint, with its own reward emission and accumulator.
+#[account]
+pub struct LpPool {
+    pub lp_mint: Pubkey,
+    /// Token account (owned by the vault authority) holding the deposited LP tokens.
+    pub lp_vault: Pubkey,
+    pub total_deposited: u64,
+    /// Farm reward mint, vault, emission rate and accumulator.
+    pub rewards: RewardToken,
+}
+
+impl LpPool {
+    const LEN: usize = 32 + 32 + 8 + RewardToken::LEN;
+}
+
+/// A staker's deposit in one LP farm.
+#[account]
+pub struct LpFarmPosition {
+    pub lp_pool: Pubkey,
     pub staker: Pubkey,
     pub amount: u64,
-    pub last_staked_time: i64,
-    pub lock_period: u64,
-    pub locked_until: i64,
-    pub borrowed_amount: u64,
-    pub locked: bool,
-    pub auto_restake: bool,
-    pub usdc_amount: u64,
-    pub lp_deposit: u64,
+    /// Last deposit; starts the LP lockup.
+    pub deposited_at: i64,
+    pub reward_per_token_paid: u128,
+    pub accrued_rewards: u64,
 }
 
-impl StakeInfo {
-    // Updated space: padded to 112 bytes.
-    const LEN: usize = 112;
+impl LpFarmPosition {
+    const LEN: usize = 32 + 32 + 8 + 8 + 16 + 8;
+}
+
+#[event]
+pub struct LpFarmRewardsClaimed {
+    pub lp_pool: Pubkey,
+    pub staker: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
 }
 
+/// Governance-approved LP mints accepted by `deposit_lp`.
 #[account]
-pub struct Proposal {
-    pub proposer: Pubkey,
-    pub description: String,
-    pub votes_for: u64,
-    pub votes_against: u64,
-    pub created_at: i64,
+pub struct LpMintRegistry {
+    pub mints: Vec<LpMintEntry>,
 }
 
-impl Proposal {
-    const LEN: usize = 268;
+impl LpMintRegistry {
+    const LEN: usize = 4 + LpMintEntry::LEN * MAX_LP_MINTS;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LpMintEntry {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    /// Maximum total deposits of this mint (0 = unlimited).
+    pub cap: u64,
+    /// Weight of this mint's deposits in the LP boost (bps, 10_000 = 1x).
+    pub boost_weight_bps: u64,
+    pub total_deposited: u64,
+    /// Inactive mints accept no new deposits; withdrawals remain open.
+    pub active: bool,
+}
+
+impl LpMintEntry {
+    const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// A locked LP deposit; its tier-weighted amount counts toward the position's LP boost.
+#[account]
+pub struct LpLock {
+    pub staker: Pubkey,
+    pub lock_id: u64,
+    pub lp_mint: Pubkey,
+    pub amount: u64,
+    /// `amount` scaled by the lock tier's LP multiplier at deposit time.
+    pub weight: u64,
+    pub lock_period: u64,
+    pub locked_until: i64,
+}
+
+impl LpLock {
+    const LEN: usize = 32 + 8 + 32 + 8 + 8 + 8 + 8;
 }
 
 #[error_code]
@@ -655,6 +13185,332 @@ pub enum ErrorCode {
     ReentrancyDetected,
     #[msg("Borrow limit exceeded.")]
     BorrowLimitExceeded,
+    #[msg("Epoch does not match the current cluster epoch.")]
+    InvalidEpoch,
+    #[msg("Signer is not authorized for this action.")]
+    Unauthorized,
+    #[msg("Basis points value exceeds 10,000.")]
+    InvalidBasisPoints,
+    #[msg("Position has no active lock.")]
+    PositionNotLocked,
+    #[msg("Penalty destination does not match the configured account.")]
+    InvalidPenaltyDestination,
+    #[msg("Amount must be greater than zero and within bounds.")]
+    InvalidAmount,
+    #[msg("Insurance fund balance is insufficient.")]
+    InsuranceFundDepleted,
+    #[msg("Token account is not owned by the expected authority.")]
+    InvalidTokenAccountOwner,
+    #[msg("Token account mint does not match the expected mint.")]
+    InvalidMint,
+    #[msg("Invalid number of ladder tranches.")]
+    InvalidTrancheCount,
+    #[msg("Ladder tranche index out of range.")]
+    InvalidTrancheIndex,
+    #[msg("Ladder tranche has already been withdrawn.")]
+    TrancheAlreadyWithdrawn,
+    #[msg("Auto-relock is not enabled for this position.")]
+    AutoRelockDisabled,
+    #[msg("Lock has not matured yet.")]
+    LockNotMatured,
+    #[msg("No unstake is scheduled for this position.")]
+    NoScheduledUnstake,
+    #[msg("Payout account does not match the registered account.")]
+    InvalidPayoutAccount,
+    #[msg("Deposit exceeds the per-wallet stake cap.")]
+    WalletStakeCapExceeded,
+    #[msg("Deposit exceeds the global stake cap.")]
+    GlobalStakeCapExceeded,
+    #[msg("Only VIP stakers can lease their fee tier.")]
+    NotVipStaker,
+    #[msg("Invalid lease terms.")]
+    InvalidLeaseTerms,
+    #[msg("Lease does not apply to this trader or position.")]
+    InvalidLease,
+    #[msg("Lease is not active.")]
+    LeaseInactive,
+    #[msg("Lease has already been accepted.")]
+    LeaseAlreadyAccepted,
+    #[msg("No pending withdrawal to claim.")]
+    NoPendingWithdrawal,
+    #[msg("Token account is frozen.")]
+    TokenAccountFrozen,
+    #[msg("Deposit is below the minimum stake amount.")]
+    BelowMinimumStake,
+    #[msg("Withdrawal is below the minimum withdrawal amount.")]
+    BelowMinimumWithdrawal,
+    #[msg("Reward destination does not match the registered beneficiary.")]
+    InvalidRewardBeneficiary,
+    #[msg("Destination does not match the registered withdrawal address.")]
+    WithdrawalAddressMismatch,
+    #[msg("Withdrawal address is immutable.")]
+    WithdrawalAddressImmutable,
+    #[msg("No withdrawal address change is pending.")]
+    NoPendingAddressChange,
+    #[msg("Timelock has not elapsed yet.")]
+    TimelockNotElapsed,
+    #[msg("This action requires the registered second signer.")]
+    SecondSignerRequired,
+    #[msg("Two-factor unstaking is not enabled.")]
+    TwoFactorNotEnabled,
+    #[msg("Rage quit requires a vote against a passed proposal, within its timelock.")]
+    RageQuitNotEligible,
+    #[msg("Position has an outstanding borrow.")]
+    OutstandingBorrow,
+    #[msg("Gifted stake is still vesting.")]
+    GiftStillVesting,
+    #[msg("Invalid set of parameter changes.")]
+    InvalidParameterChanges,
+    #[msg("Proposal description hash is empty or its URI contains control characters or whitespace.")]
+    InvalidDescription,
+    #[msg("Proposal description URI exceeds the maximum length.")]
+    DescriptionTooLong,
+    #[msg("Proposal description URI violates the content policy.")]
+    DescriptionPolicyViolation,
+    #[msg("Proposal description URI must reference a forum post.")]
+    MissingForumReference,
+    #[msg("Invalid description policy.")]
+    InvalidDescriptionPolicy,
+    #[msg("Account is not a stake account owned by this program.")]
+    InvalidStakeAccount,
+    #[msg("Stake account is already at the current version.")]
+    AlreadyMigrated,
+    #[msg("Invalid emission schedule.")]
+    InvalidEmissionSchedule,
+    #[msg("Position migration is not enabled.")]
+    MigrationDisabled,
+    #[msg("Position has borrows, pending withdrawals or side deposits and cannot be exported.")]
+    PositionNotExportable,
+    #[msg("Invalid position export proof.")]
+    InvalidMigrationProof,
+    #[msg("The position receipt must be supplied to fully unstake.")]
+    ReceiptRequired,
+    #[msg("Unknown formula version.")]
+    InvalidFormulaVersion,
+    #[msg("Too many positions for the position index.")]
+    TooManyPositions,
+    #[msg("Account is not a position owned by this wallet.")]
+    InvalidIndexedPosition,
+    #[msg("Lottery is not accepting this action in its current state.")]
+    LotteryNotOpen,
+    #[msg("Lottery draw is not due yet.")]
+    LotteryDrawNotDue,
+    #[msg("Position has no lottery weight yet.")]
+    NoLotteryWeight,
+    #[msg("Invalid randomness account.")]
+    InvalidRandomnessAccount,
+    #[msg("Randomness must be committed in the previous slot.")]
+    RandomnessExpired,
+    #[msg("Randomness has not been revealed yet.")]
+    RandomnessNotResolved,
+    #[msg("Lottery round is not settled.")]
+    LotteryNotSettled,
+    #[msg("Ticket does not cover the winning weight.")]
+    NotLotteryWinner,
+    #[msg("Winning position no longer holds the stake it entered the lottery with.")]
+    LotteryStakeWithdrawn,
+    #[msg("Lottery prize claim window has passed.")]
+    LotteryPrizeExpired,
+    #[msg("Lottery prize can still be claimed.")]
+    LotteryClaimWindowOpen,
+    #[msg("Bonus recipients and amounts are missing, mismatched, too many, or invalid.")]
+    InvalidBonusRecipients,
+    #[msg("Distribution exceeds the campaign budget.")]
+    CampaignBudgetExceeded,
+    #[msg("Reward token registry is full.")]
+    TooManyRewardTokens,
+    #[msg("Reward token is already registered.")]
+    RewardTokenAlreadyRegistered,
+    #[msg("Unknown reward token.")]
+    InvalidRewardToken,
+    #[msg("Reward token vault or destination accounts are missing or invalid.")]
+    InvalidRewardTokenAccounts,
+    #[msg("Reward vesting is enabled; a reward vesting escrow is required.")]
+    RewardVestingRequired,
+    #[msg("No vested rewards to claim.")]
+    NoVestedRewards,
+    #[msg("Vault migration accounts are missing, unpaired, too many, or invalid.")]
+    InvalidVaultMigrationAccounts,
+    #[msg("Every vault pinned in the config must be migrated before the vault rotation completes.")]
+    VaultsNotMigrated,
+    #[msg("Vault authority does not match the config.")]
+    InvalidVaultAuthority,
+    #[msg("Lock reward multipliers must be between 1x and the configured maximum.")]
+    InvalidRewardMultiplier,
+    #[msg("Protocol account is not the expected, initialized PDA.")]
+    InvalidProtocolAccount,
+    #[msg("Boost campaign parameters are invalid.")]
+    InvalidBoostCampaign,
+    #[msg("Every boost campaign the position is enrolled in must be passed to re-sync its weight.")]
+    BoostSyncRequired,
+    #[msg("The position already holds weight in the maximum number of boost campaigns.")]
+    TooManyBoostCampaigns,
+    #[msg("The boost campaign has not ended yet.")]
+    BoostCampaignActive,
+    #[msg("Scheduled withdrawal is not due yet.")]
+    WithdrawalNotDue,
+    #[msg("claim_all expects distinct (stake_info, destination, referral_account) triples within the batch limit.")]
+    InvalidClaimAllAccounts,
+    #[msg("Protocol is paused for an incident.")]
+    IncidentActive,
+    #[msg("No incident is active.")]
+    NoActiveIncident,
+    #[msg("Compound interval is below the protocol minimum.")]
+    InvalidCompoundPolicy,
+    #[msg("Auto-restake is not enabled for this position.")]
+    AutoRestakeDisabled,
+    #[msg("Compound interval has not elapsed yet.")]
+    CompoundTooSoon,
+    #[msg("Pending rewards are below the position's compound minimum.")]
+    BelowCompoundMinimum,
+    #[msg("No active payout route matches the requested payout.")]
+    InvalidPayoutRoute,
+    #[msg("Payout route table is full.")]
+    TooManyPayoutRoutes,
+    #[msg("Payout swap did not spend exactly the reward or returned less than the minimum.")]
+    PayoutSwapMismatch,
+    #[msg("Position is not dormant.")]
+    PositionNotDormant,
+    #[msg("Dormant position notice period has not elapsed.")]
+    DormantNoticePending,
+    #[msg("Nothing is staked to credit the deposit to.")]
+    NoStakeToCredit,
+    #[msg("Instruction would move the position into a state its lifecycle does not allow.")]
+    InvalidStateTransition,
+    #[msg("Invalid referrer.")]
+    InvalidReferrer,
+    #[msg("The position has a referrer; its referral account and the referral vault are required.")]
+    ReferralAccountsRequired,
+    #[msg("Referral vault does not match the configured referral vault.")]
+    InvalidReferralVault,
+    #[msg("Invalid season parameters.")]
+    InvalidSeason,
+    #[msg("Season accounts do not match.")]
+    InvalidSeasonAccounts,
+    #[msg("The season has ended.")]
+    SeasonEnded,
+    #[msg("The season has not ended yet.")]
+    SeasonNotEnded,
+    #[msg("The season has already been finalized.")]
+    SeasonFinalized,
+    #[msg("The season accounts of the position must be passed to re-sync its score.")]
+    SeasonSyncRequired,
+    #[msg("The participant has no prize in this season.")]
+    NoSeasonPrize,
+    #[msg("The season prize has already been claimed.")]
+    SeasonPrizeClaimed,
+    #[msg("The position claims in another mint; use the routed claim.")]
+    ClaimAsModeActive,
+    #[msg("LP tokens are still within the lockup period.")]
+    LpLocked,
+    #[msg("Account does not belong to the LP pool.")]
+    InvalidLpPoolAccounts,
+    #[msg("LP mint is not approved.")]
+    LpMintNotApproved,
+    #[msg("The position already holds LP tokens of another mint.")]
+    LpMintMismatch,
+    #[msg("The vault of the position's LP deposit is unknown; it must be assigned first.")]
+    LpVaultUnknown,
+    #[msg("The position's LP vault is already known.")]
+    LpVaultAlreadyKnown,
+    #[msg("Deposit would exceed the LP mint's cap.")]
+    LpMintCapExceeded,
+    #[msg("Too many LP mints registered.")]
+    TooManyLpMints,
+    #[msg("Voting on this proposal has closed.")]
+    VotingClosed,
+    #[msg("Voting on this proposal is still open.")]
+    VotingStillOpen,
+    #[msg("The proposal has already been finalized.")]
+    ProposalAlreadyFinalized,
+    #[msg("Invalid vote delegate.")]
+    InvalidDelegate,
+    #[msg("The position is not delegating its votes.")]
+    NotDelegated,
+    #[msg("Voting power is delegated; the delegate votes for this position.")]
+    VotingPowerDelegated,
+    #[msg("Delegator accounts do not match.")]
+    InvalidDelegatorAccounts,
+    #[msg("A delegator has already voted on this proposal.")]
+    DelegatorAlreadyVoted,
+    #[msg("Invalid proposal instruction.")]
+    InvalidProposalInstruction,
+    #[msg("The proposal has not passed.")]
+    ProposalNotPassed,
+    #[msg("The proposal is still in its timelock.")]
+    ProposalTimelockActive,
+    #[msg("A parameter changed after the proposal was created.")]
+    StaleParameterChange,
+    #[msg("The proposal has no pending treasury spend for this recipient.")]
+    InvalidTreasurySpend,
+    #[msg("Treasury spend exceeds the epoch spend limit.")]
+    TreasurySpendLimitExceeded,
+    #[msg("Invalid proposal options or option weights.")]
+    InvalidVoteOptions,
+    #[msg("This proposal requires a bond; pass the proposer token account and bond vault.")]
+    ProposalBondRequired,
+    #[msg("Bond vault does not match the governance config, or is not a dedicated bond account.")]
+    InvalidBondVault,
+    #[msg("The proposer does not have enough SST staked to create a proposal.")]
+    InsufficientProposerStake,
+    #[msg("The proposal can no longer be vetoed.")]
+    VetoWindowClosed,
+    #[msg("Invalid security council members or threshold.")]
+    InvalidSecurityCouncil,
+    #[msg("This member has already approved.")]
+    AlreadyApproved,
+    #[msg("The proposal already has too many votes to be cancelled.")]
+    ProposalHasVotes,
+    #[msg("The proposal's execution window has passed.")]
+    ProposalExpired,
+    #[msg("The proposal cannot be closed yet.")]
+    ProposalNotClosable,
+    #[msg("No pending slash proposal for this staker.")]
+    InvalidSlashProposal,
+    #[msg("Only active, single-choice proposals can be fast-tracked, and only once.")]
+    InvalidEmergencyProposal,
+    #[msg("Protocol is paused by the guardian.")]
+    ProtocolPaused,
+    #[msg("Protocol is not paused.")]
+    ProtocolNotPaused,
+    #[msg("Borrow vault does not match the configured borrow vault.")]
+    InvalidBorrowVault,
+    #[msg("Stake vault is not the configured SST stake vault, or cannot be replaced.")]
+    InvalidStakeVault,
+    #[msg("Reward vault does not match the configured reward vault.")]
+    InvalidRewardVault,
+    #[msg("Payout exceeds the funded rewards outstanding.")]
+    RewardsUnderfunded,
+    #[msg("Position is not eligible for liquidation.")]
+    PositionHealthy,
+    #[msg("Withdrawal would leave less stake than the outstanding loan requires as collateral.")]
+    InsufficientCollateral,
+    #[msg("Borrow would exceed the market's borrow cap.")]
+    BorrowCapExceeded,
+    #[msg("Borrow would exceed the market's per-wallet borrow cap.")]
+    WalletBorrowCapExceeded,
+    #[msg("Price feed is missing, not the configured feed, or invalid.")]
+    InvalidPriceFeed,
+    #[msg("Price feed value is stale.")]
+    StalePrice,
+    #[msg("Price feed samples disagree by more than the configured confidence bound.")]
+    PriceConfidenceTooWide,
+    #[msg("Flash loan must be repaid by a later repay_flash_loan in the same transaction.")]
+    FlashLoanNotRepaid,
+    #[msg("Position already has an outstanding flash loan.")]
+    FlashLoanOutstanding,
+    #[msg("Position has no outstanding flash loan.")]
+    NoFlashLoan,
+    #[msg("A program invoked during the flash loan is not on the allowlist.")]
+    FlashLoanProgramNotAllowed,
+    #[msg("Flash-loan allowlist is too long or has duplicates.")]
+    InvalidFlashLoanAllowlist,
+    #[msg("Vote-escrow vault is not the configured ve vault, or cannot be replaced.")]
+    InvalidVeVault,
+    #[msg("Flash loans must be taken by a top-level instruction, not through CPI.")]
+    FlashLoanCpiNotAllowed,
+    #[msg("Treasury accounts are missing or do not match the treasury vault.")]
+    InvalidTreasuryVault,
 }
 
 
@@ -0,0 +1,309 @@
+//! Q64.64 fixed-point arithmetic shared by rewards, voting power and vesting.
+//!
+//! Fixed-point values are `u128`s with 64 integer and 64 fractional bits. The reward accumulators
+//! are the exception: they keep the `REWARD_SCALE` they were first stored at. Every operation is
+//! checked and rounds in an explicit direction; which direction a call site needs follows the
+//! rounding policy documented next to the reward helpers in `lib.rs`.
+
+use crate::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Number of fractional bits.
+pub const FRACTION_BITS: u32 = 64;
+/// 1.0 in Q64.64.
+pub const ONE: u128 = 1 << FRACTION_BITS;
+const FRACTION_MASK: u128 = ONE - 1;
+
+/// Scale of the reward accumulators (`reward_per_token_stored`, `reward_dust` and the positions'
+/// `*_paid` snapshots). Accumulators already on chain are stored at it, so it must not change.
+pub const REWARD_SCALE: u128 = 1_000_000_000_000;
+
+/// `value` at the reward accumulator scale.
+pub fn to_reward_scale(value: u128) -> Result<u128> {
+    Ok(value.checked_mul(REWARD_SCALE).ok_or(ErrorCode::Overflow)?)
+}
+
+/// Rewards owed to `amount` tokens for an accumulator increase of `per_token` (at `REWARD_SCALE`),
+/// rounded down, together with the remainder (also at `REWARD_SCALE`) so callers can carry it as
+/// dust.
+pub fn reward_mul_floor(amount: u64, per_token: u128) -> Result<(u64, u128)> {
+    let scaled = (amount as u128).checked_mul(per_token).ok_or(ErrorCode::Overflow)?;
+    let earned = u64::try_from(scaled / REWARD_SCALE).map_err(|_| error!(ErrorCode::Overflow))?;
+    Ok((earned, scaled % REWARD_SCALE))
+}
+
+/// `numerator / denominator` as a Q64.64 number, rounded down.
+pub fn ratio_floor(numerator: u64, denominator: u64) -> Result<u128> {
+    require!(denominator > 0, ErrorCode::Underflow);
+    Ok(((numerator as u128) << FRACTION_BITS) / denominator as u128)
+}
+
+/// Product of two Q64.64 numbers, rounded down.
+pub fn mul(a: u128, b: u128) -> Result<u128> {
+    let (a_int, a_frac) = (a >> FRACTION_BITS, a & FRACTION_MASK);
+    let (b_int, b_frac) = (b >> FRACTION_BITS, b & FRACTION_MASK);
+    // Fraction-by-fraction products are below 2^128 and only their top half is kept.
+    let fractions = (a_frac * b_frac) >> FRACTION_BITS;
+    a_int
+        .checked_mul(b)
+        .and_then(|product| product.checked_add(a_frac * b_int))
+        .and_then(|product| product.checked_add(fractions))
+        .ok_or(error!(ErrorCode::Overflow))
+}
+
+/// `base ^ exponent` for a Q64.64 `base`, by repeated squaring (O(log exponent) steps), with
+/// every step rounded down.
+pub fn pow_floor(base: u128, exponent: u64) -> Result<u128> {
+    let mut base = base;
+    let mut result = ONE;
+    let mut exponent = exponent;
+    while exponent > 0 && result > 0 {
+        if exponent & 1 == 1 {
+            result = mul(result, base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul(base, base)?;
+        }
+    }
+    Ok(result)
+}
+
+/// `amount * factor` for a Q64.64 `factor`, rounded down, together with the discarded fraction
+/// (itself Q64.64) so callers can carry it as dust. No intermediate product can overflow.
+pub fn mul_floor(amount: u64, factor: u128) -> Result<(u64, u128)> {
+    let amount = amount as u128;
+    let whole = amount.checked_mul(factor >> FRACTION_BITS).ok_or(ErrorCode::Overflow)?;
+    // Both operands are below 2^64, so this product always fits.
+    let fraction = amount * (factor & FRACTION_MASK);
+    let integer = whole.checked_add(fraction >> FRACTION_BITS).ok_or(ErrorCode::Overflow)?;
+    let integer = u64::try_from(integer).map_err(|_| error!(ErrorCode::Overflow))?;
+    Ok((integer, fraction & FRACTION_MASK))
+}
+
+/// `amount * factor` for a Q64.64 `factor`, rounded up.
+pub fn mul_ceil(amount: u64, factor: u128) -> Result<u64> {
+    let (integer, remainder) = mul_floor(amount, factor)?;
+    let round_up = u64::from(remainder > 0);
+    Ok(integer.checked_add(round_up).ok_or(ErrorCode::Overflow)?)
+}
+
+/// `value * numerator / denominator`, rounded down.
+pub fn mul_div_floor(value: u128, numerator: u128, denominator: u128) -> Result<u64> {
+    let product = value.checked_mul(numerator).ok_or(ErrorCode::Overflow)?;
+    let quotient = product.checked_div(denominator).ok_or(ErrorCode::Underflow)?;
+    u64::try_from(quotient).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// `value * numerator / denominator`, rounded up.
+pub fn mul_div_ceil(value: u128, numerator: u128, denominator: u128) -> Result<u64> {
+    require!(denominator > 0, ErrorCode::Underflow);
+    let product = value.checked_mul(numerator).ok_or(ErrorCode::Overflow)?;
+    u64::try_from(product.div_ceil(denominator)).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// The `part / whole` share of `amount`, rounded down. `part` is clamped to `whole` and an empty
+/// `whole` yields the full amount, so the result never exceeds `amount` and cannot fail.
+pub fn pro_rata_floor(amount: u64, part: u64, whole: u64) -> u64 {
+    if whole == 0 || part >= whole {
+        return amount;
+    }
+    // part < whole, so the quotient is below `amount`.
+    ((amount as u128 * part as u128) / whole as u128) as u64
+}
+
+/// The `part / whole` share of `amount`, rounded up; same clamping as [`pro_rata_floor`].
+pub fn pro_rata_ceil(amount: u64, part: u64, whole: u64) -> u64 {
+    if whole == 0 || part >= whole {
+        return amount;
+    }
+    (amount as u128 * part as u128).div_ceil(whole as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_reward_scale_scales_and_rejects_overflow() {
+        assert_eq!(to_reward_scale(0).unwrap(), 0);
+        assert_eq!(to_reward_scale(1).unwrap(), REWARD_SCALE);
+        assert_eq!(to_reward_scale(u64::MAX as u128).unwrap(), u64::MAX as u128 * REWARD_SCALE);
+        assert!(to_reward_scale(u128::MAX / REWARD_SCALE + 1).is_err());
+    }
+
+    #[test]
+    fn reward_mul_floor_returns_earned_and_dust() {
+        assert_eq!(reward_mul_floor(3, REWARD_SCALE / 2).unwrap(), (1, REWARD_SCALE / 2));
+        assert_eq!(reward_mul_floor(0, u128::MAX).unwrap(), (0, 0));
+        assert!(reward_mul_floor(2, u128::MAX).is_err());
+        assert!(reward_mul_floor(u64::MAX, REWARD_SCALE * 2).is_err());
+    }
+
+    #[test]
+    fn ratio_floor_rounds_down() {
+        assert_eq!(ratio_floor(1, 2).unwrap(), ONE / 2);
+        assert_eq!(ratio_floor(3, 1).unwrap(), 3 * ONE);
+        // 1/3 is not representable; the stored value sits just below it.
+        let third = ratio_floor(1, 3).unwrap();
+        assert_eq!(third, ONE / 3);
+        assert!(third * 3 < ONE);
+        assert_eq!(ratio_floor(0, 7).unwrap(), 0);
+        assert_eq!(ratio_floor(u64::MAX, 1).unwrap(), (u64::MAX as u128) << 64);
+        assert!(ratio_floor(1, 0).is_err());
+    }
+
+    #[test]
+    fn mul_floor_returns_integer_and_fraction() {
+        assert_eq!(mul_floor(10, ONE).unwrap(), (10, 0));
+        assert_eq!(mul_floor(7, ONE / 2).unwrap(), (3, ONE / 2));
+        assert_eq!(mul_floor(0, u128::MAX).unwrap(), (0, 0));
+        assert_eq!(mul_floor(u64::MAX, 0).unwrap(), (0, 0));
+        // 3 * floor(1/3) falls just short of 1: rounded down to 0 with a large remainder.
+        let (integer, fraction) = mul_floor(3, ratio_floor(1, 3).unwrap()).unwrap();
+        assert_eq!(integer, 0);
+        assert_eq!(fraction, 3 * (ONE / 3));
+    }
+
+    #[test]
+    fn mul_floor_handles_factors_above_one_without_intermediate_overflow() {
+        // The integer and fractional parts of the factor are multiplied separately.
+        let factor = 2 * ONE + ONE / 4;
+        assert_eq!(mul_floor(1 << 40, factor).unwrap(), ((1u64 << 41) + (1 << 38), 0));
+        assert_eq!(mul_floor(u64::MAX, ONE).unwrap(), (u64::MAX, 0));
+        assert!(mul_floor(u64::MAX, 2 * ONE).is_err());
+        assert!(mul_floor(2, u128::MAX).is_err());
+    }
+
+    #[test]
+    fn mul_ceil_rounds_up_only_with_a_remainder() {
+        assert_eq!(mul_ceil(10, ONE).unwrap(), 10);
+        assert_eq!(mul_ceil(7, ONE / 2).unwrap(), 4);
+        assert_eq!(mul_ceil(3, ratio_floor(1, 3).unwrap()).unwrap(), 1);
+        assert_eq!(mul_ceil(0, ONE / 3).unwrap(), 0);
+        assert!(mul_ceil(u64::MAX, ONE + 1).is_err());
+    }
+
+    #[test]
+    fn mul_multiplies_fixed_point_numbers() {
+        assert_eq!(mul(ONE, ONE).unwrap(), ONE);
+        assert_eq!(mul(2 * ONE, 3 * ONE).unwrap(), 6 * ONE);
+        assert_eq!(mul(ONE / 2, ONE / 2).unwrap(), ONE / 4);
+        assert_eq!(mul(3 * ONE + ONE / 2, ONE / 2).unwrap(), ONE + ONE * 3 / 4);
+        assert_eq!(mul(0, u128::MAX).unwrap(), 0);
+        // The smallest fraction squared is far below the resolution and rounds to zero.
+        assert_eq!(mul(1, 1).unwrap(), 0);
+        assert_eq!(mul(ONE - 1, ONE - 1).unwrap(), ONE - 2);
+        assert_eq!(mul((u64::MAX as u128) << 64, ONE).unwrap(), (u64::MAX as u128) << 64);
+        assert!(mul((u64::MAX as u128) << 64, 2 * ONE).is_err());
+        assert!(mul(u128::MAX, u128::MAX).is_err());
+    }
+
+    #[test]
+    fn pow_floor_by_squaring() {
+        assert_eq!(pow_floor(ONE / 2, 0).unwrap(), ONE);
+        assert_eq!(pow_floor(ONE / 2, 1).unwrap(), ONE / 2);
+        assert_eq!(pow_floor(ONE / 2, 10).unwrap(), ONE >> 10);
+        assert_eq!(pow_floor(ONE / 2, 64).unwrap(), 1);
+        assert_eq!(pow_floor(ONE / 2, 65).unwrap(), 0);
+        assert_eq!(pow_floor(ONE / 2, u64::MAX).unwrap(), 0);
+        assert_eq!(pow_floor(ONE, u64::MAX).unwrap(), ONE);
+        assert_eq!(pow_floor(3 * ONE, 4).unwrap(), 81 * ONE);
+        assert!(pow_floor(2 * ONE, 64).is_err());
+        // 0.99^n never rounds above the exact value and stays within a few ulps of it.
+        let retention = ratio_floor(99, 100).unwrap();
+        let mut stepwise = ONE;
+        for n in 0..200u64 {
+            let squared = pow_floor(retention, n).unwrap();
+            assert!(squared <= stepwise + n as u128);
+            assert!(stepwise <= squared + 2 * n as u128);
+            stepwise = mul(stepwise, retention).unwrap();
+        }
+    }
+
+    #[test]
+    fn mul_div_rounding_directions() {
+        assert_eq!(mul_div_floor(10, 1, 3).unwrap(), 3);
+        assert_eq!(mul_div_ceil(10, 1, 3).unwrap(), 4);
+        assert_eq!(mul_div_floor(9, 1, 3).unwrap(), 3);
+        assert_eq!(mul_div_ceil(9, 1, 3).unwrap(), 3);
+        assert_eq!(mul_div_floor(0, 5, 3).unwrap(), 0);
+        assert_eq!(mul_div_ceil(0, 5, 3).unwrap(), 0);
+        assert!(mul_div_floor(1, 1, 0).is_err());
+        assert!(mul_div_ceil(1, 1, 0).is_err());
+        assert!(mul_div_floor(u128::MAX, 2, 1).is_err());
+        assert!(mul_div_floor(u64::MAX as u128 + 1, 1, 1).is_err());
+        assert!(mul_div_ceil(u64::MAX as u128, 2, 1).is_err());
+    }
+
+    #[test]
+    fn pro_rata_clamps_and_handles_empty_whole() {
+        assert_eq!(pro_rata_floor(100, 0, 10), 0);
+        assert_eq!(pro_rata_floor(100, 10, 10), 100);
+        assert_eq!(pro_rata_floor(100, 25, 10), 100);
+        assert_eq!(pro_rata_floor(100, 5, 0), 100);
+        assert_eq!(pro_rata_ceil(100, 0, 10), 0);
+        assert_eq!(pro_rata_ceil(100, 25, 10), 100);
+        assert_eq!(pro_rata_ceil(100, 5, 0), 100);
+        assert_eq!(pro_rata_floor(u64::MAX, u64::MAX - 1, u64::MAX), u64::MAX - 1);
+        assert_eq!(pro_rata_ceil(u64::MAX, 1, u64::MAX), 1);
+    }
+
+    #[test]
+    fn pro_rata_matches_exact_division_exhaustively() {
+        for whole in 1..=24u64 {
+            for part in 0..=whole {
+                for amount in 0..=64u64 {
+                    let exact_num = amount * part;
+                    let floor = pro_rata_floor(amount, part, whole);
+                    let ceil = pro_rata_ceil(amount, part, whole);
+                    assert_eq!(floor, exact_num / whole);
+                    assert_eq!(ceil, exact_num.div_ceil(whole));
+                    assert!(ceil - floor <= 1);
+                    // Splitting an amount into complementary shares never creates tokens.
+                    assert!(floor + pro_rata_floor(amount, whole - part, whole) <= amount);
+                    assert_eq!(floor + pro_rata_ceil(amount, whole - part, whole), amount);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_floor_matches_exact_ratios_exhaustively() {
+        for denominator in 1..=32u64 {
+            for numerator in 0..=2 * denominator {
+                let factor = ratio_floor(numerator, denominator).unwrap();
+                for amount in 0..=256u64 {
+                    let (integer, fraction) = mul_floor(amount, factor).unwrap();
+                    let exact = amount * numerator / denominator;
+                    // Truncating the factor can only lose value, and by less than one token.
+                    assert!(integer <= exact);
+                    assert!(exact - integer <= 1);
+                    assert!(fraction < ONE);
+                    if numerator % denominator == 0 {
+                        assert_eq!(integer, exact);
+                        assert_eq!(fraction, 0);
+                    }
+                    let ceil = mul_ceil(amount, factor).unwrap();
+                    assert_eq!(ceil, integer + u64::from(fraction > 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn accumulator_round_trip_loses_at_most_dust() {
+        // Distribute `emitted` across `staked` tokens through the reward accumulator and pay it
+        // back out: what is paid plus the carried dust reconstructs the emission.
+        for staked in [1u64, 3, 7, 1_000, 999_983, 123_456_789] {
+            for emitted in [0u64, 1, 2, 10, 1_000_000, 987_654_321] {
+                let scaled = to_reward_scale(emitted as u128).unwrap();
+                let per_token = scaled / staked as u128;
+                let undistributed = scaled % staked as u128;
+                let (paid, dust) = reward_mul_floor(staked, per_token).unwrap();
+                assert!(paid <= emitted);
+                assert_eq!(paid as u128 * REWARD_SCALE + dust + undistributed, scaled);
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import BN from "bn.js";
 import assert from "assert";
+import { createHash } from "crypto";
 import * as web3 from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
@@ -9,9 +10,17 @@ import {
   getOrCreateAssociatedTokenAccount,
   mintTo,
   createAssociatedTokenAccount,
+  createAccount,
+  getAssociatedTokenAddressSync,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import type { Sst } from "../target/types/sst";
 
+// Metaplex token metadata program (cloned into the local validator, see Anchor.toml).
+const TOKEN_METADATA_PROGRAM_ID = new web3.PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+
 describe("sst tests", () => {
   // Set the provider to the local cluster.
   const provider = anchor.AnchorProvider.env();
@@ -22,7 +31,15 @@ describe("sst tests", () => {
   let mint: web3.PublicKey;
   let stakerTokenAccount: web3.PublicKey;
   let vaultTokenAccount: web3.PublicKey;
+  let rewardVault: web3.PublicKey;
   let vaultAuthority: web3.PublicKey;
+  let protocolStats: web3.PublicKey;
+  let config: web3.PublicKey;
+  let governanceConfig: web3.PublicKey;
+  let emissionSchedule: web3.PublicKey;
+  let rewardTokens: web3.PublicKey;
+  let bonusPool: web3.PublicKey;
+  let loyaltyAccount: web3.PublicKey;
 
   // We use the provider wallet as our staker.
   const staker = provider.wallet;
@@ -67,6 +84,144 @@ describe("sst tests", () => {
       mint,
       vaultAuthority
     );
+
+    // Initialize the global protocol stats PDA.
+    [protocolStats] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("protocol_stats")],
+      program.programId
+    );
+    await program.methods
+      .initializeProtocolStats()
+      .accounts({
+        payer: staker.publicKey,
+        protocolStats: protocolStats,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Initialize the protocol config PDA (no stake caps, no early-unlock penalty).
+    [config] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("config")],
+      program.programId
+    );
+    await program.methods
+      .initializeConfig(stakerTokenAccount, new BN(0))
+      .accounts({
+        authority: staker.publicKey,
+        config: config,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Create a separate reward vault owned by the vault authority and fund it for payouts.
+    rewardVault = await createAccount(
+      provider.connection,
+      provider.wallet.payer,
+      mint,
+      vaultAuthority,
+      web3.Keypair.generate()
+    );
+    await mintTo(
+      provider.connection,
+      provider.wallet.payer,
+      mint,
+      rewardVault,
+      staker.publicKey,
+      1000000
+    );
+
+    // Register the vault authority's SST accounts as the stake vault and the reward vault.
+    await program.methods
+      .setProtocolVaults()
+      .accounts({
+        authority: staker.publicKey,
+        config: config,
+        stakeVault: vaultTokenAccount,
+        rewardVault: rewardVault,
+        veVault: null,
+      })
+      .rpc();
+
+    const configAccount = await program.account.protocolConfig.fetch(config);
+    assert.ok(configAccount.stakeVault.equals(vaultTokenAccount));
+    assert.ok(configAccount.rewardVault.equals(rewardVault));
+    assert.ok(configAccount.sstMint.equals(mint));
+
+    // Initialize the governance config PDA with a permissive description policy.
+    [governanceConfig] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance_config")],
+      program.programId
+    );
+    await program.methods
+      .initializeGovernanceConfig({
+        maxUriLen: 128,
+        requireForumUri: false,
+        forumUriPrefix: "",
+        allowExternalUrls: true,
+        bannedTerms: [],
+      })
+      .accounts({
+        authority: staker.publicKey,
+        governanceConfig: governanceConfig,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Initialize the reward emission schedule (1 token per second, open-ended).
+    [emissionSchedule] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("emission_schedule")],
+      program.programId
+    );
+    await program.methods
+      .initializeEmissionSchedule({
+        tokensPerSecond: new BN(1),
+        startTime: new BN(Math.floor(Date.now() / 1000)),
+        endTime: new BN(0),
+        stepDowns: [],
+      })
+      .accounts({
+        authority: staker.publicKey,
+        config: config,
+        emissionSchedule: emissionSchedule,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Initialize the (empty) partner reward-token registry.
+    [rewardTokens] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("reward_tokens")],
+      program.programId
+    );
+    await program.methods
+      .initializeRewardTokenRegistry()
+      .accounts({
+        authority: staker.publicKey,
+        config: config,
+        rewardTokens: rewardTokens,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Initialize the early-unstake penalty bonus pool.
+    [bonusPool] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("bonus_pool")],
+      program.programId
+    );
+    await program.methods
+      .initializeBonusPool()
+      .accounts({
+        authority: staker.publicKey,
+        config: config,
+        bonusPool: bonusPool,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Loyalty account of the staker; opened by the first balance change.
+    [loyaltyAccount] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("loyalty"), staker.publicKey.toBuffer()],
+      program.programId
+    );
   });
 
   it("Stake tokens (standard, no lock)", async () => {
@@ -78,6 +233,17 @@ describe("sst tests", () => {
       program.programId
     );
 
+    // Derive the position receipt NFT mint, its token account and metadata PDA.
+    const [receiptMint] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("position_receipt"), staker.publicKey.toBuffer()],
+      program.programId
+    );
+    const receiptTokenAccount = getAssociatedTokenAddressSync(receiptMint, staker.publicKey);
+    const [receiptMetadata] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), receiptMint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+
     // Call the stake instruction.
     await program.methods
       .stake(stakeAmount)
@@ -86,8 +252,20 @@ describe("sst tests", () => {
         stakeInfo: stakeInfoPda,
         stakerTokenAccount: stakerTokenAccount,
         vaultTokenAccount: vaultTokenAccount,
+        config: config,
+        protocolStats: protocolStats,
+        emissionSchedule: emissionSchedule,
+        rewardTokens: rewardTokens,
+        receiptMint: receiptMint,
+        receiptTokenAccount: receiptTokenAccount,
+        receiptMetadata: receiptMetadata,
+        integrationStats: null,
+        referralAccount: null,
+        loyaltyAccount: loyaltyAccount,
         vaultAuthority: vaultAuthority,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: web3.SystemProgram.programId,
         rent: web3.SYSVAR_RENT_PUBKEY,
       })
@@ -98,6 +276,11 @@ describe("sst tests", () => {
     console.log("StakeInfo account:", stakeInfoAccount);
     assert.ok(stakeInfoAccount.staker.equals(staker.publicKey));
     assert.ok(new BN(stakeInfoAccount.amount).eq(stakeAmount));
+    assert.ok(stakeInfoAccount.receiptMint.equals(receiptMint));
+
+    // The receipt NFT sits frozen in the staker's wallet.
+    const receiptBalance = await provider.connection.getTokenAccountBalance(receiptTokenAccount);
+    assert.equal(receiptBalance.value.amount, "1");
   });
 
   it("Unstake tokens", async () => {
@@ -109,23 +292,51 @@ describe("sst tests", () => {
       program.programId
     );
 
+    const unstakeAccounts = {
+      staker: staker.publicKey,
+      stakeInfo: stakeInfoPda,
+      secondSigner: null,
+      receiptMint: null,
+      receiptTokenAccount: null,
+      receiptAuthority: null,
+      stakerTokenAccount: stakerTokenAccount,
+      vaultTokenAccount: vaultTokenAccount,
+      config: config,
+      protocolStats: protocolStats,
+      emissionSchedule: emissionSchedule,
+      rewardTokens: rewardTokens,
+      vaultAuthority: vaultAuthority,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      loyaltyAccount: loyaltyAccount,
+      systemProgram: web3.SystemProgram.programId,
+      season: null,
+      seasonScore: null,
+      usdcMarket: null,
+      priceFeed: null,
+    };
+
+    // Paying out of any account other than the configured stake vault is refused.
+    await assert.rejects(
+      program.methods
+        .unstake(unstakeAmount)
+        .accounts({ ...unstakeAccounts, vaultTokenAccount: rewardVault })
+        .rpc(),
+      (err: any) => err.error?.errorCode?.code === "InvalidStakeVault"
+    );
+
     // Call the unstake instruction.
     await program.methods
       .unstake(unstakeAmount)
-      .accounts({
-        staker: staker.publicKey,
-        stakeInfo: stakeInfoPda,
-        stakerTokenAccount: stakerTokenAccount,
-        vaultTokenAccount: vaultTokenAccount,
-        vaultAuthority: vaultAuthority,
-        tokenProgram: TOKEN_PROGRAM_ID,
-      })
+      .accounts(unstakeAccounts)
       .rpc();
 
     // Fetch the stake_info account and verify the remaining balance.
     const stakeInfoAccount = await program.account.stakeInfo.fetch(stakeInfoPda);
     const expectedAmount = new BN(1000).sub(unstakeAmount);
     assert.ok(new BN(stakeInfoAccount.amount).eq(expectedAmount));
+
+    const statsAccount = await program.account.protocolStats.fetch(protocolStats);
+    assert.ok(new BN(statsAccount.totalStaked).eq(expectedAmount));
   });
 
   it("Execute trade with bonus incentives", async () => {
@@ -139,12 +350,19 @@ describe("sst tests", () => {
     await program.methods
       .executeTrade(new BN(80))
       .accounts({
-        staker: staker.publicKey,
+        authority: staker.publicKey,
         stakeInfo: stakeInfoPda,
+        feeLease: null,
+        lessorStakeInfo: null,
+        config: config,
+        priceFeed: null,
+        loyaltyAccount: null,
       })
       .rpc();
 
     // (Check the logs for confirmation; further state changes can be asserted if you expand the logic.)
+    const stakeInfo = await program.account.stakeInfo.fetch(stakeInfoPda);
+    assert.equal(stakeInfo.tradeCount.toNumber(), 1);
   });
 
   it("Claim rewards (auto-compound with LP boost)", async () => {
@@ -154,18 +372,36 @@ describe("sst tests", () => {
       program.programId
     );
 
-    // Define liquidity provided.
-    const liquidityProvided = new BN(10000);
+    const claimAccounts = {
+      authority: staker.publicKey,
+      stakeInfo: stakeInfoPda,
+      stakerTokenAccount: stakerTokenAccount,
+      rewardVault: rewardVault,
+      config: config,
+      rewardVesting: null,
+      protocolStats: protocolStats,
+      emissionSchedule: emissionSchedule,
+      rewardTokens: rewardTokens,
+      vaultAuthority: vaultAuthority,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      referralAccount: null,
+      referralVault: null,
+      loyaltyAccount: loyaltyAccount,
+      systemProgram: web3.SystemProgram.programId,
+    };
+
+    // Rewards cannot be paid out of the stake vault.
+    await assert.rejects(
+      program.methods
+        .claimRewards()
+        .accounts({ ...claimAccounts, rewardVault: vaultTokenAccount })
+        .rpc(),
+      (err: any) => err.error?.errorCode?.code === "InvalidRewardVault"
+    );
+
     await program.methods
-      .claimRewards(liquidityProvided)
-      .accounts({
-        staker: staker.publicKey,
-        stakeInfo: stakeInfoPda,
-        stakerTokenAccount: stakerTokenAccount,
-        rewardVault: vaultTokenAccount,
-        vaultAuthority: vaultAuthority,
-        tokenProgram: TOKEN_PROGRAM_ID,
-      })
+      .claimRewards()
+      .accounts(claimAccounts)
       .rpc();
 
     // Fetch and log the stake_info account after rewards are compounded.
@@ -174,22 +410,35 @@ describe("sst tests", () => {
   });
 
   it("Create a governance proposal", async () => {
-    // Derive the PDA for the proposal using seeds ["proposal", proposer, proposer].
+    // The proposer record counts proposals; the first one uses index 0.
+    const [proposerRecordPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("proposer"), staker.publicKey.toBuffer()],
+      program.programId
+    );
+    // Derive the PDA for the proposal using seeds ["proposal", proposer, index].
     const [proposalPda] = await web3.PublicKey.findProgramAddress(
       [
         Buffer.from("proposal"),
         staker.publicKey.toBuffer(),
-        staker.publicKey.toBuffer(),
+        new BN(0).toArrayLike(Buffer, "le", 8),
       ],
       program.programId
     );
 
+    // Only the hash of the proposal text is stored on-chain.
     const description = "Proposal for fee distribution changes";
+    const descriptionHash = Array.from(createHash("sha256").update(description).digest());
     await program.methods
-      .createProposal(description)
+      .createProposal(descriptionHash, "")
       .accounts({
         proposer: staker.publicKey,
+        proposerRecord: proposerRecordPda,
         proposal: proposalPda,
+        governanceConfig: governanceConfig,
+        proposerStakeInfo: null,
+        proposerTokenAccount: null,
+        bondVault: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: web3.SystemProgram.programId,
       })
       .rpc();
@@ -198,6 +447,301 @@ describe("sst tests", () => {
     const proposalAccount = await program.account.proposal.fetch(proposalPda);
     console.log("Proposal account:", proposalAccount);
     assert.ok(proposalAccount.proposer.equals(staker.publicKey));
-    assert.equal(proposalAccount.description, description);
+    assert.deepEqual(proposalAccount.descriptionHash, descriptionHash);
+    assert.ok(proposalAccount.status.active);
+  });
+
+  it("Snapshot protocol stats for the current epoch", async () => {
+    const { epoch } = await provider.connection.getEpochInfo();
+    const epochBn = new BN(epoch);
+    const [snapshotPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stats_snapshot"), epochBn.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .snapshotProtocolStats(epochBn)
+      .accounts({
+        payer: staker.publicKey,
+        protocolStats: protocolStats,
+        snapshot: snapshotPda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const statsAccount = await program.account.protocolStats.fetch(protocolStats);
+    const snapshotAccount = await program.account.statsSnapshot.fetch(snapshotPda);
+    assert.ok(new BN(snapshotAccount.epoch).eq(epochBn));
+    assert.ok(new BN(snapshotAccount.totalStaked).eq(new BN(statsAccount.totalStaked)));
+  });
+
+  it("Flash loan must be repaid in the same transaction", async () => {
+    const [stakeInfoPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stake"), staker.publicKey.toBuffer()],
+      program.programId
+    );
+    const [flashLoanAllowlist] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("flash_loan_allowlist")],
+      program.programId
+    );
+    // No allowlist has been created yet, so none applies.
+    const flashLoanAccounts = {
+      staker: staker.publicKey,
+      stakeInfo: stakeInfoPda,
+      vaultTokenAccount: vaultTokenAccount,
+      borrowerTokenAccount: stakerTokenAccount,
+      config: config,
+      vaultAuthority: vaultAuthority,
+      instructions: web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      flashLoanAllowlist: flashLoanAllowlist,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    // Without a repayment later in the transaction the loan is refused.
+    await assert.rejects(
+      program.methods.flashLoan(new BN(100)).accounts(flashLoanAccounts).rpc(),
+      (err: any) => err.error?.errorCode?.code === "FlashLoanNotRepaid"
+    );
+
+    // With the repayment appended the loan goes through and is settled by the end of the transaction.
+    const vaultBefore = await provider.connection.getTokenAccountBalance(vaultTokenAccount);
+    const repayIx = await program.methods
+      .repayFlashLoan()
+      .accounts({
+        staker: staker.publicKey,
+        stakeInfo: stakeInfoPda,
+        vaultTokenAccount: vaultTokenAccount,
+        borrowerTokenAccount: stakerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: config,
+      })
+      .instruction();
+    await program.methods
+      .flashLoan(new BN(100))
+      .accounts(flashLoanAccounts)
+      .postInstructions([repayIx])
+      .rpc();
+
+    const stakeInfo = await program.account.stakeInfo.fetch(stakeInfoPda);
+    assert.ok(new BN(stakeInfo.flashLoanOutstanding).isZero());
+    const vaultAfter = await provider.connection.getTokenAccountBalance(vaultTokenAccount);
+    assert.equal(vaultAfter.value.amount, vaultBefore.value.amount);
+
+    // An enforced allowlist still admits a loan repaid directly by this program.
+    await program.methods
+      .setFlashLoanAllowlist([], true)
+      .accounts({
+        authority: staker.publicKey,
+        governanceConfig: governanceConfig,
+        flashLoanAllowlist: flashLoanAllowlist,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .flashLoan(new BN(50))
+      .accounts(flashLoanAccounts)
+      .postInstructions([repayIx])
+      .rpc();
+  });
+
+  it("Liquidation is refused for a healthy position", async () => {
+    const [stakeInfoPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stake"), staker.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // The stake vault cannot double as the lending pool.
+    await assert.rejects(
+      program.methods
+        .updateConfig({ borrowVault: vaultTokenAccount })
+        .accounts({ authority: staker.publicKey, config: config, borrowVault: vaultTokenAccount })
+        .rpc(),
+      (err: any) => err.error?.errorCode?.code === "InvalidBorrowVault"
+    );
+
+    // Register a dedicated SST borrow vault owned by the vault authority.
+    const borrowVault = await createAccount(
+      provider.connection,
+      provider.wallet.payer,
+      mint,
+      vaultAuthority,
+      web3.Keypair.generate()
+    );
+    await program.methods
+      .updateConfig({ borrowVault })
+      .accounts({ authority: staker.publicKey, config: config, borrowVault })
+      .rpc();
+
+    const stakeBefore = await program.account.stakeInfo.fetch(stakeInfoPda);
+    await assert.rejects(
+      program.methods
+        .liquidate(new BN(10))
+        .accounts({
+          liquidator: staker.publicKey,
+          stakeInfo: stakeInfoPda,
+          config: config,
+          protocolStats: protocolStats,
+          emissionSchedule: emissionSchedule,
+          rewardTokens: rewardTokens,
+          borrowVault,
+          liquidatorTokenAccount: stakerTokenAccount,
+          vaultTokenAccount: vaultTokenAccount,
+          liquidatorCollateralAccount: stakerTokenAccount,
+          vaultAuthority: vaultAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          usdcMarket: null,
+          priceFeed: null,
+        })
+        .rpc(),
+      (err: any) => err.error?.errorCode?.code === "PositionHealthy"
+    );
+    const stakeAfter = await program.account.stakeInfo.fetch(stakeInfoPda);
+    assert.ok(new BN(stakeAfter.amount).eq(new BN(stakeBefore.amount)));
+  });
+
+  it("Proposal bond is held in a dedicated vault and refunded on cancel", async () => {
+    // Protocol vaults cannot hold bonds.
+    await assert.rejects(
+      program.methods
+        .setProposalBond(new BN(100))
+        .accounts({
+          authority: staker.publicKey,
+          governanceConfig: governanceConfig,
+          bondVault: vaultTokenAccount,
+          config: config,
+        })
+        .rpc(),
+      (err: any) => err.error?.errorCode?.code === "InvalidBondVault"
+    );
+
+    const bondVault = await createAccount(
+      provider.connection,
+      provider.wallet.payer,
+      mint,
+      vaultAuthority,
+      web3.Keypair.generate()
+    );
+    await program.methods
+      .setProposalBond(new BN(100))
+      .accounts({
+        authority: staker.publicKey,
+        governanceConfig: governanceConfig,
+        bondVault,
+        config: config,
+      })
+      .rpc();
+
+    // The staker's second proposal uses index 1.
+    const [proposerRecordPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("proposer"), staker.publicKey.toBuffer()],
+      program.programId
+    );
+    const [proposalPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("proposal"),
+        staker.publicKey.toBuffer(),
+        new BN(1).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const descriptionHash = Array.from(createHash("sha256").update("Bonded proposal").digest());
+    await program.methods
+      .createProposal(descriptionHash, "")
+      .accounts({
+        proposer: staker.publicKey,
+        proposerRecord: proposerRecordPda,
+        proposal: proposalPda,
+        governanceConfig: governanceConfig,
+        proposerStakeInfo: null,
+        proposerTokenAccount: stakerTokenAccount,
+        bondVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const proposalAccount = await program.account.proposal.fetch(proposalPda);
+    assert.ok(new BN(proposalAccount.bond).eq(new BN(100)));
+    let bondBalance = await provider.connection.getTokenAccountBalance(bondVault);
+    assert.equal(bondBalance.value.amount, "100");
+
+    // Cancelling while voting is open returns the bond and closes the proposal.
+    await program.methods
+      .cancelProposal()
+      .accounts({
+        authority: staker.publicKey,
+        proposal: proposalPda,
+        proposer: staker.publicKey,
+        governanceConfig: governanceConfig,
+        protocolStats: protocolStats,
+        bondVault,
+        proposerTokenAccount: stakerTokenAccount,
+        config: config,
+        vaultAuthority: vaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    bondBalance = await provider.connection.getTokenAccountBalance(bondVault);
+    assert.equal(bondBalance.value.amount, "0");
+    assert.equal(await provider.connection.getAccountInfo(proposalPda), null);
+  });
+
+  it("Council veto is limited to members and passed proposals", async () => {
+    const [securityCouncil] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("security_council")],
+      program.programId
+    );
+    const [proposalPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("proposal"),
+        staker.publicKey.toBuffer(),
+        new BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const [councilVeto] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("council_veto"), proposalPda.toBuffer()],
+      program.programId
+    );
+    const vetoAccounts = {
+      member: staker.publicKey,
+      securityCouncil: securityCouncil,
+      proposal: proposalPda,
+      councilVeto: councilVeto,
+      systemProgram: web3.SystemProgram.programId,
+    };
+
+    // A key outside the council cannot approve a veto.
+    await program.methods
+      .setSecurityCouncil([web3.Keypair.generate().publicKey], 1)
+      .accounts({
+        authority: staker.publicKey,
+        governanceConfig: governanceConfig,
+        securityCouncil: securityCouncil,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    await assert.rejects(
+      program.methods.councilVeto().accounts(vetoAccounts).rpc(),
+      (err: any) => err.error?.errorCode?.code === "Unauthorized"
+    );
+
+    // A member cannot veto a proposal that is still being voted on.
+    await program.methods
+      .setSecurityCouncil([staker.publicKey], 1)
+      .accounts({
+        authority: staker.publicKey,
+        governanceConfig: governanceConfig,
+        securityCouncil: securityCouncil,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    await assert.rejects(
+      program.methods.councilVeto().accounts(vetoAccounts).rpc(),
+      (err: any) => err.error?.errorCode?.code === "ProposalNotPassed"
+    );
+    const proposalAccount = await program.account.proposal.fetch(proposalPda);
+    assert.ok(proposalAccount.status.active);
   });
 });